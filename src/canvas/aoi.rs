@@ -0,0 +1,143 @@
+// Area of interest drawn over the heatmap, converts what the user drew on the canvas into
+//  lon/lat and keeps the buffers used to draw its outline
+
+use geo::{Coord, LineString, MultiPolygon, Polygon, Rect};
+use wgpu::util::DeviceExt;
+use winit::dpi::PhysicalPosition;
+
+use super::camera::Camera;
+use super::geometry::{BlendVertex, BufferLayer};
+use super::input::{AoiInput, InputState};
+use super::render_context::RenderContext;
+
+/// Convert an area of interest drawn on the canvas into lon/lat, None clears it
+pub fn drawn_aoi(input: &AoiInput, camera: &Camera) -> Option<MultiPolygon> {
+    let polygon = match input {
+        AoiInput::BoundingBox(start, end) => Rect::new(
+            screen_to_lon_lat(*start, camera),
+            screen_to_lon_lat(*end, camera),
+        )
+        .to_polygon(),
+        AoiInput::Polygon(vertices) => Polygon::new(
+            LineString::from(
+                vertices
+                    .iter()
+                    .map(|x| screen_to_lon_lat(*x, camera))
+                    .collect::<Vec<_>>(),
+            ),
+            vec![],
+        ),
+        AoiInput::Clear => return None,
+    };
+    Some(MultiPolygon::new(vec![polygon]))
+}
+
+fn screen_to_lon_lat(position: PhysicalPosition<f64>, camera: &Camera) -> Coord {
+    let world = camera.screen_to_world((position.x, position.y).into());
+    Coord {
+        x: world.x.clamp(-180.0, 180.0),
+        y: world.y.clamp(-90.0, 90.0),
+    }
+}
+
+/// Outline of the area of interest, rebuilt only when the outline changes
+#[derive(Default)]
+pub struct AoiOverlay {
+    vertices: Vec<BlendVertex>,
+    indices: Vec<u32>,
+    pub layer: Option<BufferLayer>,
+}
+
+impl AoiOverlay {
+    /// Outline the area of interest being drawn if there is one, otherwise every ring of the one
+    ///     in the filter
+    pub fn update(
+        &mut self,
+        render_context: &RenderContext,
+        camera: &Camera,
+        input: &InputState,
+        aoi: Option<&MultiPolygon>,
+    ) {
+        let rings: Vec<Vec<Coord>> = if let Some(start) = input.aoi_drag_start {
+            vec![
+                Rect::new(
+                    screen_to_lon_lat(start, camera),
+                    screen_to_lon_lat(input.cursor_position, camera),
+                )
+                .to_polygon()
+                .exterior()
+                .0
+                .clone(),
+            ]
+        } else if !input.aoi_vertices.is_empty() {
+            vec![
+                input
+                    .aoi_vertices
+                    .iter()
+                    .chain(std::iter::once(&input.cursor_position))
+                    .map(|x| screen_to_lon_lat(*x, camera))
+                    .collect(),
+            ]
+        } else {
+            aoi.into_iter()
+                .flatten()
+                .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+                .map(|ring| ring.0.clone())
+                .collect()
+        };
+
+        // Each ring is a closed loop of line segments joining its vertices
+        let mut vertices: Vec<BlendVertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        for ring in rings.iter().filter(|ring| ring.len() >= 2) {
+            let start = vertices.len() as u32;
+            let count = ring.len() as u32;
+            indices.extend((0..count).flat_map(|i| [start + i, start + (i + 1) % count]));
+            vertices.extend(ring.iter().map(|coord| BlendVertex {
+                position: [coord.x as f32, coord.y as f32, 0.0],
+                weight: 0,
+            }));
+        }
+
+        if bytemuck::cast_slice::<BlendVertex, u8>(&vertices)
+            == bytemuck::cast_slice(&self.vertices)
+            && indices == self.indices
+        {
+            return;
+        }
+        self.layer = (!indices.is_empty())
+            .then(|| generate_outline_layer(render_context, &vertices, &indices));
+        self.vertices = vertices;
+        self.indices = indices;
+    }
+}
+
+fn generate_outline_layer(
+    render_context: &RenderContext,
+    vertices: &[BlendVertex],
+    indices: &[u32],
+) -> BufferLayer {
+    let vertex_buffer =
+        render_context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("AOI Vertex Buffer"),
+                contents: bytemuck::cast_slice(vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+    let index_buffer =
+        render_context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("AOI Index Buffer"),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+    BufferLayer {
+        vertex_buffer,
+        index_buffer,
+        num_indices: indices.len() as u32,
+    }
+}
@@ -4,11 +4,12 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
 
+use js_sys::{Array, Uint8Array};
 use leptos::logging::log;
 use leptos::prelude::{GetUntracked as _, Set as _};
 use wasm_bindgen::JsCast as _;
-use web_sys::HtmlAnchorElement;
-use web_sys::HtmlCanvasElement;
+use wasm_bindgen::{JsValue, closure::Closure};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, HtmlCanvasElement, Url};
 use winit::platform::web::WindowExtWebSys as _;
 use winit::{
     application::ApplicationHandler,
@@ -18,13 +19,20 @@ use winit::{
     window::{Window, WindowId},
 };
 
+use super::aoi::AoiOverlay;
 use super::geometry::{Geometry, generate_copy_buffer};
-use super::png::InitStage;
+use super::hover::{HoverReadback, read_hover_weight};
+use super::png::{ExportLimits, InitStage};
 use super::render_context::{MaxWeightState, RenderContext};
 use super::state::State;
 use super::texture::generate_copy_texture;
+use crate::canvas::geotiff::generate_geotiff;
 use crate::canvas::png::generate_heatmap_image;
+use crate::canvas::svg::generate_svg;
+use crate::ingest::error::IngestError;
 use crate::ingest::load::BufferStorage;
+use crate::types::{GranuleFormat, HoverInfo, ImageFormat};
+use crate::ui::popup::escape_html;
 
 /// Winit app that creates a window to be drawn to with wgpu
 pub struct App<'a> {
@@ -69,20 +77,44 @@ impl ApplicationHandler<UserMessage<'static>> for App<'_> {
                 // Fill out the rest of the state class with the contents of StateMessage
                 log!("Assign state values in application handler...");
 
+                // The UI only offers export widths the device can render
+                let export_limits = ExportLimits::from(&render_context.limits);
+                log!("Export limits: {export_limits:?}");
+                self.external_state
+                    .borrow()
+                    .set_export_width_cap
+                    .set(export_limits.max_width());
+
                 self.state = State {
                     render_context: Some(*render_context),
                     window: self.state.window.clone(),
                     init_stage: InitStage::Complete,
                     geometry: None,
+                    outline_mesh: None,
                     input: self.state.input.clone(),
                     event_loop_proxy: Some(self.event_loop_proxy.clone()),
                     filter: self.state.filter,
+                    colormap: self.state.colormap,
                     camera_storage: None,
-                    size_storage: None,
+                    target_storage: None,
                     export_context: self.state.export_context.clone(),
                     set_max_weight: self.state.set_max_weight,
+                    set_aoi: self.state.set_aoi,
+                    aoi_overlay: AoiOverlay::default(),
+                    set_inspect_point: self.state.set_inspect_point,
+                    hover: HoverReadback::default(),
+                    set_hover: self.state.set_hover,
+                    set_granule_export: self.state.set_granule_export,
+                    lod_config: self.state.lod_config.clone(),
                 };
 
+                // A colormap picked while the GPU was being set up is applied now
+                if let Some(colormap) = self.state.colormap
+                    && let Some(render_context) = self.state.render_context.as_mut()
+                {
+                    render_context.set_colormap(colormap.get_untracked().image);
+                }
+
                 // Resize configures the surface based on current canvas size
                 self.state.resize(
                     self.state
@@ -112,6 +144,13 @@ impl ApplicationHandler<UserMessage<'static>> for App<'_> {
                     &data,
                     &outline_data,
                 ));
+                // Levels of detail run from the most detailed
+                self.state.outline_mesh = outline_data.into_iter().next();
+
+                // An export rendering off-screen is abandoned, the targets on screen are restored
+                if let Some(targets) = self.state.target_storage.take() {
+                    render_context.swap_targets(targets);
+                }
 
                 render_context.copy_context.texture =
                     generate_copy_texture(&render_context.device, render_context.size);
@@ -124,10 +163,14 @@ impl ApplicationHandler<UserMessage<'static>> for App<'_> {
                     },
                 );
 
+                // The new buffer is not mapped, a hover readback of the old one is dropped
+                render_context.copy_context.buffer_mapped = false;
+                self.state.hover.reset();
+
                 render_context.max_weight_context.state = MaxWeightState::Empty;
                 if let Some(export) = self.state.export_context.as_mut() {
                     export.stage = InitStage::Incomplete;
-                    export.base64_png = None;
+                    export.image = None;
                 }
 
                 log!("Done Generating Buffers");
@@ -215,60 +258,163 @@ impl ApplicationHandler<UserMessage<'static>> for App<'_> {
                 log!("Updated max_weight signal with {max}");
             }
 
+            // The weight under the cursor has been copied to the buffer, show it in the tooltip
+            UserMessage::HoverMapped => {
+                let Some((cursor, lon_lat)) = self.state.hover.finish() else {
+                    return;
+                };
+                let render_context = self
+                    .state
+                    .render_context
+                    .as_mut()
+                    .expect("Failed to get render context in UserMessage::HoverMapped");
+                let weight = read_hover_weight(render_context);
+
+                if !self.state.hover.cursor_over_canvas {
+                    return;
+                }
+
+                // The tooltip is positioned in CSS pixels rather than physical pixels
+                let scale_factor = self
+                    .state
+                    .window
+                    .as_ref()
+                    .map_or(1.0, |window| window.scale_factor());
+                if let Some(set_hover) = self.state.set_hover {
+                    set_hover.set(Some(HoverInfo {
+                        lon: lon_lat.x,
+                        lat: lon_lat.y,
+                        weight,
+                        x: cursor.x / scale_factor,
+                        y: cursor.y / scale_factor,
+                    }));
+                }
+            }
+
+            // The granules in view were asked for, the viewport is handed back to the data loader
+            //    which lists them, the camera the user sees is used while an export has swapped it
+            UserMessage::ExportGranules(format) => {
+                let Some(render_context) = self.state.render_context.as_ref() else {
+                    return;
+                };
+                let camera = self
+                    .state
+                    .camera_storage
+                    .as_ref()
+                    .unwrap_or(&render_context.camera_context.camera);
+                if let Some(set_granule_export) = self.state.set_granule_export {
+                    set_granule_export.set(Some((format, camera.world_bounds())));
+                }
+            }
+
+            // The user picked another colormap, the heatmap is drawn with it and an image exported
+            //    with the last one is not downloaded again
+            UserMessage::ColormapChanged => {
+                let Some(render_context) = self.state.render_context.as_mut() else {
+                    return;
+                };
+                if let Some(colormap) = self.state.colormap {
+                    render_context.set_colormap(colormap.get_untracked().image);
+                }
+                if let Some(export) = self.state.export_context.as_mut()
+                    && export.stage == InitStage::Complete
+                {
+                    export.stage = InitStage::Incomplete;
+                    export.image = None;
+                }
+            }
+
+            // Loading data failed, let the user know why and turn off the loading wheel so the app does not hang
+            UserMessage::IngestFailed(err) => {
+                log!("Ingest failed: {err}");
+                let external_state = self.external_state.borrow();
+                external_state.set_title.set(err.title().to_string());
+                external_state.set_body.set(escape_html(&err.to_string()));
+                external_state.set_ready.set(true);
+            }
+
             // This handles copying data to CPU when the buffer is mapped during the export render pass
             UserMessage::ExportMapped => {
-                // Default to an empty data set if we have not generated png and fail to get render_context
-                let mut base64_encoded_png: String = String::new();
+                // New data arrived while the image was read back, its buffer is gone
+                let Some(export) = self.state.export_context.as_ref() else {
+                    return;
+                };
+                if export.stage != InitStage::Complete {
+                    return;
+                }
+                let job = export
+                    .job
+                    .clone()
+                    .expect("Failed to get the job the image was rendered with");
+
+                // Generate the image unless it was already generated for this data
+                if export.image.is_none()
+                    && let Some(render_context) = self.state.render_context.as_mut()
+                {
+                    let image = match job.format {
+                        ImageFormat::Png => {
+                            // Grab the current filter, this is needed to generate the text on the output img
+                            let filter = self
+                                .state
+                                .filter
+                                .expect("Failed to get filter while generating png")
+                                .get_untracked();
+                            Ok(generate_heatmap_image(
+                                render_context,
+                                &filter,
+                                &job.options,
+                            ))
+                        }
+                        ImageFormat::GeoTiff => Ok(generate_geotiff(render_context, job.frame)),
+                        ImageFormat::Svg => {
+                            let filter = self
+                                .state
+                                .filter
+                                .expect("Failed to get filter while generating svg")
+                                .get_untracked();
+                            generate_svg(
+                                render_context,
+                                &job,
+                                &filter,
+                                self.state.outline_mesh.as_ref(),
+                            )
+                        }
+                    };
+
+                    match image {
+                        // Save the image we generated so we dont need to regenerate for the same data
+                        Ok(image) => {
+                            self.state
+                                .export_context
+                                .as_mut()
+                                .expect("Failed to get export context")
+                                .image = Some(image);
+                        }
+                        Err(err) => {
+                            let external_state = self.external_state.borrow();
+                            external_state.set_title.set("Export Too Large".to_string());
+                            external_state.set_body.set(escape_html(&err));
+                        }
+                    }
+                }
 
-                // If we have generated the png for this data before use the stored base64_encoding
-                if let Some(base64_png) = &self
+                if let Some(image) = self
                     .state
                     .export_context
                     .as_ref()
-                    .expect("Failed to get export_context")
-                    .base64_png
+                    .and_then(|x| x.image.as_ref())
                 {
-                    base64_encoded_png.clone_from(base64_png);
-                } else if let Some(render_context) = self.state.render_context.as_mut() {
-                    // Grab the current filter, this is needed to generate the text on the output img
-                    let filter = self
-                        .state
-                        .filter
-                        .expect("Failed to get filter while generating png")
-                        .get_untracked();
-
-                    // We have not generated a png yet, do so
-                    base64_encoded_png = generate_heatmap_image(render_context, &filter);
-
-                    // Save the image we generated so we dont need to regenerate for the same data
-                    self.state
-                        .export_context
-                        .as_mut()
-                        .expect("Failed to get export context")
-                        .base64_png = Some(base64_encoded_png.clone());
+                    download_bytes(image, job.format.mime_type(), job.format.file_name());
+                    log!("{} downloaded", job.format.file_name());
                 }
 
-                log!("PNG Bytes: {base64_encoded_png:X?}");
-
-                // We dynamically generate this anchor element to download the generated png, it is removed after it goes out of scope
+                // The image was rendered off-screen, go back to rendering the heatmap on screen
+                if let Some(targets) = self.state.target_storage.take()
+                    && let Some(render_context) = self.state.render_context.as_mut()
                 {
-                    let image_url = urlencoding::encode(&base64_encoded_png).to_string();
-                    let anchor: HtmlAnchorElement = web_sys::window()
-                        .expect("ERROR: Failed to get web_sys window")
-                        .document()
-                        .expect("ERROR: Failed to get document")
-                        .create_element("a")
-                        .expect("ERROR: Failed to create <a> element")
-                        .dyn_into()
-                        .expect("ERROR: Faile to convert to HtmlAnchorElement");
-
-                    anchor.set_href(&("data:image/png;base64,".to_string() + &image_url));
-                    anchor.set_download("heatmap.png");
-
-                    anchor.click();
+                    render_context.swap_targets(targets);
+                    self.state.hover.reset();
                 }
-
-                log!(".png downloaded");
             }
         }
     }
@@ -358,16 +504,90 @@ impl ApplicationHandler<UserMessage<'static>> for App<'_> {
         }
     }
 }
+/// Download a file from a data or object url, we dynamically generate an anchor element to do
+///     so, it is removed after it goes out of scope
+pub fn download(url: &str, file_name: &str) {
+    let anchor: HtmlAnchorElement = web_sys::window()
+        .expect("ERROR: Failed to get web_sys window")
+        .document()
+        .expect("ERROR: Failed to get document")
+        .create_element("a")
+        .expect("ERROR: Failed to create <a> element")
+        .dyn_into()
+        .expect("ERROR: Faile to convert to HtmlAnchorElement");
+
+    anchor.set_href(url);
+    anchor.set_download(file_name);
+
+    anchor.click();
+}
+
+/// Download text as a file, it is passed to the anchor as an object url rather than a data url so
+///     large files are not limited by the length of a url
+pub fn download_text(contents: &str, mime_type: &str, file_name: &str) {
+    let options = BlobPropertyBag::new();
+    options.set_type(&format!("{mime_type};charset=utf-8"));
+    download_blob(
+        Blob::new_with_str_sequence_and_options(
+            &Array::of1(&JsValue::from_str(contents)),
+            &options,
+        ),
+        file_name,
+    );
+}
+
+/// Download bytes as a file through an object url like `download_text`
+pub fn download_bytes(contents: &[u8], mime_type: &str, file_name: &str) {
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    download_blob(
+        Blob::new_with_u8_array_sequence_and_options(
+            &Array::of1(&Uint8Array::from(contents)),
+            &options,
+        ),
+        file_name,
+    );
+}
+
+// Helper Function:
+//     Downloads a blob from an object url, which is revoked once the download has started
+fn download_blob(blob: Result<Blob, JsValue>, file_name: &str) {
+    let url = match blob.and_then(|blob| Url::create_object_url_with_blob(&blob)) {
+        Ok(url) => url,
+        Err(err) => {
+            log!("Failed to create object url for {file_name}: {err:?}");
+            return;
+        }
+    };
+
+    download(&url, file_name);
+
+    // The browser reads the blob after the click returns, revoke it on a later task
+    let revoke = Closure::once_into_js(move || {
+        let _ = Url::revoke_object_url(&url);
+    });
+    let _ = web_sys::window()
+        .expect("ERROR: Failed to get web_sys window")
+        .set_timeout_with_callback(revoke.unchecked_ref());
+}
+
 /// All user events that can be sent to the event loop
 pub enum UserMessage<'a> {
     StateMessage(Box<RenderContext<'a>>),
     IncomingData(Vec<BufferStorage>, Vec<BufferStorage>),
     MaxWeightMapped,
     ExportMapped,
+    HoverMapped,
+    ExportGranules(GranuleFormat),
+    ColormapChanged,
+    IngestFailed(IngestError),
 }
 
 /// Stores the canvas as an html element
 pub struct ExternalState {
     pub canvas: Option<HtmlCanvasElement>,
     pub set_ready: leptos::prelude::WriteSignal<bool>,
+    pub set_title: leptos::prelude::WriteSignal<String>,
+    pub set_body: leptos::prelude::WriteSignal<String>,
+    pub set_export_width_cap: leptos::prelude::WriteSignal<u32>,
 }
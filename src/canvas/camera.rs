@@ -10,6 +10,7 @@ pub enum CameraEvent {
     AspectRatio(f64),
     Zoom(f64, cgmath::Vector2<f64>),
     EntireView,
+    Frame(geo::Rect),
 }
 
 // This is the camera that modifies the viewport that the renderpasses render
@@ -179,13 +180,21 @@ impl CameraContext {
                 self.camera.position += pos;
             }
 
-            // Displays the entire heatmap, used to calculate max weight and export to png
+            // Displays the entire heatmap, used to calculate max weight
             CameraEvent::EntireView => {
-                self.camera.position = Vector2::new(-180.0, 90.0);
-
-                self.camera.zoom = 5.0;
+                self.update_camera(&CameraEvent::Frame(geo::Rect::new(
+                    geo::coord! { x: -180.0, y: -90.0 },
+                    geo::coord! { x: 180.0, y: 90.0 },
+                )));
+            }
 
-                self.update_camera(&CameraEvent::Resize(1800, 900));
+            // Stretches a region of the world over the whole render target, the target should
+            //     have the aspect ratio of the region unless it is only being read back, ie. the max weight
+            CameraEvent::Frame(rect) => {
+                self.camera.position = Vector2::new(rect.min().x, rect.max().y);
+                self.camera.zoom = self.camera.width / rect.width();
+                self.camera.height = rect.height() * self.camera.zoom;
+                self.camera.aspect = self.camera.width / self.camera.height;
 
                 self.rebuild_view_matrix();
             }
@@ -216,6 +225,24 @@ pub struct Camera {
 }
 
 impl Camera {
+    // Converts a position on the canvas in physical pixels into world coordinates, ie. lon/lat
+    pub fn screen_to_world(&self, position: cgmath::Vector2<f64>) -> cgmath::Vector2<f64> {
+        cgmath::Vector2::new(
+            self.position.x + position.x / self.zoom,
+            self.position.y - position.y / self.zoom,
+        )
+    }
+
+    // Longitudes and latitudes visible on the canvas, clamped to the globe
+    pub fn world_bounds(&self) -> geo::Rect {
+        let min = self.screen_to_world((0.0, self.height).into());
+        let max = self.screen_to_world((self.width, 0.0).into());
+        geo::Rect::new(
+            geo::coord! { x: min.x.clamp(-180.0, 180.0), y: min.y.clamp(-90.0, 90.0) },
+            geo::coord! { x: max.x.clamp(-180.0, 180.0), y: max.y.clamp(-90.0, 90.0) },
+        )
+    }
+
     // This is the cool matrix math that makes this whole thing actually work!
     pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f64> {
         let view = cgmath::Matrix4::from_scale(self.zoom)
@@ -134,7 +134,7 @@ pub fn generate_copy_buffer(
     device: &wgpu::Device,
     size: winit::dpi::PhysicalSize<u32>,
 ) -> wgpu::Buffer {
-    let temp_contents = vec![0u8; (4 * 4 * size.width * size.height) as usize];
+    let temp_contents = vec![0u8; 4 * 4 * size.width as usize * size.height as usize];
 
     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Max Weight Buffer"),
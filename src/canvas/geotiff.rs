@@ -0,0 +1,138 @@
+// Writes the blended weights of an export as a single band GeoTIFF so the number of acquisitions
+//     at each pixel can be read by GIS software, ie. QGIS
+
+use std::io::Cursor;
+
+use geo::Rect;
+use tiff::encoder::{TiffEncoder, colortype};
+use tiff::tags::Tag;
+use winit::dpi::PhysicalSize;
+
+use super::render_context::RenderContext;
+
+// GeoTIFF tags, http://docs.opengeospatial.org/is/19-008r4/19-008r4.html
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const GEO_KEY_DIRECTORY: u16 = 34735;
+// Read by GDAL as the value of pixels with no data
+const GDAL_NODATA: u16 = 42113;
+
+// Geo keys describing a raster in longitude and latitude on WGS 84, each key is its id, where its
+//     value is stored, 0 for inline, the number of values and the value
+const GEO_KEYS: [u16; 16] = [
+    // Version 1.1.0 with 3 keys
+    1, 1, 0, 3, //
+    // GTModelTypeGeoKey, geographic
+    1024, 0, 1, 2, //
+    // GTRasterTypeGeoKey, pixels are areas
+    1025, 0, 1, 1, //
+    // GeographicTypeGeoKey, EPSG:4326
+    2048, 0, 1, 4326,
+];
+
+// Size in bytes of a single Rgba32Float texel
+const TEXEL_SIZE: usize = 4 * 4;
+
+/// Weights read back for an export along with the region of the world they were rendered over
+pub struct WeightGrid {
+    pub weights: Vec<f32>,
+    pub size: PhysicalSize<u32>,
+    pub frame: Rect,
+}
+
+/// Read the weights the export pass copied to the buffer and unmap it
+pub fn read_weight_grid(render_context: &mut RenderContext, frame: Rect) -> WeightGrid {
+    let weights = {
+        let bytes = render_context
+            .copy_context
+            .buffer
+            .slice(..)
+            .get_mapped_range();
+        read_weights(
+            &bytes,
+            render_context.copy_context.texture.width(),
+            render_context.size,
+        )
+    };
+
+    // Release the copy buffer for later use
+    render_context.copy_context.buffer.unmap();
+    render_context.copy_context.buffer_mapped = false;
+
+    WeightGrid {
+        weights,
+        size: render_context.size,
+        frame,
+    }
+}
+
+/// Read the weights the export pass copied to the buffer and encode them as a GeoTIFF covering
+///     the exported frame
+pub fn generate_geotiff(render_context: &mut RenderContext, frame: Rect) -> Vec<u8> {
+    let grid = read_weight_grid(render_context, frame);
+    encode_geotiff(&grid)
+}
+
+/// Red channel of each texel of the copy buffer, which holds the weight, cropped to the size of
+///     the export as rows of the buffer are padded to the width of the copy texture
+pub fn read_weights(bytes: &[u8], padded_width: u32, size: PhysicalSize<u32>) -> Vec<f32> {
+    let row_size = padded_width as usize * TEXEL_SIZE;
+    bytes
+        .chunks_exact(row_size)
+        .take(size.height as usize)
+        .flat_map(|row| {
+            row.chunks_exact(TEXEL_SIZE)
+                .take(size.width as usize)
+                .map(|texel| f32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]))
+        })
+        .collect()
+}
+
+/// Encode weights as a Float32 GeoTIFF in EPSG:4326 stretched over the frame, pixels without
+///     acquisitions are nodata. Weights are blended in a half float texture so counts above 2048
+///     are rounded
+pub fn encode_geotiff(grid: &WeightGrid) -> Vec<u8> {
+    let WeightGrid {
+        weights,
+        size,
+        frame,
+    } = grid;
+    let mut tiff_bytes = Cursor::new(Vec::<u8>::new());
+    {
+        let mut encoder =
+            TiffEncoder::new(&mut tiff_bytes).expect("ERROR: Failed to create tiff encoder");
+        let mut image = encoder
+            .new_image::<colortype::Gray32Float>(size.width, size.height)
+            .expect("ERROR: Failed to create tiff image");
+
+        // The top left corner of the first pixel is tied to the top left corner of the frame
+        let directory = image.encoder();
+        directory
+            .write_tag(
+                Tag::Unknown(MODEL_PIXEL_SCALE),
+                &[
+                    frame.width() / f64::from(size.width),
+                    frame.height() / f64::from(size.height),
+                    0.0,
+                ][..],
+            )
+            .expect("ERROR: Failed to write tiff pixel scale");
+        directory
+            .write_tag(
+                Tag::Unknown(MODEL_TIEPOINT),
+                &[0.0, 0.0, 0.0, frame.min().x, frame.max().y, 0.0][..],
+            )
+            .expect("ERROR: Failed to write tiff tiepoint");
+        directory
+            .write_tag(Tag::Unknown(GEO_KEY_DIRECTORY), &GEO_KEYS[..])
+            .expect("ERROR: Failed to write tiff geo keys");
+        directory
+            .write_tag(Tag::Unknown(GDAL_NODATA), "0")
+            .expect("ERROR: Failed to write tiff nodata");
+
+        image
+            .write_data(weights)
+            .expect("ERROR: Failed to write tiff data");
+    }
+    tiff_bytes.into_inner()
+}
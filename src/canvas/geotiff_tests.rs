@@ -0,0 +1,80 @@
+//! Tests of writing the weights of an export as a GeoTIFF
+use std::io::Cursor;
+
+use geo::{Rect, coord};
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+use wasm_bindgen_test::wasm_bindgen_test;
+use winit::dpi::PhysicalSize;
+
+use crate::canvas::geotiff::{WeightGrid, encode_geotiff, read_weights};
+
+#[wasm_bindgen_test]
+fn geotiff_weights_are_cropped() {
+    // A 2 by 2 export in a copy texture padded to 3 texels wide, only red holds the weight
+    let texels: [[f32; 4]; 6] = [
+        [1.0, 0.0, 0.0, 1.0],
+        [2.0, 0.0, 0.0, 1.0],
+        [9.0, 9.0, 9.0, 9.0],
+        [3.0, 0.0, 0.0, 1.0],
+        [0.0, 0.0, 0.0, 0.0],
+        [9.0, 9.0, 9.0, 9.0],
+    ];
+    let bytes: Vec<u8> = texels
+        .iter()
+        .flatten()
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+
+    assert_eq!(
+        read_weights(&bytes, 3, PhysicalSize::new(2, 2)),
+        vec![1.0, 2.0, 3.0, 0.0]
+    );
+}
+
+#[wasm_bindgen_test]
+fn geotiff_is_georeferenced() {
+    // 4 by 2 pixels over Alaska, each pixel is 2.5 by 2.5 degrees
+    let frame = Rect::new(coord! { x: -160.0, y: 60.0 }, coord! { x: -150.0, y: 65.0 });
+    let weights = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 2048.0];
+    let bytes = encode_geotiff(&WeightGrid {
+        weights: weights.to_vec(),
+        size: PhysicalSize::new(4, 2),
+        frame,
+    });
+
+    let mut decoder = Decoder::new(Cursor::new(bytes)).expect("Failed to decode GeoTIFF");
+    assert_eq!(decoder.dimensions().expect("No dimensions"), (4, 2));
+
+    assert_eq!(
+        decoder
+            .get_tag_f64_vec(Tag::Unknown(33550))
+            .expect("No pixel scale"),
+        vec![2.5, 2.5, 0.0]
+    );
+    assert_eq!(
+        decoder
+            .get_tag_f64_vec(Tag::Unknown(33922))
+            .expect("No tiepoint"),
+        vec![0.0, 0.0, 0.0, -160.0, 65.0, 0.0]
+    );
+
+    // Geographic, pixels are areas, EPSG:4326
+    let geo_keys = decoder
+        .get_tag_u16_vec(Tag::Unknown(34735))
+        .expect("No geo keys");
+    assert_eq!(&geo_keys[4..8], &[1024, 0, 1, 2]);
+    assert_eq!(&geo_keys[12..], &[2048, 0, 1, 4326]);
+
+    assert_eq!(
+        decoder
+            .get_tag_ascii_string(Tag::Unknown(42113))
+            .expect("No nodata"),
+        "0"
+    );
+
+    match decoder.read_image().expect("Failed to read GeoTIFF data") {
+        DecodingResult::F32(data) => assert_eq!(data, weights),
+        _ => panic!("Expected Float32 data"),
+    }
+}
@@ -0,0 +1,211 @@
+// Reads the weight under the cursor back from the blend texture for the hover tooltip, this uses
+//  the same copy texture and buffer as the max weight pass so it only runs while they are free
+
+use std::time::Duration;
+
+use geo::Coord;
+use web_time::Instant;
+use wgpu::{Extent3d, Origin3d};
+use winit::dpi::PhysicalPosition;
+use winit::event_loop::EventLoopProxy;
+
+use super::app::UserMessage;
+use super::camera::Camera;
+use super::geometry::Geometry;
+use super::render_context::RenderContext;
+
+// Readbacks are at least this far apart so moving the mouse does not stall rendering
+const READBACK_INTERVAL: Duration = Duration::from_millis(100);
+
+// Size in bytes of a single Rgba32Float texel
+const TEXEL_SIZE: u64 = 4 * 4;
+
+/// Tracks the readback in flight and when the last one was started
+#[derive(Default)]
+pub struct HoverReadback {
+    // Cursor position and lon/lat of the readback in flight
+    pending: Option<(PhysicalPosition<f64>, Coord)>,
+    // Cursor position and camera of the last readback, nothing is read while they are unchanged
+    last_view: Option<[f64; 5]>,
+    last_read: Option<Instant>,
+    // Nothing is read while the cursor is off of the canvas
+    pub cursor_over_canvas: bool,
+}
+
+impl HoverReadback {
+    /// True when the cursor or camera has moved since the last readback and it was long enough ago
+    pub fn is_due(&self, cursor: PhysicalPosition<f64>, camera: &Camera) -> bool {
+        self.cursor_over_canvas
+            && self.pending.is_none()
+            && self.last_view != Some(view_key(cursor, camera))
+            && self
+                .last_read
+                .is_none_or(|x| x.elapsed() >= READBACK_INTERVAL)
+    }
+
+    /// Finish the readback in flight, returns where the cursor was when it started
+    pub const fn finish(&mut self) -> Option<(PhysicalPosition<f64>, Coord)> {
+        self.pending.take()
+    }
+
+    /// Forget the readback in flight and read the pixel under the cursor again even if it has not
+    ///     moved, used when the copy buffer is replaced
+    pub const fn reset(&mut self) {
+        self.pending = None;
+        self.last_view = None;
+    }
+
+    /// Render the blend texture to the copy texture and copy the texel under the cursor to the
+    ///     start of the copy buffer, `UserMessage::HoverMapped` is sent once it can be read.
+    ///     Returns false if the cursor is not over the map
+    pub fn start(
+        &mut self,
+        render_context: &mut RenderContext,
+        geometry: &Geometry,
+        cursor: PhysicalPosition<f64>,
+        event_loop_proxy: EventLoopProxy<UserMessage<'static>>,
+    ) -> bool {
+        let camera = &render_context.camera_context.camera;
+        self.last_view = Some(view_key(cursor, camera));
+        self.last_read = Some(Instant::now());
+
+        let texture = &render_context.copy_context.texture;
+        let size = render_context.size;
+        let lon_lat = camera.screen_to_world((cursor.x, cursor.y).into());
+        if cursor.x < 0.0
+            || cursor.y < 0.0
+            || cursor.x >= f64::from(size.width)
+            || cursor.y >= f64::from(size.height)
+            || !(-180.0..=180.0).contains(&lon_lat.x)
+            || !(-90.0..=90.0).contains(&lon_lat.y)
+        {
+            return false;
+        }
+
+        let copy_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder =
+            render_context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Hover Encoder"),
+                });
+
+        // Render the blend texture to a rgba32Float texture that can be copied, like the max weight pass
+        {
+            let mut hover_render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Hover Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &copy_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            // The copy texture is padded to a multiple of 256, only the part the size of the canvas
+            //     is drawn to so texels line up with the cursor
+            hover_render_pass.set_viewport(
+                0.0,
+                0.0,
+                size.width as f32,
+                size.height as f32,
+                0.0,
+                1.0,
+            );
+            hover_render_pass.set_pipeline(&render_context.export_render_pipeline);
+            hover_render_pass.set_bind_group(
+                0,
+                &render_context.blend_texture_context.bind_group,
+                &[],
+            );
+            hover_render_pass
+                .set_vertex_buffer(0, geometry.rectangle_layer.vertex_buffer.slice(..));
+            hover_render_pass.set_index_buffer(
+                geometry.rectangle_layer.index_buffer.slice(..),
+                wgpu::IndexFormat::Uint16,
+            );
+            hover_render_pass.draw_indexed(0..geometry.rectangle_layer.num_indices, 0, 0..1);
+        }
+
+        // Only the texel under the cursor is copied
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: cursor.x as u32,
+                    y: cursor.y as u32,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &render_context.copy_context.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        render_context
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+
+        render_context.copy_context.buffer_mapped = true;
+        render_context
+            .copy_context
+            .buffer
+            .slice(..TEXEL_SIZE)
+            .map_async(wgpu::MapMode::Read, move |_| {
+                let _ = event_loop_proxy.send_event(UserMessage::HoverMapped);
+            });
+
+        self.pending = Some((
+            cursor,
+            Coord {
+                x: lon_lat.x,
+                y: lon_lat.y,
+            },
+        ));
+        true
+    }
+}
+
+/// Read the weight copied by `HoverReadback::start` and unmap the copy buffer
+pub fn read_hover_weight(render_context: &mut RenderContext) -> f32 {
+    let weight = {
+        let bytes = render_context
+            .copy_context
+            .buffer
+            .slice(..TEXEL_SIZE)
+            .get_mapped_range();
+        // Only the red channel holds the weight
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    };
+
+    render_context.copy_context.buffer.unmap();
+    render_context.copy_context.buffer_mapped = false;
+    weight
+}
+
+fn view_key(cursor: PhysicalPosition<f64>, camera: &Camera) -> [f64; 5] {
+    [
+        cursor.x,
+        cursor.y,
+        camera.position.x,
+        camera.position.y,
+        camera.zoom,
+    ]
+}
@@ -6,7 +6,18 @@ use winit::event::Modifiers;
 use winit::event::MouseButton;
 use winit::event::MouseScrollDelta;
 use winit::event::WindowEvent;
-use winit::keyboard::Key;
+use winit::keyboard::{Key, NamedKey};
+
+/// An area of interest drawn by the user in screen coordinates
+///  - Shift + drag draws a bounding box
+///  - Alt + click adds a vertex to a polygon, releasing Alt closes it
+///  - Escape clears the area of interest
+#[derive(Clone, Debug)]
+pub enum AoiInput {
+    BoundingBox(PhysicalPosition<f64>, PhysicalPosition<f64>),
+    Polygon(Vec<PhysicalPosition<f64>>),
+    Clear,
+}
 
 #[derive(Default, Clone)]
 pub struct InputState {
@@ -16,8 +27,19 @@ pub struct InputState {
     pub cursor_position: PhysicalPosition<f64>,
     mouse_scroll_delta: f64,
     mouse_drag_delta: PhysicalPosition<f64>,
+    // Corner the bounding box being drawn was started at
+    pub aoi_drag_start: Option<PhysicalPosition<f64>>,
+    // Vertices of the polygon being drawn
+    pub aoi_vertices: Vec<PhysicalPosition<f64>>,
+    completed_aoi: Option<AoiInput>,
+    // Where the left button was pressed without a modifier, it is a click if released nearby
+    click_start: Option<PhysicalPosition<f64>>,
+    completed_click: Option<PhysicalPosition<f64>>,
 }
 
+// A press and release further apart than this in physical pixels is a drag instead of a click
+const CLICK_TOLERANCE: f64 = 4.0;
+
 impl InputState {
     // Returns the amount of change in scroll delta since last function call
     pub const fn consume_scroll_delta(&mut self) -> f64 {
@@ -37,6 +59,21 @@ impl InputState {
         self.mouse_buttons.contains(&button)
     }
 
+    // Returns the area of interest finished since last function call
+    pub const fn consume_aoi(&mut self) -> Option<AoiInput> {
+        self.completed_aoi.take()
+    }
+
+    // Returns the position of the click finished since last function call
+    pub const fn consume_click(&mut self) -> Option<PhysicalPosition<f64>> {
+        self.completed_click.take()
+    }
+
+    // True while an area of interest is being drawn, the camera does not move while drawing
+    pub const fn is_drawing_aoi(&self) -> bool {
+        self.aoi_drag_start.is_some() || !self.aoi_vertices.is_empty()
+    }
+
     // Performs the specified action for the given window event
     pub fn eat_event(&mut self, event: WindowEvent) {
         use WindowEvent::{CursorMoved, KeyboardInput, ModifiersChanged, MouseInput, MouseWheel};
@@ -44,7 +81,7 @@ impl InputState {
         match event {
             // Update drag delta based on the change in cursor position
             CursorMoved { position, .. } => {
-                if self.is_mouse_button_pressed(MouseButton::Left) {
+                if self.is_mouse_button_pressed(MouseButton::Left) && !self.is_drawing_aoi() {
                     self.mouse_drag_delta.x += position.x - self.cursor_position.x;
                     self.mouse_drag_delta.y += position.y - self.cursor_position.y;
                 }
@@ -58,10 +95,35 @@ impl InputState {
                 match state {
                     Pressed => {
                         self.mouse_buttons.insert(button);
+
+                        if button == MouseButton::Left && self.modifiers.state().shift_key() {
+                            self.aoi_drag_start = Some(self.cursor_position);
+                        } else if button == MouseButton::Left && self.modifiers.state().alt_key() {
+                            self.aoi_vertices.push(self.cursor_position);
+                        } else if button == MouseButton::Left && self.modifiers.state().is_empty() {
+                            self.click_start = Some(self.cursor_position);
+                        }
                     }
 
                     Released => {
                         self.mouse_buttons.remove(&button);
+
+                        if button == MouseButton::Left
+                            && let Some(start) = self.aoi_drag_start.take()
+                            && start != self.cursor_position
+                        {
+                            self.completed_aoi =
+                                Some(AoiInput::BoundingBox(start, self.cursor_position));
+                        }
+
+                        if button == MouseButton::Left
+                            && let Some(start) = self.click_start.take()
+                            && (start.x - self.cursor_position.x)
+                                .hypot(start.y - self.cursor_position.y)
+                                <= CLICK_TOLERANCE
+                        {
+                            self.completed_click = Some(self.cursor_position);
+                        }
                     }
                 }
             }
@@ -87,6 +149,11 @@ impl InputState {
 
                 match event.state {
                     Pressed => {
+                        if event.logical_key == Key::Named(NamedKey::Escape) {
+                            self.aoi_drag_start = None;
+                            self.aoi_vertices.clear();
+                            self.completed_aoi = Some(AoiInput::Clear);
+                        }
                         self.keys.insert(event.logical_key);
                     }
 
@@ -98,6 +165,14 @@ impl InputState {
 
             ModifiersChanged(modifiers) => {
                 self.modifiers = modifiers;
+
+                // The polygon is closed once Alt is released, fewer than 3 vertices is discarded
+                if !modifiers.state().alt_key() && !self.aoi_vertices.is_empty() {
+                    let vertices = std::mem::take(&mut self.aoi_vertices);
+                    if vertices.len() >= 3 {
+                        self.completed_aoi = Some(AoiInput::Polygon(vertices));
+                    }
+                }
             }
 
             _ => {}
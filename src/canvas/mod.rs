@@ -4,14 +4,24 @@
     reason = "We allow expect for the whole module as winit makes it nearly impossible to properly manage error handling."
 )]
 
+mod aoi;
 pub mod app;
 mod camera;
 pub mod geometry;
+mod geotiff;
+#[cfg(test)]
+mod geotiff_tests;
+mod hover;
 mod input;
 mod pipeline;
 mod png;
+#[cfg(test)]
+mod png_tests;
 mod render_context;
 mod state;
+mod svg;
+#[cfg(test)]
+mod svg_tests;
 mod texture;
 
 // Canvas() is a leptos component which contains a HtmlCanvasElement containing
@@ -29,15 +39,28 @@ use winit::event_loop::EventLoop;
 use winit::platform::web::EventLoopExtWebSys;
 
 use crate::canvas::png::{ExportContext, InitStage};
+use crate::ingest::granule_export::write_granules;
 use crate::ingest::load::DataLoader;
-use crate::types::{self, GeneratePngSignal, ReadySignal};
+use crate::ingest::lod::LodConfig;
+use crate::types::{
+    self, AoiFileSignal, ColormapSignal, ExportOptionsSignal, GenerateImageSignal,
+    GranuleExportSignal, LoadStateSignal, ReadySignal,
+};
+use crate::ui::popup::escape_html;
 
 /// Component to display a heatmap generated using wgpu and wgsl shaders
 #[component]
 pub fn Canvas(
-    set_generate_img: leptos::prelude::WriteSignal<bool>,
+    set_generate_img: leptos::prelude::WriteSignal<Option<types::ImageFormat>>,
     set_ready: leptos::prelude::WriteSignal<bool>,
+    set_load_state: leptos::prelude::WriteSignal<types::LoadState>,
     set_max_weight: leptos::prelude::WriteSignal<u32>,
+    set_export_width_cap: leptos::prelude::WriteSignal<u32>,
+    set_aoi: leptos::prelude::WriteSignal<Option<geo::MultiPolygon>>,
+    set_title: leptos::prelude::WriteSignal<String>,
+    set_body: leptos::prelude::WriteSignal<String>,
+    set_popup_rows: leptos::prelude::WriteSignal<types::PopupRows>,
+    set_hover: leptos::prelude::WriteSignal<Option<types::HoverInfo>>,
 ) -> impl IntoView {
     // Signal from the UI containing the filter
     let filter = use_context::<ReadSignal<types::Filter>>()
@@ -46,8 +69,29 @@ pub fn Canvas(
     let ReadySignal(ready) =
         use_context::<ReadySignal>().expect("ERROR: Failed to get ready read signal in canvas");
 
-    let GeneratePngSignal(generate_img) = use_context::<GeneratePngSignal>()
-        .expect("ERROR: Failed to get generate_png read signal in Canvas()");
+    let LoadStateSignal(load_state) = use_context::<LoadStateSignal>()
+        .expect("ERROR: Failed to get load state read signal in canvas");
+
+    let GenerateImageSignal(generate_img) = use_context::<GenerateImageSignal>()
+        .expect("ERROR: Failed to get generate_img read signal in Canvas()");
+
+    let ExportOptionsSignal(export_options) = use_context::<ExportOptionsSignal>()
+        .expect("ERROR: Failed to get export options read signal in Canvas()");
+
+    let AoiFileSignal(aoi_file) = use_context::<AoiFileSignal>()
+        .expect("ERROR: Failed to get AOI file read signal in Canvas()");
+
+    let GranuleExportSignal(export_granules) = use_context::<GranuleExportSignal>()
+        .expect("ERROR: Failed to get granule export read signal in Canvas()");
+
+    let ColormapSignal(colormap) = use_context::<ColormapSignal>()
+        .expect("ERROR: Failed to get colormap read signal in Canvas()");
+
+    // Point on the map the user clicked, the granules there are listed in the popup
+    let (inspect_point, set_inspect_point) = signal(None::<geo::Coord>);
+
+    // Granules asked for by the UI along with the viewport they are listed in
+    let (granule_export, set_granule_export) = signal(None::<(types::GranuleFormat, geo::Rect)>);
 
     // Create event loop that can handle UserMessage events
     let event_loop = EventLoop::<UserMessage>::with_user_event()
@@ -57,20 +101,40 @@ pub fn Canvas(
     // The canvas element will be stored here once it has been created
     let external_state = Rc::new(RefCell::new(ExternalState {
         set_ready,
+        set_title,
+        set_body,
+        set_export_width_cap,
         canvas: None,
     }));
 
+    // Levels of detail shared between meshing and rendering, an invalid ladder is reported and the
+    //     default one is used instead
+    let lod_config = Rc::new(LodConfig::configured().unwrap_or_else(|err| {
+        log!("Invalid levels of detail: {err}");
+        set_title.set(err.title().to_string());
+        set_body.set(escape_html(&err.to_string()));
+        LodConfig::default()
+    }));
+
     let app = App {
         external_state: external_state.clone(),
         state: State {
             export_context: Some(ExportContext {
                 generate_img,
                 set_generate_img,
+                options: export_options,
                 stage: InitStage::Incomplete,
-                base64_png: None,
+                job: None,
+                image: None,
             }),
             filter: Some(filter),
+            colormap: Some(colormap),
             set_max_weight: Some(set_max_weight),
+            set_aoi: Some(set_aoi),
+            set_inspect_point: Some(set_inspect_point),
+            set_hover: Some(set_hover),
+            set_granule_export: Some(set_granule_export),
+            lod_config: lod_config.clone(),
             ..Default::default()
         },
         event_loop_proxy: event_loop.create_proxy(),
@@ -82,6 +146,28 @@ pub fn Canvas(
     // Start the event loop
     event_loop.spawn_app(app);
 
+    // Only the event loop knows the viewport, it is asked for when the UI exports granules
+    let export_proxy = event_loop_proxy.clone();
+    Effect::watch(
+        move || export_granules.get(),
+        move |format, _, _| {
+            if let Some(format) = *format {
+                let _ = export_proxy.send_event(UserMessage::ExportGranules(format));
+            }
+        },
+        false,
+    );
+
+    // The event loop owns the colormap texture, it reads the colormap the user picked once told
+    let colormap_proxy = event_loop_proxy.clone();
+    Effect::watch(
+        move || colormap.track(),
+        move |_, _, _| {
+            let _ = colormap_proxy.send_event(UserMessage::ColormapChanged);
+        },
+        false,
+    );
+
     let canvas_ref = NodeRef::<Div>::new();
     Effect::new(move |_| {
         log!("Adding canvas to DOM");
@@ -102,16 +188,137 @@ pub fn Canvas(
     // Struct responsible for making requests to the service for new data
 
     leptos::task::spawn_local(async move {
-        let data_loader = DataLoader::new(event_loop_proxy, set_ready, &filter()).await;
-        // Anytime the filter signal changes the data loader now calls load data with the new signal
-        // data_loader.load_data(filter());
-        Effect::new(move |_| data_loader.load_data(filter()));
+        match DataLoader::new(
+            event_loop_proxy.clone(),
+            set_ready,
+            set_load_state,
+            &filter(),
+            lod_config,
+        )
+        .await
+        {
+            Ok(data_loader) => {
+                // Imported areas of interest are read with the same connection as the data,
+                //     shapefiles need DuckDB spatial
+                let importer = data_loader.clone();
+                Effect::watch(
+                    move || aoi_file.get(),
+                    move |file, _, _| {
+                        let Some(file) = file.clone() else {
+                            return;
+                        };
+                        let importer = importer.clone();
+                        leptos::task::spawn_local(async move {
+                            match importer.import_aoi(&file).await {
+                                Ok(aoi) => set_aoi.set(Some(aoi)),
+                                Err(err) => {
+                                    log!("Failed to import AOI: {err}");
+                                    set_title.set(err.title().to_string());
+                                    set_body.set(escape_html(&err.to_string()));
+                                }
+                            }
+                        });
+                    },
+                    true,
+                );
+
+                let inspector = data_loader.clone();
+                Effect::watch(
+                    move || inspect_point.get(),
+                    move |point, _, _| {
+                        let Some(point) = *point else {
+                            return;
+                        };
+                        let inspector = inspector.clone();
+                        leptos::task::spawn_local(async move {
+                            match inspector.inspect(&filter.get_untracked(), point).await {
+                                Ok(granules) => {
+                                    let title =
+                                        format!("Granules at {:.3}, {:.3}", point.x, point.y);
+                                    set_popup_rows.set(types::PopupRows {
+                                        title: title.clone(),
+                                        columns: vec![
+                                            "Granule".to_string(),
+                                            "Start Time".to_string(),
+                                        ],
+                                        rows: granules
+                                            .iter()
+                                            .map(|x| {
+                                                vec![x.granule_name.clone(), x.start_time.clone()]
+                                            })
+                                            .collect(),
+                                    });
+                                    set_title.set(title);
+                                    set_body.set(format!(
+                                        "{} acquisitions match the filter at this point",
+                                        granules.len()
+                                    ));
+                                }
+                                Err(err) => {
+                                    log!("Failed to inspect point: {err}");
+                                    set_title.set(err.title().to_string());
+                                    set_body.set(escape_html(&err.to_string()));
+                                }
+                            }
+                        });
+                    },
+                    false,
+                );
+
+                let exporter = data_loader.clone();
+                Effect::watch(
+                    move || granule_export.get(),
+                    move |export, _, _| {
+                        let Some((format, viewport)) = *export else {
+                            return;
+                        };
+                        let exporter = exporter.clone();
+                        leptos::task::spawn_local(async move {
+                            match exporter
+                                .list_granules(&filter.get_untracked(), viewport)
+                                .await
+                            {
+                                Ok(granules) if granules.is_empty() => {
+                                    set_title.set("No Granules".to_string());
+                                    set_body.set(
+                                        "No acquisitions match the filter in view, move the map or \
+                                            generate a heatmap first"
+                                            .to_string(),
+                                    );
+                                }
+                                Ok(granules) => {
+                                    app::download_text(
+                                        &write_granules(&granules, format),
+                                        format.mime_type(),
+                                        format.file_name(),
+                                    );
+                                    log!("Exported {} granules", granules.len());
+                                }
+                                Err(err) => {
+                                    log!("Failed to export granules: {err}");
+                                    set_title.set(err.title().to_string());
+                                    set_body.set(escape_html(&err.to_string()));
+                                }
+                            }
+                        });
+                    },
+                    false,
+                );
+
+                // Anytime the filter signal changes the data loader now calls load data with the new signal
+                Effect::new(move |_| data_loader.load_data(filter()));
+            }
+            Err(err) => {
+                log!("Failed to create DataLoader: {err}");
+                let _ = event_loop_proxy.send_event(UserMessage::IngestFailed(err));
+            }
+        }
     });
 
     log!("Creating view!");
     view! {
         <div>
-            <Show when=move || { !ready() }>
+            <Show when=move || { !ready() || load_state() != types::LoadState::Idle }>
                 <div id="loader">
                     <span class="loader"></span>
                 </div>
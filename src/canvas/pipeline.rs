@@ -168,7 +168,9 @@ pub fn generate_export_colormap_pipeline(
             compilation_options: PipelineCompilationOptions::default(),
             targets: &[Some(wgpu::ColorTargetState {
                 format: config.format,
-                blend: None,
+                // Blended like the display colormap so the world outline and background show
+                //     through where there are no acquisitions
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
@@ -191,16 +193,23 @@ pub fn generate_export_colormap_pipeline(
     })
 }
 
+/// Generates a render pipeline that draws meshes in the color of the uniform bound to group 1,
+///     the world outline is drawn as triangles and the area of interest as lines
 pub fn generate_outline_pipeline(
     device: &wgpu::Device,
     camera_context: &CameraContext,
+    color_bind_group_layout: &wgpu::BindGroupLayout,
+    topology: wgpu::PrimitiveTopology,
 ) -> wgpu::RenderPipeline {
     let outline_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/outline.wgsl"));
 
     let outline_render_pipeline_layout =
         device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Outline Render Pipeline Layout"),
-            bind_group_layouts: &[&camera_context.camera_bind_group_layout],
+            bind_group_layouts: &[
+                &camera_context.camera_bind_group_layout,
+                color_bind_group_layout,
+            ],
             push_constant_ranges: &[],
         });
 
@@ -224,7 +233,7 @@ pub fn generate_outline_pipeline(
             })],
         }),
         primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
+            topology,
             strip_index_format: None,
             front_face: wgpu::FrontFace::Cw,
             cull_mode: None,
@@ -1,301 +1,450 @@
-use base64::Engine;
-use image::ImageEncoder;
-use image::codecs::png::PngEncoder;
-use image::{ImageBuffer, Rgba};
+use geo::{BoundingRect as _, MultiPolygon, Rect, coord};
+use image::{Rgba, RgbaImage};
 use leptos::logging::log;
 use leptos::prelude::ReadSignal;
 use text_to_png::TextRenderer;
+use winit::dpi::PhysicalSize;
 
 use super::render_context::RenderContext;
-use crate::types::{Filter, PlatformType, ProductTypes};
+use crate::types::{
+    EXPORT_WIDTHS, ExportFraming, ExportOptions, Filter, FilterOption, ImageFormat, OrbitDirection,
+    PlatformType,
+};
+
+// Sizes in points, 1/72 of an inch, they are converted to pixels with the DPI of the export so
+//     the legend and caption are the same size when printed no matter the resolution, the SVG
+//     export is laid out with the same sizes
+//
+// Space around the map and between the legend and caption under it
+pub const MARGIN: f32 = 12.0;
+pub const BORDER: f32 = 0.5;
+
+pub const LEGEND_BAR_HEIGHT: f32 = 9.0;
+// The legend bar is half as wide as the map up to this width
+pub const LEGEND_MAX_WIDTH: f32 = 240.0;
+// Most weights labelled under the legend bar
+const LEGEND_TICKS: u32 = 5;
+
+pub const HEADER_FONT_SIZE: f32 = 10.0;
+pub const LABEL_FONT_SIZE: f32 = 8.0;
+pub const CAPTION_FONT_SIZE: f32 = 9.0;
+
+// An area of interest is framed with this fraction of its size around it
+const AOI_PADDING: f64 = 0.05;
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+// PNGs store their resolution in pixels per meter
+const METERS_PER_INCH: f64 = 0.0254;
+
+// Size in bytes of a single Rgba32Float texel
+const TEXEL_SIZE: u64 = 4 * 4;
+// Largest copy buffer an export reads back, the WebGL2 default. Adapters may allow more but the
+//     texels are read onto the wasm heap along with the composed image
+const MAX_COPY_BUFFER_SIZE: u64 = 256 << 20;
+// The copy texture is padded to a multiple of this many texels wide so it can be mapped
+const COPY_WIDTH_ALIGNMENT: u32 = 256;
 
 /// Generate the final png that will be exported
-pub fn generate_heatmap_image(render_context: &mut RenderContext, filter: &Filter) -> String {
-    // We read the data contained in the buffer and convert it from &[u8] to Vec<u8>
-    let raw_bytes: Vec<u8> = (&*render_context
-        .copy_context
-        .buffer
-        .slice(..)
-        .get_mapped_range())
-        .into();
-
-    // The buffer is formated for f32 but we pulled a Vec<u8>, we must reform the Vec<f32> from the bytes
-    let mut color_data: Vec<f32> = Vec::new();
-    let mut raw_iter = raw_bytes.iter();
-    while let Ok(raw) = raw_iter.next_chunk::<4>() {
-        // Read one channel into a f32
-        color_data.push(f32::from_le_bytes([*raw[0], *raw[1], *raw[2], *raw[3]]));
-    }
-
-    log!("Freshley decoded: {color_data:?}");
+pub fn generate_heatmap_image(
+    render_context: &mut RenderContext,
+    filter: &Filter,
+    options: &ExportOptions,
+) -> Vec<u8> {
+    // The texels are converted to sRGB straight from the mapped buffer, must use copy texture
+    //     width here, Copy Texture is 256 byte aligned so copy_texture.width() can be larger than
+    //     displayed size and so are the contents of our buffer
+    let copy_texture = &render_context.copy_context.texture;
+    let size = PhysicalSize::new(
+        render_context.size.width.min(copy_texture.width()),
+        render_context.size.height.min(copy_texture.height()),
+    );
+    let heatmap_img = heatmap_from_texels(
+        &render_context
+            .copy_context
+            .buffer
+            .slice(..)
+            .get_mapped_range(),
+        copy_texture.width(),
+        size,
+    );
 
-    // Convert the raw image data into an ImageBuffer that can be saved, must use copy texture width here,
-    //     Copy Texture is 256 byte aligned so copy_texture.width() is larger than displayed size and so
-    //     are the contents of our buffer
-    let colormap_img = image::Rgba32FImage::from_vec(
-        render_context.copy_context.texture.width(),
-        render_context.copy_context.texture.height(),
-        color_data,
-    )
-    .expect("Failed to convert parsed floats into an Rgba<f32> ImageBuffer");
+    // Release the copy buffer for later use
+    render_context.copy_context.buffer.unmap();
+    render_context.copy_context.buffer_mapped = false;
 
     // Generate the image to be export
     let output_img = generate_export_image(
-        &colormap_img,
+        &heatmap_img,
         render_context
             .max_weight_context
             .value
             .expect("Failed to get max weight to generate output png"),
         filter,
+        &render_context.colormap,
+        options,
+    );
+    log!(
+        "Composed {} by {} export image",
+        output_img.width(),
+        output_img.height()
     );
 
-    // Release the copy buffer for later use
-    render_context.copy_context.buffer.unmap();
-    render_context.copy_context.buffer_mapped = false;
+    encode_png(&output_img, options.dpi)
+}
 
-    let output_img_width = output_img.width();
-    let output_img_height = output_img.height();
+/// Read the Rgba32Float texels of the copy buffer, rows of `copy_width` texels, as an sRGB
+///     image of the part of the texture that was rendered to
+pub fn heatmap_from_texels(texels: &[u8], copy_width: u32, size: PhysicalSize<u32>) -> RgbaImage {
+    RgbaImage::from_fn(size.width, size.height, |x, y| {
+        let offset = (u64::from(y) * u64::from(copy_width) + u64::from(x)) * TEXEL_SIZE;
+        let texel = &texels[offset as usize..(offset + TEXEL_SIZE) as usize];
+        linear_to_srgb(Rgba([0, 4, 8, 12].map(|i| {
+            f32::from_le_bytes([texel[i], texel[i + 1], texel[i + 2], texel[i + 3]])
+        })))
+    })
+}
 
-    let raw_image_data: Vec<u8> = image::DynamicImage::from(output_img).to_rgba8().into_raw();
+/// Encode an image as an 8 bit png with its resolution stored so it prints at the chosen size
+pub fn encode_png(img: &RgbaImage, dpi: u32) -> Vec<u8> {
+    let pixels_per_meter = (f64::from(dpi) / METERS_PER_INCH).round() as u32;
 
-    // Encode the generated ImageBuffer into a png
     let mut png_bytes = Vec::<u8>::new();
-    let png_encoder = PngEncoder::new(&mut png_bytes);
-    let _result = png_encoder.write_image(
-        raw_image_data.as_slice(),
-        output_img_width,
-        output_img_height,
-        image::ExtendedColorType::Rgba8,
-    );
-
-    // Encode the png in base64 to allow for download from an html anchor tag
-    base64::engine::general_purpose::STANDARD.encode(&png_bytes)
+    {
+        let mut encoder = png::Encoder::new(&mut png_bytes, img.width(), img.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: pixels_per_meter,
+            yppu: pixels_per_meter,
+            unit: png::Unit::Meter,
+        }));
+
+        let mut writer = encoder
+            .write_header()
+            .expect("ERROR: Failed to write png header");
+        writer
+            .write_image_data(img.as_raw())
+            .expect("ERROR: Failed to write png data");
+    }
+    png_bytes
 }
 
+/// Compose the heatmap, which already has the world outline drawn under it and is in sRGB, with a
+///     legend and a caption describing the filter under it, on a white page unless the export is
+///     transparent
 pub fn generate_export_image(
-    colormap_img: &ImageBuffer<Rgba<f32>, Vec<f32>>,
+    heatmap_img: &RgbaImage,
     max_weight: f32,
     filter: &Filter,
-) -> ImageBuffer<Rgba<f32>, Vec<f32>> {
-    let colormap_img_width = 3083;
-    let colormap_img_height = 1551;
-
-    /////////////////////
-    // Read Image Data //
-    /////////////////////
-
-    let resized_colormap_img = image::imageops::resize(
-        colormap_img,
-        colormap_img_width,
-        colormap_img_height,
-        image::imageops::FilterType::Nearest,
+    colormap: &RgbaImage,
+    options: &ExportOptions,
+) -> RgbaImage {
+    let text_renderer = TextRenderer::default();
+    let margin = points_to_pixels(MARGIN, options.dpi);
+    let border = points_to_pixels(BORDER, options.dpi);
+
+    let legend_img = legend_image(
+        &text_renderer,
+        max_weight,
+        colormap,
+        (heatmap_img.width() / 2).clamp(1, points_to_pixels(LEGEND_MAX_WIDTH, options.dpi)),
+        options.dpi,
+    );
+    let caption_img = caption_image(&text_renderer, filter, options.dpi);
+
+    // The image is widened if the legend and caption do not fit under the map
+    let footer_width = legend_img.width() + margin + caption_img.width();
+    let width = heatmap_img.width().max(footer_width) + 2 * margin;
+    let height = heatmap_img.height() + legend_img.height().max(caption_img.height()) + 3 * margin;
+    let background = if options.transparent {
+        TRANSPARENT
+    } else {
+        WHITE
+    };
+    let mut output_img = RgbaImage::from_pixel(width, height, background);
+
+    fill_rect(
+        &mut output_img,
+        margin - border,
+        margin - border,
+        heatmap_img.width() + 2 * border,
+        heatmap_img.height() + 2 * border,
+        BLACK,
     );
+    image::imageops::replace(&mut output_img, heatmap_img, margin.into(), margin.into());
 
-    // Store image template in memory
-    let template_bytes = &[0];
-    let mut template_img = image::load_from_memory(template_bytes)
-        .expect("ERROR: Failed to load export_template.png")
-        .to_rgba32f();
-
-    // Store the world outline in memory, must be overlayed after heatmap image to appear on final image
-    let outline_bytes = &[0];
-    let outline_img = image::load_from_memory(outline_bytes)
-        .expect("ERROR: Failed to load export_outline.png")
-        .to_rgba32f();
-    let resized_outline_img = image::imageops::resize(
-        &outline_img,
-        colormap_img_width,
-        colormap_img_height,
-        image::imageops::FilterType::Nearest,
+    // Legend under the left of the map and the caption under its right
+    let footer_y = heatmap_img.height() + 2 * margin;
+    image::imageops::overlay(&mut output_img, &legend_img, margin.into(), footer_y.into());
+    image::imageops::overlay(
+        &mut output_img,
+        &caption_img,
+        (width - margin - caption_img.width()).into(),
+        footer_y.into(),
     );
 
-    ///////////////////////
-    // Create the Legend //
-    ///////////////////////
-
-    // Renders text into a png we can overlay on the rest of our image
-    let text_renderer = TextRenderer::try_new_with_ttf_font_data([0])
-        .expect("ERROR: Failed to read times new roman font when creating text renderer");
-
-    // Store the legend in memory
-    let legend_bytes = &[0];
-    let mut legend_img = image::load_from_memory(legend_bytes)
-        .expect("ERROR: Failed to load export_legend")
-        .to_rgba32f();
-
-    // These numbers correspond to the ratio of each color on the export colormap, ie num_pixels_of_color/length_of_texture
-    let legend_weights = [
-        0.004_166_667,
-        0.010_416_667,
-        0.010_416_667,
-        0.020_833_333,
-        0.022_916_667,
-        0.075,
-        0.15,
-        0.714_583,
-    ];
+    output_img
+}
+
+/// Convert a length in points to pixels at the passed resolution, never less than a pixel
+pub fn points_to_pixels(points: f32, dpi: u32) -> u32 {
+    (points * dpi as f32 / 72.0).round().max(1.0) as u32
+}
+
+/// Region of the world the map of an export shows, the area of interest is padded so its outline
+///     is not drawn on the border and anything degenerate falls back to the whole world
+pub fn export_frame(framing: ExportFraming, viewport: Rect, aoi: Option<&MultiPolygon>) -> Rect {
+    let world = Rect::new(coord! { x: -180.0, y: -90.0 }, coord! { x: 180.0, y: 90.0 });
+
+    let frame = match framing {
+        ExportFraming::World => world,
+        ExportFraming::Viewport => viewport,
+        ExportFraming::Aoi => aoi.and_then(|x| x.bounding_rect()).map_or(world, |x| {
+            let pad_x = x.width() * AOI_PADDING;
+            let pad_y = x.height() * AOI_PADDING;
+            Rect::new(
+                coord! {
+                    x: (x.min().x - pad_x).max(-180.0),
+                    y: (x.min().y - pad_y).max(-90.0),
+                },
+                coord! {
+                    x: (x.max().x + pad_x).min(180.0),
+                    y: (x.max().y + pad_y).min(90.0),
+                },
+            )
+        }),
+    };
+
+    if frame.width() > 0.0 && frame.height() > 0.0 {
+        frame
+    } else {
+        world
+    }
+}
+
+/// Largest export the GPU can render and copy back to the CPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExportLimits {
+    pub max_dimension: u32,
+    pub max_buffer_size: u64,
+}
+
+impl From<&wgpu::Limits> for ExportLimits {
+    fn from(limits: &wgpu::Limits) -> Self {
+        Self {
+            max_dimension: limits.max_texture_dimension_2d,
+            max_buffer_size: limits.max_buffer_size.min(MAX_COPY_BUFFER_SIZE),
+        }
+    }
+}
+
+impl ExportLimits {
+    /// Widest map of the whole world that can be exported, the UI does not offer anything wider
+    pub fn max_width(self) -> u32 {
+        let world = Rect::new(coord! { x: -180.0, y: -90.0 }, coord! { x: 180.0, y: 90.0 });
+        export_size(world, *EXPORT_WIDTHS.end(), self).width
+    }
+}
+
+/// Size in pixels a frame is rendered at, its aspect ratio is kept, neither side is larger
+///     than the GPU supports and its texels fit in a single copy buffer
+pub fn export_size(frame: Rect, width: u32, limits: ExportLimits) -> PhysicalSize<u32> {
+    let aspect = frame.height() / frame.width();
+    let height_of = |width: u32| {
+        ((f64::from(width) * aspect).round().max(1.0) as u32).min(limits.max_dimension)
+    };
+    // The padded width of the copy texture must fit as well
+    let max_width = (limits.max_dimension / COPY_WIDTH_ALIGNMENT * COPY_WIDTH_ALIGNMENT).max(1);
+
+    let mut width = width.clamp(1, max_width);
+    if (f64::from(width) * aspect).round() > f64::from(limits.max_dimension) {
+        width = ((f64::from(limits.max_dimension) / aspect).round().max(1.0) as u32).min(max_width);
+    }
+    while width > 1 && copy_buffer_size(width, height_of(width)) > limits.max_buffer_size {
+        width -= 1;
+    }
+
+    PhysicalSize::new(width, height_of(width))
+}
+
+/// Size in bytes of the buffer the texels of an export are copied into
+pub fn copy_buffer_size(width: u32, height: u32) -> u64 {
+    TEXEL_SIZE * u64::from(width.next_multiple_of(COPY_WIDTH_ALIGNMENT)) * u64::from(height)
+}
+
+/// Weights labelled under the legend bar, the lowest weight of 1 and the max weight with evenly
+///     spaced weights between them, rounded to a multiple of 5 once they are large enough
+pub fn legend_ticks(max_weight: f32) -> Vec<u32> {
+    let max = (max_weight.round() as u32).max(1);
+    if max <= LEGEND_TICKS {
+        return (1..=max).collect();
+    }
 
-    // Adds numbers to the legend, each range is added individually to allow
-    // for proper line spacing. Renders text of size font_size into a png format
-    // this is then converted into a ImageBuffer and overlayed onto the legend
-    let font_size = 56;
-    let x_coord = 152;
-    let y_initial = 197;
-    let mut layer = 0;
-    let mut last_upper = 1.0; // Everywhere with color on the heatmap has >=1 images
-    while layer < 7 {
-        let upper = max_weight.mul_add(legend_weights[layer], last_upper).ceil();
-        let text_data = text_renderer
-            .render_text_to_png_data(
-                format!("{:?}-{:?}", last_upper as u32, upper as u32),
-                font_size,
-                0x0,
+    let mut ticks: Vec<u32> = (0..LEGEND_TICKS)
+        .map(|i| {
+            let weight = ((max - 1) * i) as f32 / (LEGEND_TICKS - 1) as f32 + 1.0;
+            if weight >= 10.0 {
+                (weight / 5.0).round() as u32 * 5
+            } else {
+                weight.round() as u32
+            }
+        })
+        .collect();
+    ticks[LEGEND_TICKS as usize - 1] = max;
+    ticks.dedup();
+    ticks
+}
+
+/// Color of a weight on the exported heatmap, this is the same lookup as export_colormap.wgsl
+pub fn colormap_color(colormap: &RgbaImage, weight: f32, max_weight: f32) -> Rgba<u8> {
+    let tex_dim = colormap.width() as f32;
+    let map_coord = (((weight * 1.1) / max_weight) * tex_dim * 1.2).clamp(0.0, tex_dim - 1.0);
+    let [r, g, b, _] = colormap.get_pixel(map_coord as u32, 0).0;
+
+    Rgba([r, g, b, 255])
+}
+
+// A header over a bar running through the colormap from a weight of 1 to the max weight, with
+//     the weights from legend_ticks labelled under it
+fn legend_image(
+    text_renderer: &TextRenderer,
+    max_weight: f32,
+    colormap: &RgbaImage,
+    bar_width: u32,
+    dpi: u32,
+) -> RgbaImage {
+    let header_img = render_text(
+        text_renderer,
+        "Acquisitions",
+        points_to_pixels(HEADER_FONT_SIZE, dpi),
+    );
+    let ticks = legend_ticks(max_weight);
+    let label_imgs: Vec<RgbaImage> = ticks
+        .iter()
+        .map(|x| {
+            render_text(
+                text_renderer,
+                &x.to_string(),
+                points_to_pixels(LABEL_FONT_SIZE, dpi),
             )
-            .expect("ERROR: Failed to create text_data png")
-            .data;
-        let text_img = image::load_from_memory(&text_data)
-            .expect("ERROR: Failed to create dynamic image for data_text")
-            .to_rgba32f();
+        })
+        .collect();
+
+    let bar_height = points_to_pixels(LEGEND_BAR_HEIGHT, dpi);
+    let spacing = points_to_pixels(MARGIN / 4.0, dpi);
+    let bar_y = header_img.height() + spacing;
+    let label_y = bar_y + bar_height + spacing;
+    let label_height = label_imgs.iter().map(RgbaImage::height).max().unwrap_or(0);
+
+    // Labels are centered under their weight so the last one may hang past the end of the bar
+    let width = header_img
+        .width()
+        .max(bar_width + label_imgs.last().map_or(0, RgbaImage::width));
+    let mut legend_img = RgbaImage::new(width, label_y + label_height);
+    image::imageops::overlay(&mut legend_img, &header_img, 0, 0);
+
+    // Position along the bar of a weight and the weight at a position along the bar
+    let span = (max_weight - 1.0).max(1.0);
+    let last = (bar_width - 1).max(1) as f32;
+    for x in 0..bar_width {
+        let weight = (x as f32 / last).mul_add(span, 1.0);
+        fill_rect(
+            &mut legend_img,
+            x,
+            bar_y,
+            1,
+            bar_height,
+            colormap_color(colormap, weight, max_weight),
+        );
+    }
+
+    for (tick, label_img) in ticks.iter().zip(&label_imgs) {
+        let x = ((*tick as f32 - 1.0) / span * last).round() as u32;
         image::imageops::overlay(
             &mut legend_img,
-            &text_img,
-            x_coord,
-            y_initial + (font_size * i64::try_from(layer).expect("Cast from usize to i64 wrapped")),
+            label_img,
+            x.saturating_sub(label_img.width() / 2).into(),
+            label_y.into(),
         );
+    }
+
+    legend_img
+}
+
+// Each line of filter_to_text centered under the last
+fn caption_image(text_renderer: &TextRenderer, filter: &Filter, dpi: u32) -> RgbaImage {
+    let font_size = points_to_pixels(CAPTION_FONT_SIZE, dpi);
+    let line_imgs: Vec<RgbaImage> = filter_to_text(filter)
+        .iter()
+        .map(|x| render_text(text_renderer, x, font_size))
+        .collect();
 
-        last_upper = upper;
-        layer += 1;
+    let width = line_imgs.iter().map(RgbaImage::width).max().unwrap_or(0);
+    let height = line_imgs.iter().map(RgbaImage::height).sum();
+    let mut caption_img = RgbaImage::new(width, height);
 
-        log!("Upper: {upper:?}\nRunning Total: {last_upper:?}");
+    let mut y = 0;
+    for line_img in &line_imgs {
+        image::imageops::overlay(
+            &mut caption_img,
+            line_img,
+            center_img(width, line_img).into(),
+            y.into(),
+        );
+        y += line_img.height();
     }
 
-    // Last range in legend, formatting is unique so it cant be done in the loop
-    let text_data = text_renderer
-        .render_text_to_png_data(format!("> {:?}", last_upper as u32), font_size, 0x0)
-        .expect("ERROR: Failed to create final text_data png")
-        .data;
-    let text_img = image::load_from_memory(&text_data)
-        .expect("ERROR: Failed to create dynamic image for text_data")
-        .to_rgba32f();
-    image::imageops::overlay(
-        &mut legend_img,
-        &text_img,
-        x_coord,
-        y_initial
-            + (font_size
-                * i64::try_from(layer).expect("Layer wrapped when casting to usize to i64")),
-    );
+    caption_img
+}
 
-    /////////////////////////////////
-    // Create Labels and Date Text //
-    /////////////////////////////////
-
-    // Parse the filter into text that can be turned into images
-    let filter_text = filter_to_text(filter);
-    let font_size = 68;
-    let line1_data = text_renderer
-        .render_text_to_png_data(
-            "Copernicus ".to_owned() + &filter_text.1 + " data,",
-            font_size,
-            0x0,
-        )
-        .expect("ERROR: Failed to create date range text")
-        .data;
-    let line2_data = text_renderer
-        .render_text_to_png_data(filter_text.2 + "-" + &filter_text.3, font_size, 0x0)
-        .expect("ERROR: Failed to create date range text")
-        .data;
-    let line3_data = text_renderer
-        .render_text_to_png_data(
-            "Current: 30 April ".to_owned() + &filter_text.3,
-            font_size,
-            0x0,
-        )
-        .expect("ERROR: Failed to create date range text")
-        .data;
-    let line4_data = text_renderer
-        .render_text_to_png_data(
-            "Map shows Sentinel-1 ".to_owned() + &filter_text.0,
-            font_size,
-            0x0,
-        )
-        .expect("ERROR: Failed to create date range text")
-        .data;
-    let line5_data = text_renderer
-        .render_text_to_png_data("product global coverage", font_size, 0x0)
-        .expect("ERROR: Failed to create date range text")
+// Renders black text into a png and reads it back as an image that can be overlayed
+fn render_text(text_renderer: &TextRenderer, text: &str, font_size: u32) -> RgbaImage {
+    let text_data = text_renderer
+        .render_text_to_png_data(text, i64::from(font_size), 0x0)
+        .expect("ERROR: Failed to create text png")
         .data;
 
-    let line1_img = image::load_from_memory(&line1_data)
-        .expect("ERROR: Failed to create dynamic image for line1 text")
-        .to_rgba32f();
-    let line2_img = image::load_from_memory(&line2_data)
-        .expect("ERROR: Failed to create dynamic image for line2 text")
-        .to_rgba32f();
-    let line3_img = image::load_from_memory(&line3_data)
-        .expect("ERROR: Failed to create dynamic image for line3 text")
-        .to_rgba32f();
-    let line4_img: ImageBuffer<Rgba<f32>, Vec<f32>> = image::load_from_memory(&line4_data)
-        .expect("ERROR: Failed to create dynamic image for line4 text")
-        .to_rgba32f();
-    let line5_img: ImageBuffer<Rgba<f32>, Vec<f32>> = image::load_from_memory(&line5_data)
-        .expect("ERROR: Failed to create dynamic image for line5 text")
-        .to_rgba32f();
-
-    // Create the blank template to write the date and info text onto
-    let date_img_width: u32 = 1240;
-    let mut date_img = image::ImageBuffer::<Rgba<f32>, Vec<f32>>::new(date_img_width, 400);
-    date_img.pixels_mut().for_each(|x| {
-        x.0 = [1.0, 1.0, 1.0, 1.0];
-    });
-
-    image::imageops::overlay(
-        &mut date_img,
-        &line1_img,
-        center_img(date_img_width, &line1_img).into(),
-        0,
-    );
-    image::imageops::overlay(
-        &mut date_img,
-        &line2_img,
-        center_img(date_img_width, &line2_img).into(),
-        font_size,
-    );
-    image::imageops::overlay(
-        &mut date_img,
-        &line3_img,
-        center_img(date_img_width, &line3_img).into(),
-        font_size * 2,
-    );
-    image::imageops::overlay(
-        &mut date_img,
-        &line4_img,
-        center_img(date_img_width, &line4_img).into(),
-        400 - font_size * 2,
-    );
-    image::imageops::overlay(
-        &mut date_img,
-        &line5_img,
-        center_img(date_img_width, &line5_img).into(),
-        400 - font_size,
-    );
-
-    ////////////////////////
-    // Create Final Image //
-    ////////////////////////
+    image::load_from_memory(&text_data)
+        .expect("ERROR: Failed to create dynamic image for text")
+        .to_rgba8()
+}
 
-    // resized_colormap_img must come before resized_outline_img for the world outline to display properly
-    image::imageops::overlay(&mut template_img, &resized_colormap_img, 216, 186);
-    image::imageops::overlay(&mut template_img, &resized_outline_img, 216, 186);
-    image::imageops::overlay(&mut template_img, &legend_img, 2804, 1750);
-    image::imageops::overlay(&mut template_img, &date_img, 1563, 1796);
+// Helper Function:
+//     Fills a rectangle of the image with a single color
+fn fill_rect(img: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(img.height()) {
+        for px in x..(x + width).min(img.width()) {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
 
-    template_img
+// Helper Function:
+//     Encodes a linear color as 8 bit sRGB, alpha is only quantized
+fn linear_to_srgb(color: Rgba<f32>) -> Rgba<u8> {
+    let encode = |c: f32| {
+        if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055f32.mul_add(c.powf(1.0 / 2.4), -0.055)
+        }
+    };
+    let quantize = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let [r, g, b, a] = color.0;
+    Rgba([
+        quantize(encode(r)),
+        quantize(encode(g)),
+        quantize(encode(b)),
+        quantize(a),
+    ])
 }
 
 // Helper Function:
 //     Returns the x coordinate that centers one image on another image
-fn center_img(destination_width: u32, text: &ImageBuffer<Rgba<f32>, Vec<f32>>) -> u32 {
+fn center_img(destination_width: u32, text: &RgbaImage) -> u32 {
     let dest_center = destination_width / 2;
     let text_offset = text.width() / 2;
 
@@ -307,59 +456,104 @@ fn center_img(destination_width: u32, text: &ImageBuffer<Rgba<f32>, Vec<f32>>) -
     dest_center - text_offset
 }
 
-// Parse a filter into a tuple of four strings, the contents of each string are:
-//      0. Product Type
-//      1. Platform
-//      2. Start Year
-//      3. End Year
-fn filter_to_text(filter: &Filter) -> (String, String, String, String) {
-    let mut product_string = String::new();
-    for product_type in filter.product_type.iter().enumerate() {
-        if product_type.0 != 0 && product_type.0 < filter.product_type.len() - 1 {
-            product_string += ", ";
-        } else if product_type.0 == filter.product_type.len() - 1 {
-            product_string += " and ";
-        }
-        match product_type.1 {
-            ProductTypes::GroundRangeDetected => product_string += "GRD",
-            ProductTypes::SingleLookComplex => product_string += "SLC",
-            ProductTypes::Ocean => product_string += "OCN",
-        }
+/// Describe a filter in the lines of a caption, beam modes, polarizations and orbits are only
+///     written when the filter narrows them, ie.
+///      Sentinel-1A and Sentinel-1B GRD and SLC acquisitions
+///      2026-06-01 to 2026-07-01
+///      IW beam mode
+///      Ascending passes
+///      Relative orbits 12 and 40-45
+pub fn filter_to_text(filter: &Filter) -> Vec<String> {
+    let platforms = join_list(
+        filter
+            .platform_type
+            .iter()
+            .map(|x| platform_name(*x).to_string()),
+    );
+    let products = join_list(filter.product_type.iter().map(|x| x.label()));
+
+    let mut lines = vec![
+        format!("{platforms} {products} acquisitions"),
+        format!(
+            "{} to {}",
+            filter.date_range.start.format("%Y-%m-%d"),
+            filter.date_range.end.format("%Y-%m-%d")
+        ),
+    ];
+    lines.extend(options_to_text(&filter.beam_mode, "beam mode"));
+    lines.extend(options_to_text(&filter.polarization, "polarization"));
+    match filter.orbit_direction {
+        Some(OrbitDirection::Ascending) => lines.push("Ascending passes".to_string()),
+        Some(OrbitDirection::Descending) => lines.push("Descending passes".to_string()),
+        None => {}
+    }
+    if !filter.relative_orbit.is_empty() {
+        let orbits = join_list(filter.relative_orbit.iter().map(|x| {
+            if x.start() == x.end() {
+                x.start().to_string()
+            } else {
+                format!("{}-{}", x.start(), x.end())
+            }
+        }));
+        lines.push(format!(
+            "{} {orbits}",
+            plural("Relative orbit", &filter.relative_orbit)
+        ));
     }
+    if filter.aoi.is_some() {
+        lines.push("Inside of the area of interest".to_string());
+    }
+    lines
+}
 
-    let mut platform_string = String::new();
-    for platform_type in filter.platform_type.iter().enumerate() {
-        if platform_type.0 != 0 && platform_type.0 < filter.platform_type.len() - 1 {
-            platform_string += ", ";
-        } else if platform_type.0 == filter.platform_type.len() - 1 {
-            platform_string += " and ";
-        }
-        match platform_type.1 {
-            PlatformType::Sentinel1A => platform_string += "Sentinel-1A",
-            PlatformType::Sentinel1B => platform_string += "Sentinel-1B",
-            PlatformType::Sentinel1C => platform_string += "Sentinel-1C",
-            PlatformType::Sentinel1D => platform_string += "Sentinel-1D",
-        }
+// Helper Function:
+//     Line listing the options of a field the filter narrows, None if every option is selected
+fn options_to_text<T: FilterOption>(selected: &[T], name: &str) -> Option<String> {
+    if selected.len() >= T::iter().count() {
+        return None;
+    }
+    if selected.is_empty() {
+        return Some(format!("No {name}"));
     }
+    Some(format!(
+        "{} {}",
+        join_list(selected.iter().map(|x| x.label())),
+        plural(name, selected)
+    ))
+}
 
-    let start_year: String = filter
-        .date_range
-        .start
-        .format("%Y-%m-%d")
-        .to_string()
-        .chars()
-        .take_while(|x| *x != '-')
-        .collect();
-    let end_year: String = filter
-        .date_range
-        .end
-        .format("%Y-%m-%d")
-        .to_string()
-        .chars()
-        .take_while(|x| *x != '-')
-        .collect();
+// Helper Function:
+//     Pluralizes a noun for the number of items
+fn plural<T>(noun: &str, items: &[T]) -> String {
+    if items.len() == 1 {
+        noun.to_string()
+    } else {
+        format!("{noun}s")
+    }
+}
 
-    (product_string, platform_string, start_year, end_year)
+// Helper Function:
+//     Joins items as they would be written in a sentence, ie. "a, b and c"
+fn join_list(items: impl Iterator<Item = String>) -> String {
+    let items: Vec<String> = items.collect();
+    match items.as_slice() {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+const fn platform_name(platform_type: PlatformType) -> &'static str {
+    match platform_type {
+        PlatformType::Sentinel1A => "Sentinel-1A",
+        PlatformType::Sentinel1B => "Sentinel-1B",
+        PlatformType::Sentinel1C => "Sentinel-1C",
+        PlatformType::Sentinel1D => "Sentinel-1D",
+        PlatformType::Nisar => "NISAR",
+        PlatformType::Alos2 => "ALOS-2",
+        PlatformType::Ers1 => "ERS-1",
+        PlatformType::Ers2 => "ERS-2",
+    }
 }
 
 /// Tracks setup stage of state and png generations
@@ -370,11 +564,42 @@ pub enum InitStage {
     InProgress,
     Complete,
 }
-/// Contains a tracker for the state of png generation and the image that was generated
+/// Contains a tracker for the state of image generation and the image that was generated
 #[derive(Clone)]
 pub struct ExportContext {
-    pub generate_img: ReadSignal<bool>,
-    pub set_generate_img: leptos::prelude::WriteSignal<bool>,
+    // Format of the image to generate, reset to None once it has started
+    pub generate_img: ReadSignal<Option<ImageFormat>>,
+    pub set_generate_img: leptos::prelude::WriteSignal<Option<ImageFormat>>,
+    pub options: ReadSignal<ExportOptions>,
     pub stage: InitStage,
-    pub base64_png: Option<String>,
+    // What the current or last image was rendered with, it is reused while this is unchanged
+    pub job: Option<ExportJob>,
+    pub image: Option<Vec<u8>>,
+}
+
+/// Format, options, framing and size an export is rendered with
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportJob {
+    pub format: ImageFormat,
+    pub options: ExportOptions,
+    pub frame: Rect,
+    pub size: PhysicalSize<u32>,
+}
+
+impl ExportJob {
+    pub fn new(
+        format: ImageFormat,
+        options: ExportOptions,
+        viewport: Rect,
+        aoi: Option<&MultiPolygon>,
+        limits: ExportLimits,
+    ) -> Self {
+        let frame = export_frame(options.framing, viewport, aoi);
+        Self {
+            format,
+            options,
+            frame,
+            size: export_size(frame, options.width, limits),
+        }
+    }
 }
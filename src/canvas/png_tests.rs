@@ -0,0 +1,293 @@
+//! Tests of composing the exported png from a heatmap read back from the GPU
+use geo::{MultiPolygon, Rect, coord};
+use image::{Rgba, RgbaImage};
+use strum::IntoEnumIterator as _;
+use wasm_bindgen_test::wasm_bindgen_test;
+use winit::dpi::PhysicalSize;
+
+use crate::canvas::png::{
+    ExportJob, ExportLimits, colormap_color, copy_buffer_size, encode_png, export_frame,
+    export_size, filter_to_text, generate_export_image, heatmap_from_texels, legend_ticks,
+};
+use crate::test_support::{colormap, date, filter, options, sentinel1_filter, world};
+use crate::types::{
+    BeamMode, ExportFraming, ExportOptions, ImageFormat, OrbitDirection, PlatformType,
+    Polarization, ProductTypes,
+};
+
+// The map is placed 12pt, this many pixels at 288 DPI, in from the top left corner
+const MARGIN: u32 = 48;
+
+#[wasm_bindgen_test]
+fn export_heatmap_read_as_srgb() {
+    // Rows of the copy buffer are padded to 4 texels, only the 2 by 2 rendered to are read
+    let texel = |value: f32| [value, value, value, 1.0].map(f32::to_le_bytes).concat();
+    let mut texels = Vec::new();
+    for y in 0..2 {
+        for x in 0..4 {
+            // 0.214 is a linear 50% gray, the padding would read as white
+            let value = match (x, y) {
+                (1, 1) => 0.214_041,
+                (0 | 1, _) => 0.0,
+                _ => 1.0,
+            };
+            texels.extend(texel(value));
+        }
+    }
+
+    let heatmap = heatmap_from_texels(&texels, 4, PhysicalSize::new(2, 2));
+    assert_eq!(heatmap.dimensions(), (2, 2));
+    assert_eq!(*heatmap.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    let pixel = heatmap.get_pixel(1, 1).0;
+    assert!(pixel[0].abs_diff(128) <= 1 && pixel[3] == 255);
+}
+
+#[wasm_bindgen_test]
+fn export_image_contains_heatmap() {
+    // A dark globe with a single gray granule pixel
+    let mut heatmap = RgbaImage::from_pixel(512, 256, Rgba([0, 0, 0, 255]));
+    heatmap.put_pixel(100, 50, Rgba([128, 128, 128, 255]));
+
+    let output = generate_export_image(&heatmap, 8.0, &filter(), &colormap(), &options());
+    assert!(output.width() >= heatmap.width() + 2 * MARGIN);
+    assert!(output.height() > heatmap.height() + 2 * MARGIN);
+
+    // White background with a black border around the map
+    assert_eq!(*output.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+    assert_eq!(
+        *output.get_pixel(MARGIN - 1, MARGIN - 1),
+        Rgba([0, 0, 0, 255])
+    );
+
+    // The map is copied in as is
+    assert_eq!(
+        *output.get_pixel(MARGIN + 100, MARGIN + 50),
+        Rgba([128, 128, 128, 255])
+    );
+}
+
+#[wasm_bindgen_test]
+fn export_legend_uses_colormap() {
+    let heatmap = RgbaImage::from_pixel(512, 256, Rgba([0, 0, 0, 255]));
+    let output = generate_export_image(&heatmap, 8.0, &filter(), &colormap(), &options());
+
+    // The legend bar runs from the color of a weight of 1 to the color of the max weight
+    let footer = || {
+        output
+            .enumerate_pixels()
+            .filter(|(_, y, _)| *y > heatmap.height() + MARGIN)
+            .map(|(_, _, pixel)| *pixel)
+    };
+    let lowest = colormap_color(&colormap(), 1.0, 8.0);
+    let highest = colormap_color(&colormap(), 8.0, 8.0);
+    assert_eq!(highest, Rgba([255, 0, 0, 255]));
+    assert!(footer().any(|pixel| pixel == lowest));
+    assert!(footer().any(|pixel| pixel == highest));
+}
+
+#[wasm_bindgen_test]
+fn export_transparent_background() {
+    // Nothing was drawn where there are no acquisitions
+    let heatmap = RgbaImage::new(512, 256);
+    let options = ExportOptions {
+        transparent: true,
+        ..options()
+    };
+    let output = generate_export_image(&heatmap, 8.0, &filter(), &colormap(), &options);
+
+    assert_eq!(*output.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+    assert_eq!(output.get_pixel(MARGIN + 100, MARGIN + 50).0[3], 0);
+    assert_eq!(
+        *output.get_pixel(MARGIN - 1, MARGIN - 1),
+        Rgba([0, 0, 0, 255])
+    );
+}
+
+#[wasm_bindgen_test]
+fn export_text_scales_with_dpi() {
+    let heatmap = RgbaImage::from_pixel(512, 256, Rgba([0, 0, 0, 255]));
+    let low = generate_export_image(&heatmap, 8.0, &filter(), &colormap(), &options());
+    let high = generate_export_image(
+        &heatmap,
+        8.0,
+        &filter(),
+        &colormap(),
+        &ExportOptions {
+            dpi: 576,
+            ..options()
+        },
+    );
+
+    // The map keeps its size while the margins, legend and caption grow with the DPI
+    let low_footer = low.height() - heatmap.height();
+    let high_footer = high.height() - heatmap.height();
+    assert!(high_footer > low_footer * 3 / 2);
+    assert_eq!(
+        *high.get_pixel(2 * MARGIN - 1, 2 * MARGIN - 1),
+        Rgba([0, 0, 0, 255])
+    );
+}
+
+#[wasm_bindgen_test]
+fn export_png_stores_dpi() {
+    let img = RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255]));
+    let bytes = encode_png(&img, 300);
+
+    let reader = png::Decoder::new(bytes.as_slice())
+        .read_info()
+        .expect("Failed to decode png");
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (4, 2));
+    let dims = info.pixel_dims.expect("No resolution was stored");
+    assert_eq!(dims.unit, png::Unit::Meter);
+    // 300 DPI is 11811 pixels per meter
+    assert_eq!((dims.xppu, dims.yppu), (11811, 11811));
+}
+
+#[wasm_bindgen_test]
+fn export_framing() {
+    let viewport = Rect::new(coord! { x: -160.0, y: 50.0 }, coord! { x: -130.0, y: 70.0 });
+    let aoi = MultiPolygon::new(vec![
+        Rect::new(coord! { x: -150.0, y: 60.0 }, coord! { x: -140.0, y: 65.0 }).to_polygon(),
+    ]);
+
+    assert_eq!(
+        export_frame(ExportFraming::World, viewport, Some(&aoi)),
+        world()
+    );
+    assert_eq!(
+        export_frame(ExportFraming::Viewport, viewport, None),
+        viewport
+    );
+    // The area of interest is padded by 5% and the whole world is used without one
+    assert_eq!(
+        export_frame(ExportFraming::Aoi, viewport, Some(&aoi)),
+        Rect::new(
+            coord! { x: -150.5, y: 59.75 },
+            coord! { x: -139.5, y: 65.25 }
+        )
+    );
+    assert_eq!(export_frame(ExportFraming::Aoi, viewport, None), world());
+
+    // Padding stops at the edge of the globe
+    let edge = MultiPolygon::new(vec![
+        Rect::new(coord! { x: 170.0, y: 80.0 }, coord! { x: 180.0, y: 90.0 }).to_polygon(),
+    ]);
+    let frame = export_frame(ExportFraming::Aoi, viewport, Some(&edge));
+    assert_eq!((frame.max().x, frame.max().y), (180.0, 90.0));
+}
+
+// Only the size of textures is limited
+fn limits(max_dimension: u32) -> ExportLimits {
+    ExportLimits {
+        max_dimension,
+        max_buffer_size: u64::MAX,
+    }
+}
+
+#[wasm_bindgen_test]
+fn export_size_keeps_aspect_ratio() {
+    assert_eq!(
+        export_size(world(), 3000, limits(8192)),
+        (3000, 1500).into()
+    );
+
+    // A tall frame is shrunk until it fits within the largest texture
+    let tall = Rect::new(coord! { x: 0.0, y: -80.0 }, coord! { x: 10.0, y: 80.0 });
+    assert_eq!(export_size(tall, 1000, limits(4096)), (256, 4096).into());
+    assert_eq!(
+        export_size(world(), 10_000, limits(4096)),
+        (4096, 2048).into()
+    );
+
+    // The copy texture is padded to a multiple of 256 wide, which must fit as well
+    assert_eq!(export_size(world(), 4000, limits(4000)).width, 3840);
+
+    let job = ExportJob::new(
+        ImageFormat::Png,
+        ExportOptions {
+            width: 1200,
+            framing: ExportFraming::Viewport,
+            ..ExportOptions::default()
+        },
+        Rect::new(coord! { x: -160.0, y: 50.0 }, coord! { x: -130.0, y: 70.0 }),
+        None,
+        limits(8192),
+    );
+    assert_eq!(job.size, (1200, 800).into());
+}
+
+#[wasm_bindgen_test]
+fn export_size_fits_copy_buffer() {
+    // WebGL2 only guarantees buffers of 256 MiB, the whole world 8192 wide needs 512 MiB
+    let webgl = ExportLimits {
+        max_dimension: 8192,
+        max_buffer_size: 1 << 28,
+    };
+    let size = export_size(world(), 8192, webgl);
+    assert!(copy_buffer_size(size.width, size.height) <= webgl.max_buffer_size);
+    assert!(size.width > 5600);
+    assert_eq!(size.height, size.width.div_ceil(2));
+
+    assert_eq!(webgl.max_width(), size.width);
+    assert_eq!(limits(2048).max_width(), 2048);
+
+    // Adapters allowing larger buffers are held to the same budget
+    let adapter = ExportLimits::from(&wgpu::Limits {
+        max_texture_dimension_2d: 8192,
+        max_buffer_size: u64::MAX,
+        ..wgpu::Limits::downlevel_webgl2_defaults()
+    });
+    assert_eq!(adapter, webgl);
+}
+
+#[wasm_bindgen_test]
+fn export_legend_ticks() {
+    assert_eq!(legend_ticks(0.0), vec![1]);
+    assert_eq!(legend_ticks(3.0), vec![1, 2, 3]);
+    assert_eq!(legend_ticks(8.0), vec![1, 3, 5, 6, 8]);
+    assert_eq!(legend_ticks(100.0), vec![1, 25, 50, 75, 100]);
+}
+
+#[wasm_bindgen_test]
+fn export_caption_describes_filter() {
+    // Fields with every option selected are left out
+    assert_eq!(
+        filter_to_text(&sentinel1_filter(date(2026, 6, 1), date(2026, 7, 1))),
+        vec![
+            "Sentinel-1A, Sentinel-1B, Sentinel-1C and Sentinel-1D GRD, SLC and OCN acquisitions",
+            "2026-06-01 to 2026-07-01",
+        ]
+    );
+
+    let mut filter = filter();
+    assert_eq!(
+        filter_to_text(&filter),
+        vec![
+            "Sentinel-1A and Sentinel-1C GRD and SLC acquisitions",
+            "2026-06-01 to 2026-07-01",
+            "IW beam mode",
+            "DV polarization",
+        ]
+    );
+
+    filter.platform_type.push(PlatformType::Nisar);
+    filter.product_type = vec![ProductTypes::GroundRangeDetected];
+    filter.beam_mode.push(BeamMode::ExtraWideSwath);
+    filter.polarization = Polarization::iter().collect();
+    filter.orbit_direction = Some(OrbitDirection::Ascending);
+    filter.relative_orbit = vec![12..=12, 40..=45];
+    let aoi = Rect::new(coord! { x: -149.0, y: 61.0 }, coord! { x: -146.0, y: 64.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+    assert_eq!(
+        filter_to_text(&filter),
+        vec![
+            "Sentinel-1A, Sentinel-1C and NISAR GRD acquisitions",
+            "2026-06-01 to 2026-07-01",
+            "IW and EW beam modes",
+            "Ascending passes",
+            "Relative orbits 12 and 40-45",
+            "Inside of the area of interest",
+        ]
+    );
+}
@@ -1,6 +1,7 @@
 use std::rc::Rc;
 use std::sync::Arc;
 
+use image::RgbaImage;
 use leptos::logging::log;
 use winit::window::Window;
 use winit::{dpi::PhysicalSize, event_loop::EventLoopProxy};
@@ -13,9 +14,14 @@ use super::pipeline::{
     generate_export_pipeline, generate_outline_pipeline,
 };
 use super::texture::{
-    TextureContext, generate_blend_texture, generate_colormaps, generate_copy_texture,
-    generate_export_texture,
+    TextureContext, generate_blend_texture, generate_colormap_texture, generate_copy_texture,
+    generate_export_texture, upload_colormap,
 };
+use crate::colormap::Colormap;
+
+// Colors the world outline and the area of interest are drawn in, as linear RGBA
+const OUTLINE_COLOR: [f32; 4] = [0.03, 0.03, 0.03, 1.0];
+const AOI_COLOR: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
 
 // Stores all the things we need to set up wgpu and run render passes,
 pub struct RenderContext<'a> {
@@ -29,10 +35,16 @@ pub struct RenderContext<'a> {
     pub display_colormap_render_pipeline: wgpu::RenderPipeline,
     pub export_colormap_render_pipeline: wgpu::RenderPipeline,
     pub outline_render_pipeline: wgpu::RenderPipeline,
+    pub outline_color: BufferContext,
+    // Shares the outline shader, lines need a pipeline of their own as topology is part of it
+    pub aoi_render_pipeline: wgpu::RenderPipeline,
+    pub aoi_color: BufferContext,
     pub export_render_pipeline: wgpu::RenderPipeline,
     pub camera_context: CameraContext,
     pub blend_texture_context: TextureContext,
     pub colormap_texture_context: TextureContext,
+    // Colors uploaded to the colormap texture, exports read their legend from it
+    pub colormap: Arc<RgbaImage>,
     pub export_texture_context: TextureContext,
     pub copy_context: CopyContext,
     pub max_weight_context: MaxWeightContext,
@@ -84,8 +96,10 @@ pub async fn generate_render_context(
                 required_features: wgpu::Features::empty(),
                 // WebGL doesn't support all of wgpu's features, so if
                 // we're building for the web we'll have to disable some.
+                // The defaults cap textures at 2048, ask for what the adapter supports so exports
+                // can be larger
                 required_limits: if cfg!(target_arch = "wasm32") {
-                    wgpu::Limits::downlevel_webgl2_defaults()
+                    wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
                 } else {
                     wgpu::Limits::default()
                 },
@@ -128,20 +142,16 @@ pub async fn generate_render_context(
     // Used to modify the displayed viewport, ie zoom and pan
     let camera_context = CameraContext::generate_camera_context(&device, &config);
 
-    // Used to convert polygons into heatmap
-    let blend_texture_context = generate_blend_texture(&device, size);
-    let colormap_texture_context = generate_colormaps(&device, &queue);
-    let export_texture_context = generate_export_texture(&device, size);
-
-    // Used to get data from GPU to CPU
-    let copy_texture = generate_copy_texture(&device, size);
-    let copy_buffer = generate_copy_buffer(&device, size);
-
-    let copy_context = CopyContext {
-        texture: copy_texture,
-        buffer: copy_buffer,
-        buffer_mapped: false,
-    };
+    // Used to convert polygons into heatmap and to get data from GPU to CPU
+    let RenderTargets {
+        blend_texture_context,
+        export_texture_context,
+        copy_context,
+        ..
+    } = RenderTargets::new(&device, size);
+    // The colormap the user picked is set once the event loop receives this context
+    let colormap = Colormap::default().image;
+    let colormap_texture_context = generate_colormap_texture(&device, &queue, &colormap);
 
     // Used to pass calculated max weight into Render Pass
     let max_weight_context = MaxWeightContext {
@@ -173,7 +183,26 @@ pub async fn generate_render_context(
         ),
         &config,
     );
-    let outline_render_pipeline = generate_outline_pipeline(&device, &camera_context);
+    let outline_color = generate_uniform_buffer(&device);
+    queue.write_buffer(
+        &outline_color.buffer,
+        0,
+        bytemuck::cast_slice(&OUTLINE_COLOR),
+    );
+    let outline_render_pipeline = generate_outline_pipeline(
+        &device,
+        &camera_context,
+        &outline_color.bind_group_layout,
+        wgpu::PrimitiveTopology::TriangleList,
+    );
+    let aoi_color = generate_uniform_buffer(&device);
+    queue.write_buffer(&aoi_color.buffer, 0, bytemuck::cast_slice(&AOI_COLOR));
+    let aoi_render_pipeline = generate_outline_pipeline(
+        &device,
+        &camera_context,
+        &aoi_color.bind_group_layout,
+        wgpu::PrimitiveTopology::LineList,
+    );
     let export_render_pipeline =
         generate_export_pipeline(&device, &export_texture_context.bind_group_layout);
 
@@ -189,10 +218,14 @@ pub async fn generate_render_context(
         display_colormap_render_pipeline,
         export_colormap_render_pipeline,
         outline_render_pipeline,
+        outline_color,
+        aoi_render_pipeline,
+        aoi_color,
         export_render_pipeline,
         camera_context,
         blend_texture_context,
         colormap_texture_context,
+        colormap,
         export_texture_context,
         copy_context,
         max_weight_context,
@@ -204,6 +237,70 @@ pub async fn generate_render_context(
     let _ = event_loop_proxy.send_event(UserMessage::StateMessage(Box::new(message)));
 }
 
+impl RenderContext<'_> {
+    /// Draw the heatmap with another colormap, the pipelines are kept as the texture is bound
+    ///     with the same layout
+    pub fn set_colormap(&mut self, colormap: Arc<RgbaImage>) {
+        let (texture, bind_group) = upload_colormap(
+            &self.device,
+            &self.queue,
+            &self.colormap_texture_context.bind_group_layout,
+            &colormap,
+        );
+        self.colormap_texture_context.texture = texture;
+        self.colormap_texture_context.bind_group = bind_group;
+        self.colormap = colormap;
+    }
+
+    /// Render to the passed targets instead of the current ones, which are returned, this does not
+    ///     touch the surface so the heatmap can be rendered off-screen at another size
+    pub fn swap_targets(&mut self, targets: RenderTargets) -> RenderTargets {
+        RenderTargets {
+            size: std::mem::replace(&mut self.size, targets.size),
+            blend_texture_context: std::mem::replace(
+                &mut self.blend_texture_context,
+                targets.blend_texture_context,
+            ),
+            export_texture_context: std::mem::replace(
+                &mut self.export_texture_context,
+                targets.export_texture_context,
+            ),
+            copy_context: std::mem::replace(&mut self.copy_context, targets.copy_context),
+        }
+    }
+}
+
+/// The textures a heatmap of a given size is rendered to, these are swapped out while an export
+///     renders off-screen
+pub struct RenderTargets {
+    pub size: PhysicalSize<u32>,
+    pub blend_texture_context: TextureContext,
+    pub export_texture_context: TextureContext,
+    pub copy_context: CopyContext,
+}
+
+impl RenderTargets {
+    pub fn new(device: &wgpu::Device, size: PhysicalSize<u32>) -> Self {
+        // The copy buffer matches the copy texture which is padded to a multiple of 256
+        let copy_texture = generate_copy_texture(device, size);
+        let copy_buffer = generate_copy_buffer(
+            device,
+            PhysicalSize::new(copy_texture.width(), copy_texture.height()),
+        );
+
+        Self {
+            size,
+            blend_texture_context: generate_blend_texture(device, size),
+            export_texture_context: generate_export_texture(device, size),
+            copy_context: CopyContext {
+                texture: copy_texture,
+                buffer: copy_buffer,
+                buffer_mapped: false,
+            },
+        }
+    }
+}
+
 /// Contains a texture and buffer used to map a texture onto the CPU
 pub struct CopyContext {
     pub texture: wgpu::Texture,
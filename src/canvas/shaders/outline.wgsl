@@ -6,6 +6,10 @@ struct CameraUniform {
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+// Color the mesh is filled or outlined with
+@group(1) @binding(0)
+var<uniform> color: vec4<f32>;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) weight: u32,
@@ -30,6 +34,6 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(0.03, 0.03, 0.03, 1.0);
+    return color;
 }
 
@@ -1,45 +1,104 @@
 // Contains the state struct which stores information needed for wgpu
 //  to render a shader
 
+use std::rc::Rc;
 use std::sync::Arc;
 
+use geo::{Coord, MultiPolygon};
 use leptos::logging::log;
-use leptos::prelude::GetUntracked;
+use leptos::prelude::{GetUntracked, Set as _, WithUntracked as _};
 use wgpu::{BindGroup, Extent3d, Origin3d};
 use winit::dpi::PhysicalSize;
 use winit::event::WindowEvent;
 use winit::event_loop::EventLoopProxy;
 use winit::window::Window;
 
+use super::aoi::{AoiOverlay, drawn_aoi};
 use super::app::UserMessage;
 use super::camera::{Camera, CameraEvent};
-use super::geometry::{Geometry, generate_copy_buffer};
+use super::geometry::{BufferLayer, Geometry};
+use super::hover::HoverReadback;
 use super::input::InputState;
-use super::render_context::{CopyContext, MaxWeightState, RenderContext};
-use super::texture::{generate_blend_texture, generate_copy_texture, generate_export_texture};
-use crate::canvas::png::{ExportContext, InitStage};
-use crate::types;
+use super::render_context::{MaxWeightState, RenderContext, RenderTargets};
+use crate::canvas::png::{ExportContext, ExportJob, ExportLimits, InitStage};
+use crate::colormap::Colormap;
+use crate::ingest::load::BufferStorage;
+use crate::ingest::lod::LodConfig;
+use crate::types::{self, ImageFormat};
 
 /// Stores the information needed to draw to a surface with a shader
 #[derive(Default)]
 pub struct State<'a> {
     pub render_context: Option<RenderContext<'a>>,
     pub geometry: Option<Geometry>,
+    // Most detailed world outline, kept on the CPU for the SVG export
+    pub outline_mesh: Option<BufferStorage>,
     pub window: Option<Arc<Window>>,
     pub input: InputState,
     pub init_stage: InitStage,
     pub event_loop_proxy: Option<EventLoopProxy<UserMessage<'static>>>,
     pub filter: Option<leptos::prelude::ReadSignal<types::Filter>>,
+    pub colormap: Option<leptos::prelude::ReadSignal<Colormap>>,
     pub camera_storage: Option<Camera>,
-    pub size_storage: Option<PhysicalSize<u32>>,
+    // Targets the heatmap is rendered to on screen while an export renders off-screen
+    pub target_storage: Option<RenderTargets>,
     pub export_context: Option<ExportContext>,
     pub set_max_weight: Option<leptos::prelude::WriteSignal<u32>>,
+    pub set_aoi: Option<leptos::prelude::WriteSignal<Option<MultiPolygon>>>,
+    pub aoi_overlay: AoiOverlay,
+    pub set_inspect_point: Option<leptos::prelude::WriteSignal<Option<Coord>>>,
+    pub hover: HoverReadback,
+    pub set_hover: Option<leptos::prelude::WriteSignal<Option<types::HoverInfo>>>,
+    pub set_granule_export:
+        Option<leptos::prelude::WriteSignal<Option<(types::GranuleFormat, geo::Rect)>>>,
+    pub lod_config: Rc<LodConfig>,
 }
 
 impl State<'_> {
     // Process any user input on the heatmap
     pub fn handle_input_event(&mut self, event: WindowEvent) {
+        // The tooltip follows the cursor and is hidden once it leaves the canvas
+        match event {
+            WindowEvent::CursorMoved { .. } => self.hover.cursor_over_canvas = true,
+            WindowEvent::CursorLeft { .. } => {
+                self.hover.cursor_over_canvas = false;
+                if let Some(set_hover) = self.set_hover {
+                    set_hover.set(None);
+                }
+            }
+            _ => (),
+        }
+
         self.input.eat_event(event);
+
+        // An area of interest was finished, it is converted to lon/lat with the camera the user sees
+        if let Some(aoi) = self.input.consume_aoi()
+            && let Some(render_context) = self.render_context.as_ref()
+            && let Some(set_aoi) = self.set_aoi
+        {
+            let camera = self
+                .camera_storage
+                .as_ref()
+                .unwrap_or(&render_context.camera_context.camera);
+            set_aoi.set(drawn_aoi(&aoi, camera));
+        }
+
+        // A click on the map inspects the granules at that point, clicks are ignored while the
+        //     camera is swapped out for an export
+        if let Some(click) = self.input.consume_click()
+            && self.camera_storage.is_none()
+            && let Some(render_context) = self.render_context.as_ref()
+            && let Some(set_inspect_point) = self.set_inspect_point
+        {
+            let camera_context = &render_context.camera_context;
+            let offset = camera_context.mouse_coordinate_convert((click.x, click.y).into());
+            let lon = camera_context.camera.position.x - offset.x;
+            let lat = camera_context.camera.position.y - offset.y;
+
+            if (-180.0..=180.0).contains(&lon) && (-90.0..=90.0).contains(&lat) {
+                set_inspect_point.set(Some(Coord { x: lon, y: lat }));
+            }
+        }
     }
 
     // Configures the surface based on the passed physical size
@@ -64,8 +123,6 @@ impl State<'_> {
                 new_size.height = render_context.limits.max_texture_dimension_2d;
             }
 
-            render_context.size = new_size;
-
             let mut config = render_context.config.clone();
             config.width = new_size.width;
             config.height = new_size.height;
@@ -75,18 +132,15 @@ impl State<'_> {
                 .surface
                 .configure(&render_context.device, &render_context.config);
 
-            // Textures must be the same size as the window to preserve resolution
-            render_context.blend_texture_context =
-                generate_blend_texture(&render_context.device, new_size);
-
-            render_context.copy_context = CopyContext {
-                texture: generate_copy_texture(&render_context.device, new_size),
-                buffer: generate_copy_buffer(&render_context.device, new_size),
-                buffer_mapped: false,
-            };
-
-            render_context.export_texture_context =
-                generate_export_texture(&render_context.device, new_size);
+            // Textures must be the same size as the window to preserve resolution, while an export
+            //     renders off-screen they replace the ones restored once it is done
+            let targets = RenderTargets::new(&render_context.device, new_size);
+            if let Some(target_storage) = self.target_storage.as_mut() {
+                *target_storage = targets;
+            } else {
+                render_context.swap_targets(targets);
+                self.hover.reset();
+            }
 
             log!("New Size: {new_size:?}");
         }
@@ -94,40 +148,59 @@ impl State<'_> {
 
     /// Renders the contents of state to the canvas
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        // Check if we are generating a png for the current set of data,
-        // if we are we resize the canvas to increase the resolution of
-        // the generated image, resize() takes a mut& self so we must do this before
-        // getting a mut& render_context which is why this check occurs here
+        // Check if we are generating a png for the current set of data, if we are the heatmap is
+        //     rendered off-screen to textures of the export size, the targets on screen are
+        //     restored once the png has been read back. The copy buffer must not be mapped while
+        //     it is swapped out so this waits for the max weight and hover readbacks
         if let Some(export) = &self.export_context
-            && export.stage == InitStage::Incomplete
+            && export.stage != InitStage::InProgress
             && self.geometry.is_some()
-            && export.generate_img.get_untracked()
+            && let Some(format) = export.generate_img.get_untracked()
+            && let Some(render_context) = self.render_context.as_mut()
+            && render_context.max_weight_context.state != MaxWeightState::InProgress
+            && !render_context.copy_context.buffer_mapped
         {
-            (export.set_generate_img)(false);
-            let width = self
-                .render_context
-                .as_ref()
-                .expect("Failed to get render context while getting max texture dims")
-                .limits
-                .max_texture_dimension_2d;
-            let height = (width as f32 / (16.0 / 9.0)) as u32;
-
-            // Resize our canvas to the highest resolution the GPU can support
-            let new_size = PhysicalSize::<u32>::new(width, height);
+            (export.set_generate_img)(None);
 
-            self.size_storage = Some(
-                self.render_context
-                    .as_ref()
-                    .expect("Failed to get render context to store size")
-                    .size,
+            // The export is framed with the camera the user sees
+            let viewport = self
+                .camera_storage
+                .as_ref()
+                .unwrap_or(&render_context.camera_context.camera)
+                .world_bounds();
+            let aoi = self
+                .filter
+                .and_then(|filter| filter.with_untracked(|filter| filter.aoi.clone()));
+            let job = ExportJob::new(
+                format,
+                export.options.get_untracked(),
+                viewport,
+                aoi.as_ref(),
+                ExportLimits::from(&render_context.limits),
             );
 
-            self.resize(new_size);
+            // The image for this data was generated with the same options already, download it again
+            if export.stage == InitStage::Complete && export.job.as_ref() == Some(&job) {
+                let _ = self
+                    .event_loop_proxy
+                    .as_ref()
+                    .expect("Failed to get event loop proxy")
+                    .send_event(UserMessage::ExportMapped);
+            } else {
+                log!("Rendering {:?} export of {:?}", job.size, job.frame);
+                let on_screen = render_context
+                    .swap_targets(RenderTargets::new(&render_context.device, job.size));
+                self.target_storage.get_or_insert(on_screen);
+                self.hover.reset();
 
-            self.export_context
-                .as_mut()
-                .expect("Failed to get export context to update png_generated")
-                .stage = InitStage::InProgress;
+                let export = self
+                    .export_context
+                    .as_mut()
+                    .expect("Failed to get export context to update png_generated");
+                export.stage = InitStage::InProgress;
+                export.job = Some(job);
+                export.image = None;
+            }
         }
 
         // Exit Render function if there is no geometry to render
@@ -135,6 +208,21 @@ impl State<'_> {
             return Ok(());
         };
 
+        // Outline the area of interest, this is done before the camera is moved for the max weight or export pass
+        if let Some(render_context) = self.render_context.as_ref() {
+            let aoi = self
+                .filter
+                .and_then(|filter| filter.with_untracked(|filter| filter.aoi.clone()));
+            self.aoi_overlay.update(
+                render_context,
+                self.camera_storage
+                    .as_ref()
+                    .unwrap_or(&render_context.camera_context.camera),
+                &self.input,
+                aoi.as_ref(),
+            );
+        }
+
         let render_context = self
             .render_context
             .as_mut()
@@ -163,15 +251,18 @@ impl State<'_> {
                 .camera_context
                 .run_camera_logic(&mut self.input);
 
-            // If we have not calculated the max weight or generated the png set the camera
-            // to cover the entire screen, save the old camera
+            // Region of the world the png being generated shows
+            let export_frame = self
+                .export_context
+                .as_ref()
+                .filter(|export| export.stage == InitStage::InProgress)
+                .and_then(|export| export.job.as_ref())
+                .map(|job| job.frame);
+
+            // If we have not calculated the max weight set the camera to cover the entire screen,
+            // if we are generating a png set it to the export's frame, save the old camera
             if render_context.max_weight_context.state == MaxWeightState::Empty
-                || self
-                    .export_context
-                    .as_ref()
-                    .expect("failed to get export context from state")
-                    .stage
-                    == InitStage::InProgress
+                || export_frame.is_some()
             {
                 // Save values that will be changed
                 if self.camera_storage.is_none() {
@@ -180,7 +271,23 @@ impl State<'_> {
 
                 render_context
                     .camera_context
-                    .update_camera(&CameraEvent::EntireView);
+                    .update_camera(&CameraEvent::Resize(
+                        render_context.size.width,
+                        render_context.size.height,
+                    ));
+
+                // The max weight is always read from the entire world
+                if let Some(frame) = export_frame
+                    && render_context.max_weight_context.state != MaxWeightState::Empty
+                {
+                    render_context
+                        .camera_context
+                        .update_camera(&CameraEvent::Frame(frame));
+                } else {
+                    render_context
+                        .camera_context
+                        .update_camera(&CameraEvent::EntireView);
+                }
             }
             // Restore any saved values
             else if self.camera_storage.is_some() {
@@ -191,28 +298,13 @@ impl State<'_> {
                     .clone();
                 self.camera_storage = None;
             }
-            // Size is only changed on export so we need a seperate check to not break on max weight pass
-            if let Some(size_storage) = self.size_storage {
-                render_context.size = size_storage;
-                self.camera_storage = None;
-            }
-
             render_context
                 .camera_context
                 .write_camera_buffer(render_context);
 
             // Select the Level of Detail to use for the satellite granules based on the zoom
             let zoom = render_context.camera_context.camera.zoom;
-            let mut active_blend_layer = &geometry.lod_layers[0];
-            match zoom {
-                15.0..30.0 => {
-                    active_blend_layer = &geometry.lod_layers[1];
-                }
-                0.0..15.0 => {
-                    active_blend_layer = &geometry.lod_layers[2];
-                }
-                _ => (),
-            }
+            let active_blend_layer = select_lod_layer(&geometry.lod_layers, &self.lod_config, zoom);
 
             // Configure render pass and set pipeline, bind groups, vertex buffer, and index buffer
             let mut blend_render_pass =
@@ -387,6 +479,12 @@ impl State<'_> {
             let mut colormap_output: Option<wgpu::SurfaceTexture> = None;
             let active_colormap: &BindGroup;
             let active_colormap_render_pipeline: &wgpu::RenderPipeline;
+            let mut clear_color = wgpu::Color {
+                r: 0.02,
+                g: 0.02,
+                b: 0.02,
+                a: 1.0,
+            };
 
             // Draw to the export context texture if we have not generated a png yet
             if let Some(export) = &self.export_context
@@ -400,7 +498,16 @@ impl State<'_> {
                 active_colormap = &render_context.colormap_texture_context.bind_group;
                 active_colormap_render_pipeline = &render_context.export_colormap_render_pipeline;
 
-                log!("Generating .png");
+                // A transparent export leaves out the background behind the world outline
+                if export
+                    .job
+                    .as_ref()
+                    .is_some_and(|job| job.options.transparent)
+                {
+                    clear_color = wgpu::Color::TRANSPARENT;
+                }
+
+                log!("Generating export image");
             } else {
                 // We will draw to the surface of the window, this is displayed in the HtmlElement
                 colormap_output = Some(render_context.surface.get_current_texture()?);
@@ -424,16 +531,8 @@ impl State<'_> {
             // Select the level of detail for the world outline
             {
                 let zoom = render_context.camera_context.camera.zoom;
-                let mut active_outline_layer = &geometry.outline_layers[0];
-                match zoom {
-                    15.0..30.0 => {
-                        active_outline_layer = &geometry.outline_layers[1];
-                    }
-                    0.0..15.0 => {
-                        active_outline_layer = &geometry.outline_layers[2];
-                    }
-                    _ => (),
-                }
+                let active_outline_layer =
+                    select_lod_layer(&geometry.outline_layers, &self.lod_config, zoom);
 
                 let mut color_render_pass =
                     colormap_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -442,12 +541,7 @@ impl State<'_> {
                             view: &color_view,
                             resolve_target: None,
                             ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color {
-                                    r: 0.02,
-                                    g: 0.02,
-                                    b: 0.02,
-                                    a: 1.0,
-                                }),
+                                load: wgpu::LoadOp::Clear(clear_color),
                                 store: wgpu::StoreOp::Store,
                             },
                         })],
@@ -464,6 +558,7 @@ impl State<'_> {
                     &render_context.camera_context.camera_bind_group,
                     &[],
                 );
+                color_render_pass.set_bind_group(1, &render_context.outline_color.bind_group, &[]);
 
                 color_render_pass
                     .set_vertex_buffer(0, active_outline_layer.vertex_buffer.slice(..));
@@ -494,17 +589,66 @@ impl State<'_> {
                     wgpu::IndexFormat::Uint16,
                 );
                 color_render_pass.draw_indexed(0..geometry.rectangle_layer.num_indices, 0, 0..1);
+
+                // Outline the area of interest over the heatmap
+                if let Some(aoi_layer) = self.aoi_overlay.layer.as_ref() {
+                    color_render_pass.set_pipeline(&render_context.aoi_render_pipeline);
+                    color_render_pass.set_bind_group(
+                        0,
+                        &render_context.camera_context.camera_bind_group,
+                        &[],
+                    );
+                    color_render_pass.set_bind_group(1, &render_context.aoi_color.bind_group, &[]);
+                    color_render_pass.set_vertex_buffer(0, aoi_layer.vertex_buffer.slice(..));
+                    color_render_pass.set_index_buffer(
+                        aoi_layer.index_buffer.slice(..),
+                        wgpu::IndexFormat::Uint32,
+                    );
+                    color_render_pass.draw_indexed(0..aoi_layer.num_indices, 0, 0..1);
+                }
             }
 
             render_context
                 .queue
                 .submit(std::iter::once(colormap_encoder.finish()));
 
-            ////////////////////////////
-            // Export PNG Render pass //
-            ////////////////////////////
-            // Runs if a png has not yet been generated and the copy_context buffer is
-            //   not mapped to the cpu. This does essentially the same thing as the max weight render
+            /////////////////////////
+            // Hover Readback Pass //
+            /////////////////////////
+            // Copies the weight under the cursor to the CPU for the tooltip, skipped while the
+            //   copy buffer is in use or the camera has been swapped out for an export
+
+            let exporting = self
+                .export_context
+                .as_ref()
+                .is_some_and(|export| export.stage == InitStage::InProgress);
+            if !exporting
+                && self.camera_storage.is_none()
+                && !render_context.copy_context.buffer_mapped
+                && self.hover.is_due(
+                    self.input.cursor_position,
+                    &render_context.camera_context.camera,
+                )
+                && !self.hover.start(
+                    render_context,
+                    geometry,
+                    self.input.cursor_position,
+                    self.event_loop_proxy
+                        .as_ref()
+                        .expect("Failed to get event loop proxy when reading hover weight")
+                        .clone(),
+                )
+                && let Some(set_hover) = self.set_hover
+            {
+                set_hover.set(None);
+            }
+
+            //////////////////////////////
+            // Export Image Render pass //
+            //////////////////////////////
+            // Runs if an image has not yet been generated and the copy_context buffer is
+            //   not mapped to the cpu. A png copies the colored heatmap while a GeoTIFF copies the
+            //   weights of the blend texture. This does essentially the same thing as the max weight render
             //   we should look into removing this render pass in favor of reusing the max weight pipeline
             //   to simplify the code, could break the pass out into a copy_pass() function
 
@@ -547,12 +691,26 @@ impl State<'_> {
                             timestamp_writes: None,
                         });
 
-                    export_render_pass.set_pipeline(&render_context.export_render_pipeline);
-                    export_render_pass.set_bind_group(
-                        0,
-                        &render_context.export_texture_context.bind_group,
-                        &[],
+                    // The copy texture is padded to a multiple of 256, the png is cropped to the
+                    //     part of it the size of the export so nothing is stretched into the padding
+                    export_render_pass.set_viewport(
+                        0.0,
+                        0.0,
+                        render_context.size.width as f32,
+                        render_context.size.height as f32,
+                        0.0,
+                        1.0,
                     );
+                    export_render_pass.set_pipeline(&render_context.export_render_pipeline);
+                    // A GeoTIFF or SVG is read from the weights rather than the colored heatmap
+                    let export_source = if export.job.as_ref().is_some_and(|job| {
+                        matches!(job.format, ImageFormat::GeoTiff | ImageFormat::Svg)
+                    }) {
+                        &render_context.blend_texture_context.bind_group
+                    } else {
+                        &render_context.export_texture_context.bind_group
+                    };
+                    export_render_pass.set_bind_group(0, export_source, &[]);
                     export_render_pass
                         .set_vertex_buffer(0, geometry.rectangle_layer.vertex_buffer.slice(..));
                     export_render_pass.set_index_buffer(
@@ -637,19 +795,6 @@ impl State<'_> {
                     },
                 );
             }
-            // If we have already generated a png then skip the export pass and just fire off the ExportMapped event
-            else if let Some(export) = &self.export_context
-                && export.stage == InitStage::Complete
-                && export.generate_img.get_untracked()
-            {
-                (export.set_generate_img)(false);
-                let _ = self
-                    .event_loop_proxy
-                    .as_ref()
-                    .expect("Failed to get event loop proxy")
-                    .send_event(UserMessage::ExportMapped);
-            }
-
             // If we rendered onto the winit surface display the render
             if let Some(output) = colormap_output {
                 output.present();
@@ -658,3 +803,13 @@ impl State<'_> {
         Ok(())
     }
 }
+
+/// Select the layer for the passed zoom, clamped to the number of layers that were meshed
+fn select_lod_layer<'a>(
+    layers: &'a [BufferLayer],
+    lod_config: &LodConfig,
+    zoom: f64,
+) -> &'a BufferLayer {
+    let index = lod_config.select(zoom).min(layers.len().saturating_sub(1));
+    &layers[index]
+}
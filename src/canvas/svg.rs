@@ -0,0 +1,451 @@
+// Writes the heatmap as an SVG so it can be scaled and edited for print, the blended weights are
+//     dissolved into one polygon for each number of acquisitions as SVG has no way to sum the
+//     overlapping granule triangles, the world outline, legend and caption are drawn as vectors
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use geo::{Coord, MultiPolygon, Rect};
+use image::{Rgba, RgbaImage};
+use leptos::logging::log;
+
+use super::geotiff::{WeightGrid, read_weight_grid};
+use super::png::{
+    BORDER, CAPTION_FONT_SIZE, ExportJob, HEADER_FONT_SIZE, LABEL_FONT_SIZE, LEGEND_BAR_HEIGHT,
+    LEGEND_MAX_WIDTH, MARGIN, colormap_color, filter_to_text, legend_ticks, points_to_pixels,
+};
+use super::render_context::RenderContext;
+use crate::ingest::load::BufferStorage;
+use crate::types::{ExportOptions, Filter};
+use crate::ui::popup::escape_html;
+
+// Colors of the map on screen in sRGB, the linear colors of outline.wgsl and aoi.wgsl and the
+//     clear color of the colormap pass
+const BACKGROUND_COLOR: &str = "#272727";
+const OUTLINE_COLOR: &str = "#303030";
+const AOI_COLOR: &str = "#f9f9f9";
+
+// Largest SVG written, past this noisy maps have more outlines than browsers and editors can open
+const MAX_SVG_SIZE: usize = 64_000_000;
+
+// Stops of the gradient running through the colormap in the legend bar
+const LEGEND_STOPS: u32 = 64;
+
+// Text is not measured, it is laid out assuming characters are about this fraction of the font
+//     size wide and lines are this multiple of the font size tall
+const CHARACTER_WIDTH: f32 = 0.55;
+const LINE_HEIGHT: f32 = 1.2;
+
+/// Read the weights the export pass copied to the buffer and write them as an SVG of the
+///     exported frame with the world outline, a legend and a caption describing the filter, or a
+///     message if it would be too large to download
+pub fn generate_svg(
+    render_context: &mut RenderContext,
+    job: &ExportJob,
+    filter: &Filter,
+    outline: Option<&BufferStorage>,
+) -> Result<Vec<u8>, String> {
+    let grid = read_weight_grid(render_context, job.frame);
+    let max_weight = render_context
+        .max_weight_context
+        .value
+        .expect("Failed to get max weight to generate output svg");
+
+    let svg = compose_svg(
+        &grid,
+        max_weight,
+        filter,
+        &render_context.colormap,
+        &job.options,
+        outline,
+        MAX_SVG_SIZE,
+    )?;
+    log!("Composed {} byte export svg", svg.len());
+
+    Ok(svg.into_bytes())
+}
+
+/// Lay out the map with a legend and a caption under it like the exported png, sizes are in
+///     pixels at the DPI of the export and the document is sized in inches so it prints the same.
+///     Fails if the outlines of the map grow past `max_size` bytes
+pub fn compose_svg(
+    grid: &WeightGrid,
+    max_weight: f32,
+    filter: &Filter,
+    colormap: &RgbaImage,
+    options: &ExportOptions,
+    outline: Option<&BufferStorage>,
+    max_size: usize,
+) -> Result<String, String> {
+    let margin = points_to_pixels(MARGIN, options.dpi);
+    let border = points_to_pixels(BORDER, options.dpi);
+    let map_width = grid.size.width;
+    let map_height = grid.size.height;
+
+    let legend = Legend::new(max_weight, map_width, options.dpi);
+    let caption = filter_to_text(filter);
+    let caption_font_size = points_to_pixels(CAPTION_FONT_SIZE, options.dpi);
+    let caption_line_height = (caption_font_size as f32 * LINE_HEIGHT).round() as u32;
+    let caption_width = caption
+        .iter()
+        .map(|x| text_width(x, caption_font_size))
+        .max()
+        .unwrap_or(0);
+    let caption_height = caption_line_height * caption.len() as u32;
+
+    // The page is widened if the legend and caption do not fit under the map
+    let footer_width = legend.width + margin + caption_width;
+    let width = map_width.max(footer_width) + 2 * margin;
+    let height = map_height + legend.height.max(caption_height) + 3 * margin;
+    let footer_y = map_height + 2 * margin;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.3}in\" height=\"{:.3}in\" \
+            viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\">\n",
+        width as f32 / options.dpi as f32,
+        height as f32 / options.dpi as f32,
+    );
+    if !options.transparent {
+        let _ = writeln!(
+            svg,
+            "<rect width=\"{width}\" height=\"{height}\" fill=\"#ffffff\"/>"
+        );
+    }
+
+    // Black border around the map, the nested svg clips anything outside of the frame
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#000000\"/>",
+        margin - border,
+        margin - border,
+        map_width + 2 * border,
+        map_height + 2 * border,
+    );
+    let _ = writeln!(
+        svg,
+        "<svg x=\"{margin}\" y=\"{margin}\" width=\"{map_width}\" height=\"{map_height}\" \
+            viewBox=\"0 0 {map_width} {map_height}\">"
+    );
+    if !options.transparent {
+        let _ = writeln!(
+            svg,
+            "<rect width=\"{map_width}\" height=\"{map_height}\" fill=\"{BACKGROUND_COLOR}\"/>"
+        );
+    }
+    if let Some(outline) = outline {
+        svg.push_str(&outline_path(outline, grid));
+    }
+
+    // Outlines of neighbouring classes share edges, crisp edges stops seams showing between them
+    svg.push_str("<g shape-rendering=\"crispEdges\">\n");
+    for (weight, rings) in weight_regions(grid) {
+        let color = hex_color(colormap_color(colormap, weight as f32, max_weight));
+        let d: String = rings.iter().map(|x| ring_path(x)).collect();
+        let _ = writeln!(
+            svg,
+            "<path fill=\"{color}\" d=\"{d}\"><title>{weight}</title></path>"
+        );
+        if svg.len() > max_size {
+            return Err(format!(
+                "The map is too detailed to export as an SVG of at most {} MB, export a smaller \
+                    area or a PNG instead",
+                max_size / 1_000_000
+            ));
+        }
+    }
+    svg.push_str("</g>\n");
+
+    if let Some(aoi) = filter.aoi.as_ref() {
+        svg.push_str(&aoi_path(aoi, grid));
+    }
+    svg.push_str("</svg>\n");
+
+    // Legend under the left of the map and the caption under its right
+    svg.push_str(&legend.to_svg(colormap, margin, footer_y));
+
+    let caption_x = width - margin - caption_width / 2;
+    let _ = writeln!(
+        svg,
+        "<g font-size=\"{caption_font_size}\" text-anchor=\"middle\" dominant-baseline=\"hanging\">"
+    );
+    for (i, line) in caption.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{caption_x}\" y=\"{}\">{}</text>",
+            footer_y + i as u32 * caption_line_height,
+            escape_html(line)
+        );
+    }
+    svg.push_str("</g>\n</svg>\n");
+
+    Ok(svg)
+}
+
+/// Outlines of the pixels of the grid with acquisitions grouped by their rounded weight, each
+///     outline is a ring of the corners its edges turn at. Edges between pixels of the same class
+///     cancel so every class is dissolved into as few rings as its shape allows, holes wind the
+///     other way to the rings around them and are left unfilled by the nonzero fill rule
+pub fn weight_regions(grid: &WeightGrid) -> BTreeMap<u32, Vec<Vec<[u32; 2]>>> {
+    let width = grid.size.width;
+    let height = grid.size.height;
+    let class = |x: u32, y: u32| {
+        grid.weights
+            .get((y * width + x) as usize)
+            .map_or(0, |x| x.round() as u32)
+    };
+
+    // Unit edges along the boundaries of each class from the corner they start at to the one
+    //     they end at, clockwise around the pixels of the class. Outside of the grid is empty
+    let mut edges: BTreeMap<u32, BTreeMap<[u32; 2], Vec<[u32; 2]>>> = BTreeMap::new();
+    let mut add = |weight: u32, start: [u32; 2], end: [u32; 2]| {
+        if weight > 0 {
+            edges
+                .entry(weight)
+                .or_default()
+                .entry(start)
+                .or_default()
+                .push(end);
+        }
+    };
+    for y in 0..height {
+        for x in 0..=width {
+            let left = if x > 0 { class(x - 1, y) } else { 0 };
+            let right = if x < width { class(x, y) } else { 0 };
+            if left != right {
+                add(left, [x, y], [x, y + 1]);
+                add(right, [x, y + 1], [x, y]);
+            }
+        }
+    }
+    for y in 0..=height {
+        for x in 0..width {
+            let above = if y > 0 { class(x, y - 1) } else { 0 };
+            let below = if y < height { class(x, y) } else { 0 };
+            if above != below {
+                add(above, [x + 1, y], [x, y]);
+                add(below, [x, y], [x + 1, y]);
+            }
+        }
+    }
+
+    edges
+        .into_iter()
+        .map(|(weight, mut edges)| {
+            let mut rings = Vec::new();
+            // The first corner left is the top of the leftmost edge of a ring so always a turn
+            while let Some((&start, _)) = edges.first_key_value() {
+                let mut ring = vec![start];
+                let mut corner = start;
+                loop {
+                    let ends = edges
+                        .get_mut(&corner)
+                        .expect("Failed to follow class outline");
+                    let end = ends.pop().expect("Failed to follow class outline");
+                    if ends.is_empty() {
+                        edges.remove(&corner);
+                    }
+
+                    // Corners along a straight edge are dropped
+                    if let [.., before, last] = ring[..]
+                        && ((before[0] == last[0] && last[0] == end[0])
+                            || (before[1] == last[1] && last[1] == end[1]))
+                    {
+                        ring.pop();
+                    }
+                    if end == start {
+                        break;
+                    }
+                    ring.push(end);
+                    corner = end;
+                }
+                rings.push(ring);
+            }
+            (weight, rings)
+        })
+        .collect()
+}
+
+// The header, the colormap bar and its labels, laid out like the legend of the png
+struct Legend {
+    ticks: Vec<u32>,
+    max_weight: f32,
+    header_font_size: u32,
+    label_font_size: u32,
+    bar_width: u32,
+    bar_height: u32,
+    bar_y: u32,
+    label_y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Legend {
+    fn new(max_weight: f32, map_width: u32, dpi: u32) -> Self {
+        let ticks = legend_ticks(max_weight);
+        let header_font_size = points_to_pixels(HEADER_FONT_SIZE, dpi);
+        let label_font_size = points_to_pixels(LABEL_FONT_SIZE, dpi);
+        let bar_width = (map_width / 2).clamp(1, points_to_pixels(LEGEND_MAX_WIDTH, dpi));
+        let bar_height = points_to_pixels(LEGEND_BAR_HEIGHT, dpi);
+        let spacing = points_to_pixels(MARGIN / 4.0, dpi);
+        let bar_y = header_font_size + spacing;
+        let label_y = bar_y + bar_height + spacing;
+
+        // Labels are centered under their weight so the last one may hang past the end of the bar
+        let last_label = ticks
+            .last()
+            .map_or(0, |x| text_width(&x.to_string(), label_font_size));
+        let width = text_width("Acquisitions", header_font_size).max(bar_width + last_label);
+
+        Self {
+            ticks,
+            max_weight,
+            header_font_size,
+            label_font_size,
+            bar_width,
+            bar_height,
+            bar_y,
+            label_y,
+            width,
+            height: label_y + label_font_size,
+        }
+    }
+
+    fn to_svg(&self, colormap: &RgbaImage, x: u32, y: u32) -> String {
+        // Position along the bar of a weight and the weight at a position along the bar
+        let span = (self.max_weight - 1.0).max(1.0);
+        let last = (self.bar_width - 1).max(1) as f32;
+
+        let stops: String = (0..LEGEND_STOPS)
+            .map(|i| {
+                let offset = i as f32 / (LEGEND_STOPS - 1) as f32;
+                let weight = offset.mul_add(span, 1.0);
+                format!(
+                    "<stop offset=\"{offset:.4}\" stop-color=\"{}\"/>",
+                    hex_color(colormap_color(colormap, weight, self.max_weight))
+                )
+            })
+            .collect();
+
+        let labels: String = self
+            .ticks
+            .iter()
+            .map(|tick| {
+                let label = tick.to_string();
+                let center = ((*tick as f32 - 1.0) / span * last).round() as u32;
+                let label_x = center.saturating_sub(text_width(&label, self.label_font_size) / 2);
+                format!(
+                    "<text x=\"{label_x}\" y=\"{}\">{label}</text>",
+                    self.label_y
+                )
+            })
+            .collect();
+
+        format!(
+            "<g transform=\"translate({x} {y})\" dominant-baseline=\"hanging\">\n\
+                <defs><linearGradient id=\"legend\">{stops}</linearGradient></defs>\n\
+                <text font-size=\"{}\">Acquisitions</text>\n\
+                <rect y=\"{}\" width=\"{}\" height=\"{}\" fill=\"url(#legend)\"/>\n\
+                <g font-size=\"{}\">{labels}</g>\n\
+            </g>\n",
+            self.header_font_size,
+            self.bar_y,
+            self.bar_width,
+            self.bar_height,
+            self.label_font_size,
+        )
+    }
+}
+
+// Triangles of the world outline inside of the frame, stroked in their fill color so the edges
+//     between them are not visible when anti-aliased
+fn outline_path(outline: &BufferStorage, grid: &WeightGrid) -> String {
+    let frame = grid.frame;
+    let d: String = outline
+        .indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let points: Vec<Coord> = triangle
+                .iter()
+                .map(|x| {
+                    let [lon, lat, _] = outline.vertices[*x as usize].position;
+                    Coord {
+                        x: f64::from(lon),
+                        y: f64::from(lat),
+                    }
+                })
+                .collect();
+            let outside = points.iter().all(|x| x.x < frame.min().x)
+                || points.iter().all(|x| x.x > frame.max().x)
+                || points.iter().all(|x| x.y < frame.min().y)
+                || points.iter().all(|x| x.y > frame.max().y);
+            (!outside).then(|| {
+                let [a, b, c] = [0, 1, 2].map(|i| project(points[i], frame, grid));
+                format!("M{a}L{b}L{c}z")
+            })
+        })
+        .collect();
+
+    format!(
+        "<path fill=\"{OUTLINE_COLOR}\" stroke=\"{OUTLINE_COLOR}\" stroke-width=\"0.5\" \
+            stroke-linejoin=\"round\" d=\"{d}\"/>\n"
+    )
+}
+
+// Every ring of the area of interest drawn as a line one pixel wide like on screen
+fn aoi_path(aoi: &MultiPolygon, grid: &WeightGrid) -> String {
+    let d: String = aoi
+        .iter()
+        .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+        .filter(|ring| ring.0.len() >= 2)
+        .map(|ring| {
+            let points: Vec<String> = ring
+                .0
+                .iter()
+                .map(|x| project(*x, grid.frame, grid))
+                .collect();
+            format!("M{}z", points.join("L"))
+        })
+        .collect();
+
+    format!("<path fill=\"none\" stroke=\"{AOI_COLOR}\" stroke-width=\"1\" d=\"{d}\"/>\n")
+}
+
+// Helper Function:
+//     Position of a lon/lat coordinate on the map in pixels as an SVG point
+fn project(coord: Coord, frame: Rect, grid: &WeightGrid) -> String {
+    let x = (coord.x - frame.min().x) / frame.width() * f64::from(grid.size.width);
+    let y = (frame.max().y - coord.y) / frame.height() * f64::from(grid.size.height);
+    format!("{x:.2} {y:.2}")
+}
+
+// Helper Function:
+//     Path of a ring of corners, the edges between them are all horizontal or vertical
+fn ring_path(ring: &[[u32; 2]]) -> String {
+    let Some([x, y]) = ring.first() else {
+        return String::new();
+    };
+    let mut path = format!("M{x} {y}");
+    for pair in ring.windows(2) {
+        let [a, b] = [pair[0], pair[1]];
+        if a[1] == b[1] {
+            let _ = write!(path, "h{}", i64::from(b[0]) - i64::from(a[0]));
+        } else {
+            let _ = write!(path, "v{}", i64::from(b[1]) - i64::from(a[1]));
+        }
+    }
+    path.push('z');
+    path
+}
+
+// Helper Function:
+//     Estimated width in pixels of a line of text
+fn text_width(text: &str, font_size: u32) -> u32 {
+    (text.chars().count() as f32 * font_size as f32 * CHARACTER_WIDTH).ceil() as u32
+}
+
+// Helper Function:
+//     Formats an sRGB color as #rrggbb, alpha is dropped
+fn hex_color(color: Rgba<u8>) -> String {
+    let [r, g, b, _] = color.0;
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
@@ -0,0 +1,215 @@
+//! Tests of writing the heatmap as an SVG
+use geo::{MultiPolygon, Rect, coord};
+use wasm_bindgen_test::wasm_bindgen_test;
+use winit::dpi::PhysicalSize;
+
+use crate::canvas::geometry::BlendVertex;
+use crate::canvas::geotiff::WeightGrid;
+use crate::canvas::png::filter_to_text;
+use crate::canvas::svg::{compose_svg, weight_regions};
+use crate::ingest::load::BufferStorage;
+use crate::test_support::{colormap, filter, options, world};
+use crate::types::ExportOptions;
+use crate::ui::popup::escape_html;
+
+// Larger than any svg of the tests
+const MAX_SIZE: usize = 1_000_000;
+
+// A single triangle of the outline with a corner on null island
+fn outline() -> BufferStorage {
+    BufferStorage {
+        vertices: [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]
+            .iter()
+            .map(|[x, y]| BlendVertex {
+                position: [*x, *y, 0.0],
+                weight: 0,
+            })
+            .collect(),
+        indices: vec![0, 1, 2],
+        num_indices: 3,
+    }
+}
+
+#[wasm_bindgen_test]
+fn svg_weight_regions_dissolve_pixels() {
+    let grid = WeightGrid {
+        weights: vec![1.0, 1.0, 0.0, 1.0, 1.0, 2.0, 0.0, 0.0, 1.9],
+        size: PhysicalSize::new(3, 3),
+        frame: world(),
+    };
+
+    let regions = weight_regions(&grid);
+    assert_eq!(regions.len(), 2);
+    assert_eq!(regions[&1], vec![vec![[0, 0], [2, 0], [2, 2], [0, 2]]]);
+    assert_eq!(regions[&2], vec![vec![[2, 1], [3, 1], [3, 3], [2, 3]]]);
+
+    // An L of pixels is one ring rather than a rectangle for each run
+    let grid = WeightGrid {
+        weights: vec![1.0, 0.0, 1.0, 0.0, 1.0, 1.0],
+        size: PhysicalSize::new(2, 3),
+        frame: world(),
+    };
+    assert_eq!(
+        weight_regions(&grid)[&1],
+        vec![vec![[0, 0], [1, 0], [1, 2], [2, 2], [2, 3], [0, 3]]]
+    );
+}
+
+#[wasm_bindgen_test]
+fn svg_weight_regions_keep_holes() {
+    // A ring of pixels around a pixel of another class
+    let mut weights = vec![1.0; 9];
+    weights[4] = 2.0;
+    let grid = WeightGrid {
+        weights,
+        size: PhysicalSize::new(3, 3),
+        frame: world(),
+    };
+
+    let regions = weight_regions(&grid);
+    assert_eq!(regions[&2], vec![vec![[1, 1], [2, 1], [2, 2], [1, 2]]]);
+
+    // The hole winds the other way to the outside so the nonzero fill rule leaves it empty
+    assert_eq!(
+        regions[&1],
+        vec![
+            vec![[0, 0], [3, 0], [3, 3], [0, 3]],
+            vec![[1, 1], [1, 2], [2, 2], [2, 1]],
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn svg_contains_map_legend_and_caption() {
+    let grid = WeightGrid {
+        weights: vec![1.0; 360 * 180],
+        size: PhysicalSize::new(360, 180),
+        frame: world(),
+    };
+    let svg = compose_svg(
+        &grid,
+        8.0,
+        &filter(),
+        &colormap(),
+        &options(),
+        None,
+        MAX_SIZE,
+    )
+    .expect("Failed to compose svg");
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("fill=\"#ffffff\""));
+
+    // The whole map is a single class colored like the png, 1.1 / 8 of the way into the colormap
+    assert!(svg.contains("<path fill=\"#2a0000\" d=\"M0 0h360v180h-360z\">"));
+
+    assert!(svg.contains(">Acquisitions</text>"));
+    assert!(svg.contains("<linearGradient id=\"legend\">"));
+    for line in filter_to_text(&filter()) {
+        assert!(svg.contains(&format!(">{}</text>", escape_html(&line))));
+    }
+}
+
+#[wasm_bindgen_test]
+fn svg_transparent_background() {
+    let grid = WeightGrid {
+        weights: vec![0.0; 4],
+        size: PhysicalSize::new(2, 2),
+        frame: world(),
+    };
+    let svg = compose_svg(
+        &grid,
+        1.0,
+        &filter(),
+        &colormap(),
+        &ExportOptions {
+            transparent: true,
+            ..options()
+        },
+        None,
+        MAX_SIZE,
+    )
+    .expect("Failed to compose svg");
+
+    assert!(!svg.contains("fill=\"#ffffff\""));
+    assert!(!svg.contains("fill=\"#272727\""));
+}
+
+#[wasm_bindgen_test]
+fn svg_outline_and_aoi_are_projected() {
+    // A pixel is a degree
+    let grid = WeightGrid {
+        weights: vec![0.0; 360 * 180],
+        size: PhysicalSize::new(360, 180),
+        frame: world(),
+    };
+    let mut filter = filter();
+    filter.aoi = Some(MultiPolygon::new(vec![
+        Rect::new(coord! { x: -10.0, y: -10.0 }, coord! { x: 10.0, y: 10.0 }).to_polygon(),
+    ]));
+    let svg = compose_svg(
+        &grid,
+        1.0,
+        &filter,
+        &colormap(),
+        &options(),
+        Some(&outline()),
+        MAX_SIZE,
+    )
+    .expect("Failed to compose svg");
+
+    assert!(svg.contains("d=\"M180.00 90.00L190.00 90.00L180.00 80.00z\""));
+    assert!(svg.contains("stroke=\"#f9f9f9\""));
+    assert!(svg.contains("170.00 100.00"));
+
+    // Triangles outside of the frame are left out
+    let alaska = WeightGrid {
+        frame: Rect::new(coord! { x: -160.0, y: 60.0 }, coord! { x: -150.0, y: 65.0 }),
+        ..grid
+    };
+    let svg = compose_svg(
+        &alaska,
+        1.0,
+        &filter,
+        &colormap(),
+        &options(),
+        Some(&outline()),
+        MAX_SIZE,
+    )
+    .expect("Failed to compose svg");
+    assert!(svg.contains("stroke-linejoin=\"round\" d=\"\""));
+}
+
+#[wasm_bindgen_test]
+fn svg_too_large_is_an_error() {
+    // A checkerboard of classes has an outline for every pixel
+    let grid = WeightGrid {
+        weights: (0..64 * 64)
+            .map(|x: u32| (x % 64 % 2 + x / 64 % 2 + 1) as f32)
+            .collect(),
+        size: PhysicalSize::new(64, 64),
+        frame: world(),
+    };
+    let svg = compose_svg(
+        &grid,
+        3.0,
+        &filter(),
+        &colormap(),
+        &options(),
+        None,
+        MAX_SIZE,
+    )
+    .expect("Failed to compose svg");
+    assert!(
+        compose_svg(
+            &grid,
+            3.0,
+            &filter(),
+            &colormap(),
+            &options(),
+            None,
+            svg.len() / 2
+        )
+        .is_err()
+    );
+}
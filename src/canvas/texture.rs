@@ -1,4 +1,4 @@
-use image::GenericImageView;
+use image::RgbaImage;
 use web_sys::js_sys::Math::ceil;
 
 pub struct TextureContext {
@@ -88,25 +88,45 @@ pub fn generate_blend_texture(
     }
 }
 
-/// Generates 2 1D texture with the colormaps the heatmap will use
-pub fn generate_colormaps(device: &wgpu::Device, queue: &wgpu::Queue) -> TextureContext {
-    let display_colormap_bytes: &[u8; _] = include_bytes!("../../assets/plasma.png");
+/// Uploads the colormap into a texture that can be bound to the colormap render pass
+pub fn generate_colormap_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    colormap: &RgbaImage,
+) -> TextureContext {
+    // Set up a bind group layout for the texture
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D1,
+                multisampled: false,
+            },
+            count: None,
+        }],
+        label: Some("colormap_bind_group_layout"),
+    });
+
+    let (texture, bind_group) = upload_colormap(device, queue, &bind_group_layout, colormap);
 
-    generate_colormap_texture(device, queue, display_colormap_bytes)
+    TextureContext {
+        texture,
+        bind_group_layout,
+        bind_group,
+    }
 }
 
-/// Reads the passed bytes into a texture that can be bound to the colormap render pass
-fn generate_colormap_texture(
+/// Uploads the colormap into a new texture bound with the layout the colormap pipelines were
+///     created with, used to swap the colormap without rebuilding them
+pub fn upload_colormap(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    colormap_bytes: &[u8],
-) -> TextureContext {
-    // Convert the passed bytes to an Image buffer
-    let colormap_image = image::load_from_memory(colormap_bytes)
-        .expect("ERROR: Failed to generate image from colormap_bytes");
-    let colormap_rgba = colormap_image.to_rgba8();
-
-    let dimensions = colormap_image.dimensions();
+    bind_group_layout: &wgpu::BindGroupLayout,
+    colormap: &RgbaImage,
+) -> (wgpu::Texture, wgpu::BindGroup) {
+    let dimensions = colormap.dimensions();
 
     let texture_size = wgpu::Extent3d {
         width: dimensions.0,
@@ -125,7 +145,7 @@ fn generate_colormap_texture(
         view_formats: &[],
     });
 
-    // Fill in the texture with the data from the .png we read above
+    // Fill in the texture with the colors of the colormap
     queue.write_texture(
         wgpu::ImageCopyTexture {
             texture: &texture,
@@ -133,7 +153,7 @@ fn generate_colormap_texture(
             origin: wgpu::Origin3d::ZERO,
             aspect: wgpu::TextureAspect::All,
         },
-        &colormap_rgba,
+        colormap,
         wgpu::ImageDataLayout {
             offset: 0,
             bytes_per_row: Some(4 * dimensions.0),
@@ -143,23 +163,9 @@ fn generate_colormap_texture(
     );
 
     // Set up a bind group for the texture
-    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::FRAGMENT,
-            ty: wgpu::BindingType::Texture {
-                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                view_dimension: wgpu::TextureViewDimension::D1,
-                multisampled: false,
-            },
-            count: None,
-        }],
-        label: Some("colormap_bind_group_layout"),
-    });
-
     let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        layout: &bind_group_layout,
+        layout: bind_group_layout,
         entries: &[wgpu::BindGroupEntry {
             binding: 0,
             resource: wgpu::BindingResource::TextureView(&texture_view),
@@ -167,11 +173,7 @@ fn generate_colormap_texture(
         label: Some("colormap_bind_group"),
     });
 
-    TextureContext {
-        texture,
-        bind_group_layout,
-        bind_group,
-    }
+    (texture, bind_group)
 }
 
 /// A texture capable of being copied to a buffer, we render the blend texture onto this texture when
@@ -0,0 +1,131 @@
+//! Colormaps the heatmap can be drawn with, the ones bundled in `assets/` or a custom one read
+//!     from a png or a list of color stops. The canvas, legend and exports all read the same one
+use std::sync::Arc;
+
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use strum_macros::{Display, EnumIter};
+
+/// Every colormap is resampled to this many colors, the width of the bundled ones
+pub const COLORMAP_WIDTH: u32 = 480;
+
+/// Name of a colormap that is not one of the bundled ones
+pub const CUSTOM_COLORMAP: &str = "Custom";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumIter)]
+pub enum BundledColormap {
+    #[default]
+    Plasma,
+    Viridis,
+    Magma,
+    Cividis,
+}
+
+impl BundledColormap {
+    const fn bytes(self) -> &'static [u8] {
+        match self {
+            Self::Plasma => include_bytes!("../assets/plasma.png"),
+            Self::Viridis => include_bytes!("../assets/viridis.png"),
+            Self::Magma => include_bytes!("../assets/magma.png"),
+            Self::Cividis => include_bytes!("../assets/cividis.png"),
+        }
+    }
+}
+
+/// A single row of sRGB colors running from the fewest to the most acquisitions
+#[derive(Clone, Debug)]
+pub struct Colormap {
+    pub name: String,
+    pub image: Arc<RgbaImage>,
+}
+
+impl Default for Colormap {
+    fn default() -> Self {
+        Self::bundled(BundledColormap::default())
+    }
+}
+
+impl Colormap {
+    pub fn bundled(colormap: BundledColormap) -> Self {
+        let image = image::load_from_memory(colormap.bytes())
+            .expect("ERROR: Failed to generate image from colormap bytes")
+            .to_rgba8();
+        Self {
+            name: colormap.to_string(),
+            image: Arc::new(image),
+        }
+    }
+
+    /// Read a colormap from a png, the row through its middle is used from left to right
+    pub fn from_png(bytes: &[u8]) -> Result<Self, String> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|err| format!("Failed to read the colormap png: {err}"))?
+            .to_rgba8();
+        let row =
+            image::imageops::crop_imm(&image, 0, image.height() / 2, image.width(), 1).to_image();
+
+        Ok(Self::custom(image::imageops::resize(
+            &row,
+            COLORMAP_WIDTH,
+            1,
+            FilterType::Triangle,
+        )))
+    }
+
+    /// Read a colormap from a comma separated list of hex colors, ie. "#000000, #ff8000, #ffff00",
+    ///     the colors are evenly spaced and blended between
+    pub fn from_stops(text: &str) -> Result<Self, String> {
+        let stops = text
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| parse_hex_color(x).ok_or_else(|| format!("{x} is not a color like #ff8000")))
+            .collect::<Result<Vec<[u8; 3]>, String>>()?;
+        if stops.len() < 2 {
+            return Err("A colormap needs at least 2 color stops".to_string());
+        }
+
+        let last = (stops.len() - 1) as f32;
+        Ok(Self::custom(RgbaImage::from_fn(
+            COLORMAP_WIDTH,
+            1,
+            |x, _| {
+                let position = x as f32 / (COLORMAP_WIDTH - 1) as f32 * last;
+                let index = (position as usize).min(stops.len() - 2);
+                let t = position - index as f32;
+                let [r, g, b] = [0, 1, 2].map(|i| {
+                    let (start, end) = (f32::from(stops[index][i]), f32::from(stops[index + 1][i]));
+                    (end - start).mul_add(t, start).round() as u8
+                });
+                Rgba([r, g, b, 255])
+            },
+        )))
+    }
+
+    fn custom(image: RgbaImage) -> Self {
+        Self {
+            name: CUSTOM_COLORMAP.to_string(),
+            image: Arc::new(image),
+        }
+    }
+}
+
+// Helper Function:
+//     Parses #rgb or #rrggbb into its channels
+fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#')?;
+    if !hex.is_ascii() {
+        return None;
+    }
+    match hex.len() {
+        3 => {
+            let [r, g, b] = [0, 1, 2].map(|i| u8::from_str_radix(&hex[i..=i], 16).ok());
+            Some([r? * 17, g? * 17, b? * 17])
+        }
+        6 => {
+            let [r, g, b] = [0, 2, 4].map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok());
+            Some([r?, g?, b?])
+        }
+        _ => None,
+    }
+}
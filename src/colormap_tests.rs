@@ -0,0 +1,65 @@
+//! Tests of reading the bundled and custom colormaps
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use strum::IntoEnumIterator as _;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::colormap::{BundledColormap, COLORMAP_WIDTH, CUSTOM_COLORMAP, Colormap};
+
+#[wasm_bindgen_test]
+fn bundled_colormaps_are_one_row() {
+    for bundled in BundledColormap::iter() {
+        let colormap = Colormap::bundled(bundled);
+        assert_eq!(colormap.name, bundled.to_string());
+        assert_eq!(colormap.image.dimensions(), (COLORMAP_WIDTH, 1));
+    }
+}
+
+#[wasm_bindgen_test]
+fn colormap_from_stops_blends_between_them() {
+    let colormap = Colormap::from_stops("#000000, #f00, #ffff00").expect("Failed to read stops");
+    assert_eq!(colormap.name, CUSTOM_COLORMAP);
+    assert_eq!(colormap.image.dimensions(), (COLORMAP_WIDTH, 1));
+
+    assert_eq!(*colormap.image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    assert_eq!(
+        *colormap.image.get_pixel(COLORMAP_WIDTH - 1, 0),
+        Rgba([255, 255, 0, 255])
+    );
+    // Half way is the middle stop, a quarter of the way is half way to it
+    let middle = colormap.image.get_pixel(COLORMAP_WIDTH / 2, 0).0;
+    assert!(middle[0] == 255 && middle[1] < 5);
+    let quarter = colormap.image.get_pixel(COLORMAP_WIDTH / 4, 0).0;
+    assert!((i32::from(quarter[0]) - 128).abs() <= 2);
+}
+
+#[wasm_bindgen_test]
+fn colormap_from_stops_rejects_invalid() {
+    assert!(Colormap::from_stops("#ff8000").is_err());
+    assert!(Colormap::from_stops("#ff8000, orange").is_err());
+    assert!(Colormap::from_stops("#ff8000, #12345").is_err());
+}
+
+#[wasm_bindgen_test]
+fn colormap_from_png_reads_middle_row() {
+    // The middle row runs from black to blue, the rows around it are white
+    let image = RgbaImage::from_fn(64, 3, |x, y| {
+        if y == 1 {
+            Rgba([0, 0, (x * 4) as u8, 255])
+        } else {
+            Rgba([255, 255, 255, 255])
+        }
+    });
+    let mut bytes = Cursor::new(Vec::new());
+    image
+        .write_to(&mut bytes, ImageFormat::Png)
+        .expect("Failed to encode png");
+
+    let colormap = Colormap::from_png(bytes.get_ref()).expect("Failed to read png");
+    assert_eq!(colormap.image.dimensions(), (COLORMAP_WIDTH, 1));
+    assert_eq!(colormap.image.get_pixel(0, 0).0[..2], [0, 0]);
+    assert!(colormap.image.get_pixel(COLORMAP_WIDTH - 1, 0).0[2] > 240);
+
+    assert!(Colormap::from_png(b"not a png").is_err());
+}
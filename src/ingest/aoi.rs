@@ -0,0 +1,121 @@
+// Reads an area of interest out of a file the user imported, GeoJSON and WKT are parsed here
+//     while zipped shapefiles are handed to DuckDB spatial's ST_Read
+
+use geo::{CoordsIter as _, Geometry, MultiPolygon, Polygon};
+use geojson::GeoJson;
+use wkt::TryFromWkt as _;
+
+use crate::ingest::async_duckdb::AsyncDuckDBConnection;
+use crate::ingest::error::IngestError;
+use crate::ingest::request::decode_geometries;
+use crate::ingest::validate::geometry_type_name;
+use crate::types::AoiFile;
+
+// GDAL opens `.shp.zip` files as shapefiles without needing them to be unzipped first
+const SHAPEFILE_NAME: &str = "aoi_import.shp.zip";
+
+/// Read the polygons in an imported file, zip files are read as shapefiles and anything else as
+///     GeoJSON or WKT text
+pub async fn import_aoi(
+    connection: &AsyncDuckDBConnection,
+    file: &AoiFile,
+) -> Result<MultiPolygon, IngestError> {
+    if file.name.to_lowercase().ends_with(".zip") {
+        return collect_polygons(read_shapefile(connection, &file.bytes).await?);
+    }
+
+    let text = std::str::from_utf8(&file.bytes).map_err(|_| {
+        IngestError::Aoi(format!(
+            "{} is not a GeoJSON, WKT or zipped shapefile",
+            file.name
+        ))
+    })?;
+    parse_aoi(text)
+}
+
+/// Parse the polygons out of GeoJSON or WKT, GeoJSON is detected by its opening brace
+pub fn parse_aoi(text: &str) -> Result<MultiPolygon, IngestError> {
+    let text = text.trim();
+    if text.starts_with('{') {
+        let geojson = text
+            .parse::<GeoJson>()
+            .map_err(|err| IngestError::Aoi(format!("Invalid GeoJSON, {err}")))?;
+        let collection = geojson::quick_collection::<f64>(&geojson)
+            .map_err(|err| IngestError::Aoi(format!("Invalid GeoJSON, {err}")))?;
+        collect_polygons(collection.0)
+    } else {
+        let geometry = Geometry::<f64>::try_from_wkt_str(text)
+            .map_err(|err| IngestError::Aoi(format!("Invalid WKT, {err}")))?;
+        collect_polygons(vec![geometry])
+    }
+}
+
+// Features of the shapefile as geometries, the file is only registered while it is being read
+async fn read_shapefile(
+    connection: &AsyncDuckDBConnection,
+    bytes: &[u8],
+) -> Result<Vec<Geometry>, IngestError> {
+    connection.register_file(SHAPEFILE_NAME, bytes).await?;
+    let batches = connection
+        .query(&format!(
+            "SELECT ST_AsWKB(geom) FROM ST_Read('{SHAPEFILE_NAME}');"
+        ))
+        .await;
+    connection.drop_file(SHAPEFILE_NAME).await?;
+
+    let batches = batches.map_err(|err| {
+        IngestError::Aoi(format!(
+            "Failed to read the shapefile, {}",
+            String::from(err.message())
+        ))
+    })?;
+    decode_geometries(&batches)
+}
+
+// Join every polygon into one MultiPolygon, anything that is not a polygon or lies outside of
+//     lon/lat is rejected rather than silently dropped
+fn collect_polygons(geometries: Vec<Geometry>) -> Result<MultiPolygon, IngestError> {
+    let mut polygons: Vec<Polygon> = Vec::new();
+    for geometry in geometries {
+        push_polygons(geometry, &mut polygons)?;
+    }
+
+    if polygons.is_empty() {
+        return Err(IngestError::Aoi("No polygons were found".to_string()));
+    }
+
+    let aoi = MultiPolygon::new(polygons);
+    if let Some(coord) = aoi
+        .coords_iter()
+        .find(|x| !(-180.0..=180.0).contains(&x.x) || !(-90.0..=90.0).contains(&x.y))
+    {
+        return Err(IngestError::Aoi(format!(
+            "({}, {}) is not a longitude and latitude, reproject the file to EPSG:4326",
+            coord.x, coord.y
+        )));
+    }
+
+    Ok(aoi)
+}
+
+// Polygons inside of collections are added as well, ie. a GeoJSON FeatureCollection
+fn push_polygons(geometry: Geometry, polygons: &mut Vec<Polygon>) -> Result<(), IngestError> {
+    match geometry {
+        Geometry::Polygon(poly) => polygons.push(poly),
+        Geometry::MultiPolygon(multi_poly) => polygons.extend(multi_poly),
+        Geometry::Rect(rect) => polygons.push(rect.to_polygon()),
+        Geometry::Triangle(triangle) => polygons.push(triangle.to_polygon()),
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                push_polygons(geometry, polygons)?;
+            }
+        }
+        other => {
+            return Err(IngestError::Aoi(format!(
+                "Expected polygons but found a {}",
+                geometry_type_name(&other)
+            )));
+        }
+    }
+    Ok(())
+}
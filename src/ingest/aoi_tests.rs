@@ -0,0 +1,86 @@
+//! Tests of reading imported areas of interest from GeoJSON and WKT
+use geo::{Area as _, BoundingRect as _};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::aoi::parse_aoi;
+use crate::ingest::error::IngestError;
+
+fn aoi_error(text: &str) -> String {
+    match parse_aoi(text) {
+        Err(IngestError::Aoi(msg)) => msg,
+        other => panic!("Expected an AOI error, got {other:?}"),
+    }
+}
+
+#[wasm_bindgen_test]
+fn aoi_from_geojson_feature_collection() {
+    let aoi = parse_aoi(
+        r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "properties": { "name": "Anchorage" },
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[-150, 61], [-149, 61], [-149, 62], [-150, 62], [-150, 61]]]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "properties": { "name": "Fairbanks" },
+                    "geometry": {
+                        "type": "MultiPolygon",
+                        "coordinates": [[[[-148, 64], [-147, 64], [-147, 65], [-148, 65], [-148, 64]]]]
+                    }
+                }
+            ]
+        }"#,
+    )
+    .expect("Failed to parse GeoJSON");
+
+    assert_eq!(aoi.0.len(), 2);
+    assert!((aoi.unsigned_area() - 2.0).abs() < f64::EPSILON);
+}
+
+#[wasm_bindgen_test]
+fn aoi_from_wkt() {
+    let aoi = parse_aoi(
+        "MULTIPOLYGON (((-149 61, -146 61, -146 64, -149 64, -149 61),
+            (-148 62, -147 62, -147 63, -148 63, -148 62)))",
+    )
+    .expect("Failed to parse WKT");
+
+    assert_eq!(aoi.0.len(), 1);
+    assert_eq!(aoi.0[0].interiors().len(), 1);
+    let bounds = aoi.bounding_rect().expect("AOI is empty");
+    assert_eq!((bounds.min().x, bounds.max().y), (-149.0, 64.0));
+}
+
+#[wasm_bindgen_test]
+fn aoi_rejects_non_polygons() {
+    assert_eq!(
+        aoi_error("LINESTRING (-149 61, -146 64)"),
+        "Expected polygons but found a LineString"
+    );
+    assert_eq!(
+        aoi_error(r#"{ "type": "Point", "coordinates": [-149, 61] }"#),
+        "Expected polygons but found a Point"
+    );
+    assert_eq!(
+        aoi_error(r#"{ "type": "FeatureCollection", "features": [] }"#),
+        "No polygons were found"
+    );
+}
+
+#[wasm_bindgen_test]
+fn aoi_rejects_invalid_input() {
+    assert!(aoi_error("POLYGON ((-149 61, -146").starts_with("Invalid WKT"));
+    assert!(aoi_error(r#"{ "type": "Polygon" }"#).starts_with("Invalid GeoJSON"));
+
+    // Projected coordinates, ie. UTM, are not lon/lat
+    assert_eq!(
+        aoi_error("POLYGON ((500000 6800000, 510000 6800000, 510000 6810000, 500000 6800000))"),
+        "(500000, 6800000) is not a longitude and latitude, reproject the file to EPSG:4326"
+    );
+}
@@ -1,10 +1,13 @@
 use std::io::Cursor;
 
+use arrow::error::ArrowError;
 use arrow::ipc::reader::FileReader;
 use arrow::record_batch::RecordBatch;
-use js_sys::{Promise, Uint8Array};
+use js_sys::{Array, Promise, Uint8Array};
 use wasm_bindgen::prelude::*;
 
+use crate::ingest::query::{Param, Query};
+
 #[wasm_bindgen(module = "/assets/duckdb-browser.mjs")]
 extern "C" {
     #[wasm_bindgen(js_name = "AsyncDuckDB")]
@@ -12,6 +15,32 @@ extern "C" {
     type AsyncDuckDB;
     #[wasm_bindgen(method, catch, js_name = "runQuery")]
     async fn run_query(this: &AsyncDuckDB, conn: u32, text: &str) -> Result<Uint8Array, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "createPrepared")]
+    async fn create_prepared(this: &AsyncDuckDB, conn: u32, text: &str)
+    -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "runPrepared")]
+    async fn run_prepared(
+        this: &AsyncDuckDB,
+        conn: u32,
+        statement: u32,
+        params: Array,
+    ) -> Result<Uint8Array, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "closePrepared")]
+    async fn close_prepared(
+        this: &AsyncDuckDB,
+        conn: u32,
+        statement: u32,
+    ) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "registerFileBuffer")]
+    async fn register_file_buffer(
+        this: &AsyncDuckDB,
+        name: &str,
+        buffer: Uint8Array,
+    ) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "copyFileToBuffer")]
+    async fn copy_file_to_buffer(this: &AsyncDuckDB, name: &str) -> Result<Uint8Array, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "dropFile")]
+    async fn drop_file(this: &AsyncDuckDB, name: &str) -> Result<JsValue, JsValue>;
 
     #[wasm_bindgen(js_name = "AsyncDuckDBConnection")]
     #[derive(Debug)]
@@ -29,24 +58,66 @@ impl AsyncDuckDBConnection {
     }
 
     pub async fn query(&self, sql: &str) -> Result<Vec<RecordBatch>, js_sys::Error> {
-        let res = self.bindings().run_query(self.conn()?, sql).await?.to_vec();
-        let cursor = Cursor::new(res);
-        let reader = FileReader::try_new(cursor, None)
-            .expect("Failed to create file reader around cursor for reading RecordBatches");
-        let mut batches: Vec<RecordBatch> = Vec::new();
-        for maybe_batch in reader {
-            match maybe_batch {
-                Ok(batch) => batches.push(batch),
-                Err(err) => return Err(js_sys::Error::new(&err.to_string())),
-            }
-        }
-        Ok(batches)
+        let res = self.query_ipc(sql).await?.to_vec();
+        decode_ipc(res).map_err(|err| js_sys::Error::new(&err.to_string()))
+    }
+
+    /// Run a query and return the result as Arrow IPC bytes without decoding them,
+    ///     used to hand results to the mesh worker
+    pub async fn query_ipc(&self, sql: &str) -> Result<Uint8Array, js_sys::Error> {
+        Ok(self.bindings().run_query(self.conn()?, sql).await?)
+    }
+
+    /// Run a query as a prepared statement with its values bound as parameters, the result is
+    ///     left as Arrow IPC bytes like `query_ipc`
+    pub async fn query_prepared_ipc(&self, query: &Query) -> Result<Uint8Array, js_sys::Error> {
+        let (sql, params) = query.prepared();
+        let conn = self.conn()?;
+        let bindings = self.bindings();
+
+        let statement = bindings
+            .create_prepared(conn, &sql)
+            .await?
+            .as_f64()
+            .map(|x| x as u32)
+            .ok_or_else(|| js_sys::Error::new("Prepared statement id was not a number"))?;
+        let result = bindings
+            .run_prepared(conn, statement, params.iter().map(Param::to_js).collect())
+            .await;
+        let _ = bindings.close_prepared(conn, statement).await;
+
+        Ok(result?)
     }
+
+    /// Register an in memory file with `DuckDB`, queries can then read it by name
+    pub async fn register_file(&self, name: &str, bytes: &[u8]) -> Result<(), js_sys::Error> {
+        self.bindings()
+            .register_file_buffer(name, Uint8Array::from(bytes))
+            .await?;
+        Ok(())
+    }
+
+    /// Copy a file out of `DuckDB`'s virtual file system, ie. one written with `COPY ... TO`
+    pub async fn read_file(&self, name: &str) -> Result<Uint8Array, js_sys::Error> {
+        Ok(self.bindings().copy_file_to_buffer(name).await?)
+    }
+
+    /// Remove a file from `DuckDB`'s virtual file system, freeing its memory
+    pub async fn drop_file(&self, name: &str) -> Result<(), js_sys::Error> {
+        self.bindings().drop_file(name).await?;
+        Ok(())
+    }
+}
+
+/// Decode the Arrow IPC bytes returned by `DuckDB` into `RecordBatch`es
+pub fn decode_ipc(bytes: Vec<u8>) -> Result<Vec<RecordBatch>, ArrowError> {
+    let cursor = Cursor::new(bytes);
+    let reader = FileReader::try_new(cursor, None)?;
+    reader.collect()
 }
 
 pub async fn generate_duckdb_connection() -> Result<AsyncDuckDBConnection, JsValue> {
-    js_sys::eval(include_str!("duckdb.js"))
-        .expect("Failed to initialize DuckDB in JS")
+    js_sys::eval(include_str!("duckdb.js"))?
         .dyn_into::<Promise>()?
         .await?
         .dyn_into::<AsyncDuckDBConnection>()
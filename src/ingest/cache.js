@@ -0,0 +1,125 @@
+// Persistent cache of parquet files stored in the Origin Private File System,
+//  an index of every entry and when it was stored and last used is kept alongside the files
+//  so stale entries can be downloaded again and the least recently used entries can be evicted
+//  once the cache is too large. Every tab shares the index, it is only read and written while
+//  holding a Web Lock so tabs can't overwrite each other's changes
+
+const DIRECTORY = "heatmap-cache";
+const INDEX = "index.json";
+const LOCK = "heatmap-cache-index";
+
+async function directory() {
+    const root = await navigator.storage.getDirectory();
+    return root.getDirectoryHandle(DIRECTORY, { create: true });
+}
+
+// Runs update with exclusive access to the index, browsers without Web Locks only have one
+//  context that can reach the cache at a time
+async function withIndex(update) {
+    const run = async () => {
+        const dir = await directory();
+        const index = await readIndex(dir);
+        const [result, changed] = await update(dir, index);
+        if (changed) {
+            await writeFile(dir, INDEX, JSON.stringify(index));
+        }
+        return result;
+    };
+
+    if (navigator.locks === undefined) {
+        return run();
+    }
+    return navigator.locks.request(LOCK, run);
+}
+
+async function readIndex(dir) {
+    try {
+        const file = await (await dir.getFileHandle(INDEX)).getFile();
+        return JSON.parse(await file.text());
+    } catch {
+        return {};
+    }
+}
+
+async function writeFile(dir, name, data) {
+    const handle = await dir.getFileHandle(name, { create: true });
+    const writable = await handle.createWritable();
+    await writable.write(data);
+    await writable.close();
+}
+
+async function fileName(key) {
+    const digest = await crypto.subtle.digest("SHA-256", new TextEncoder().encode(key));
+    const hex = Array.from(new Uint8Array(digest), (x) => x.toString(16).padStart(2, "0")).join("");
+    return `${hex}.parquet`;
+}
+
+// Returns the cached bytes for key or undefined if key is not cached or was stored more than
+//  maxAge milliseconds ago
+export async function cacheGet(key, maxAge) {
+    return withIndex(async (dir, index) => {
+        const entry = index[key];
+        if (entry === undefined) {
+            return [undefined, false];
+        }
+        // Entries from before stored was tracked are stale as well
+        if (!(Date.now() - entry.stored <= maxAge)) {
+            return [undefined, false];
+        }
+
+        try {
+            const file = await (await dir.getFileHandle(entry.file)).getFile();
+            const bytes = new Uint8Array(await file.arrayBuffer());
+            entry.used = Date.now();
+            return [bytes, true];
+        } catch {
+            // The file was removed out from under the index
+            delete index[key];
+            return [undefined, true];
+        }
+    });
+}
+
+// Stores bytes under key then evicts the least recently used entries until the cache fits in maxBytes
+export async function cachePut(key, bytes, maxBytes) {
+    const file = await fileName(key);
+
+    await withIndex(async (dir, index) => {
+        await writeFile(dir, file, bytes);
+        const now = Date.now();
+        index[key] = { file, size: bytes.byteLength, stored: now, used: now };
+
+        const entries = Object.entries(index).sort(([, a], [, b]) => a.used - b.used);
+        let total = entries.reduce((sum, [, entry]) => sum + entry.size, 0);
+        for (const [evictKey, entry] of entries) {
+            if (total <= maxBytes || evictKey === key) {
+                break;
+            }
+            await dir.removeEntry(entry.file).catch(() => {});
+            delete index[evictKey];
+            total -= entry.size;
+        }
+
+        return [undefined, true];
+    });
+}
+
+// Total size in bytes of every cached file
+export async function cacheSize() {
+    return withIndex(async (_, index) => [
+        Object.values(index).reduce((sum, entry) => sum + entry.size, 0),
+        false,
+    ]);
+}
+
+export async function cacheClear() {
+    const clear = async () => {
+        const root = await navigator.storage.getDirectory();
+        await root.removeEntry(DIRECTORY, { recursive: true }).catch(() => {});
+    };
+
+    if (navigator.locks === undefined) {
+        return clear();
+    }
+    return navigator.locks.request(LOCK, clear);
+}
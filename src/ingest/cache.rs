@@ -0,0 +1,113 @@
+//! Persistent browser cache of the monthly parquet files, files are kept in the Origin Private
+//!     File System keyed by their URL so they survive page reloads and are not downloaded again.
+//!     The URL includes the bucket so each maturity is cached separately. Only months that are
+//!     complete are cached and they are downloaded again once they reach `MAX_AGE`
+use chrono::{Datelike as _, Days, Months, NaiveDate, Utc};
+use js_sys::Uint8Array;
+use leptos::logging::log;
+use wasm_bindgen::prelude::*;
+
+use crate::ingest::async_duckdb::AsyncDuckDBConnection;
+use crate::ingest::error::IngestError;
+use crate::ingest::sql::generate_copy_parquet_sql;
+
+/// Once the cache grows past this size the least recently used files are evicted
+pub const MAX_CACHE_BYTES: f64 = 2.0 * 1024.0 * 1024.0 * 1024.0;
+
+/// Cached files are downloaded again after this many milliseconds, 30 days, so months that are
+///     reprocessed in the archive are picked up
+pub const MAX_AGE: f64 = 30.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Acquisitions are published up to this many days after they are acquired, a month is only
+///     cached once this long has passed since it ended
+pub const SETTLE_DAYS: u64 = 7;
+
+// Name the cached file is registered with in DuckDB while it is being ingested
+const STAGING_FILE: &str = "cache_staging.parquet";
+
+#[wasm_bindgen(module = "/src/ingest/cache.js")]
+extern "C" {
+    #[wasm_bindgen(catch, js_name = "cacheGet")]
+    async fn cache_get(key: &str, max_age: f64) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(catch, js_name = "cachePut")]
+    async fn cache_put(key: &str, bytes: Uint8Array, max_bytes: f64) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(catch, js_name = "cacheSize")]
+    async fn cache_size() -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(catch, js_name = "cacheClear")]
+    async fn cache_clear() -> Result<JsValue, JsValue>;
+}
+
+/// True if the month starting on `month` can no longer gain acquisitions, files of months that
+///     are still being added to are read directly instead of through the cache
+pub fn is_complete(month: NaiveDate, today: NaiveDate) -> bool {
+    NaiveDate::from_ymd_opt(month.year(), month.month(), 1)
+        .and_then(|x| x.checked_add_months(Months::new(1)))
+        .and_then(|x| x.checked_add_days(Days::new(SETTLE_DAYS)))
+        .is_some_and(|settled| today >= settled)
+}
+
+/// Today in UTC, the time acquisitions are recorded in
+pub fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+/// Make the parquet file at url available to `DuckDB`, from the cache if it has been seen recently
+///     otherwise it is downloaded by `DuckDB` and stored in the cache. Returns the name to read the
+///     file from, call `release` once it has been ingested
+pub async fn open(connection: &AsyncDuckDBConnection, url: &str) -> Result<String, IngestError> {
+    // The cache is best effort, if it is unavailable the file is downloaded as usual
+    match cache_get(url, MAX_AGE)
+        .await
+        .map(JsCast::dyn_into::<Uint8Array>)
+    {
+        Ok(Ok(bytes)) => {
+            log!("Cache hit: {url}");
+            connection
+                .register_file(STAGING_FILE, &bytes.to_vec())
+                .await?;
+            return Ok(STAGING_FILE.to_string());
+        }
+        Ok(Err(_)) => log!("Cache miss: {url}"),
+        Err(err) => log!("Failed to read cache: {err:?}"),
+    }
+
+    connection
+        .query(&generate_copy_parquet_sql(url, STAGING_FILE))
+        .await?;
+    let bytes = connection.read_file(STAGING_FILE).await?;
+    if let Err(err) = cache_put(url, bytes, MAX_CACHE_BYTES).await {
+        log!("Failed to write {url} to cache: {err:?}");
+    }
+
+    Ok(STAGING_FILE.to_string())
+}
+
+/// Free the memory used by a file returned from `open`
+pub async fn release(connection: &AsyncDuckDBConnection, file: &str) {
+    if let Err(err) = connection.drop_file(file).await {
+        log!("Failed to drop cached file {file}: {err:?}");
+    }
+}
+
+/// Total size of the cache in bytes
+pub async fn size() -> Result<f64, IngestError> {
+    cache_size()
+        .await
+        .map_err(cache_error)?
+        .as_f64()
+        .ok_or_else(|| IngestError::Cache("Cache size was not a number".to_string()))
+}
+
+/// Remove every file from the cache, data already ingested into `DuckDB` is unaffected
+pub async fn clear() -> Result<(), IngestError> {
+    cache_clear().await.map_err(cache_error)?;
+    Ok(())
+}
+
+#[allow(
+    clippy::needless_pass_by_value,
+    reason = "Used directly with map_err which passes the error by value"
+)]
+fn cache_error(err: JsValue) -> IngestError {
+    IngestError::Cache(format!("{err:?}"))
+}
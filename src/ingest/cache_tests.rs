@@ -0,0 +1,17 @@
+//! Tests of deciding which monthly files are kept in the cache
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::cache::is_complete;
+use crate::test_support::date;
+
+#[wasm_bindgen_test]
+fn cache_only_complete_months() {
+    // June is still gaining acquisitions during June and the days after it while they are published
+    assert!(!is_complete(date(2026, 6, 1), date(2026, 6, 20)));
+    assert!(!is_complete(date(2026, 6, 1), date(2026, 7, 7)));
+    assert!(is_complete(date(2026, 6, 1), date(2026, 7, 8)));
+    assert!(is_complete(date(2025, 12, 1), date(2026, 6, 20)));
+
+    // Any day of the month stands for the whole month
+    assert!(!is_complete(date(2026, 6, 22), date(2026, 7, 2)));
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors that can occur while ingesting data into `DuckDB` or requesting it back out,
+///     serializable so errors raised in the mesh worker can be sent back to the main thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum IngestError {
+    /// `DuckDB` failed to run a query, ie. a missing parquet file or invalid SQL
+    Query(String),
+    /// `DuckDB` returned data that did not match the expected schema
+    Schema(String),
+    /// A geometry could not be decoded from WKB
+    WkbDecode(String),
+    /// A granule geometry was decoded but it was not a polygon
+    NonPolygon(String),
+    /// The mesh worker could not be started or sent back an unexpected message
+    Worker(String),
+    /// The page or build configuration is invalid, ie. an unknown data source
+    Config(String),
+    /// The persistent browser cache could not be read or modified
+    Cache(String),
+    /// An imported area of interest could not be read or did not contain polygons in lon/lat
+    Aoi(String),
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query(msg) => write!(f, "DuckDB query failed: {msg}"),
+            Self::Schema(msg) => write!(f, "Unexpected data from DuckDB: {msg}"),
+            Self::WkbDecode(msg) => write!(f, "Failed to decode geometry: {msg}"),
+            Self::NonPolygon(kind) => write!(f, "Expected a Polygon geometry but found a {kind}"),
+            Self::Worker(msg) => write!(f, "Mesh worker failed: {msg}"),
+            Self::Config(msg) => write!(f, "Invalid configuration: {msg}"),
+            Self::Cache(msg) => write!(f, "Browser cache failed: {msg}"),
+            Self::Aoi(msg) => write!(f, "Could not import area of interest: {msg}"),
+        }
+    }
+}
+
+impl Error for IngestError {}
+
+impl IngestError {
+    /// Short title used when displaying the error in the popup
+    pub const fn title(&self) -> &'static str {
+        match self {
+            Self::Query(_) | Self::Worker(_) => "Failed to Load Data",
+            Self::Schema(_) | Self::WkbDecode(_) | Self::NonPolygon(_) => "Malformed Data",
+            Self::Config(_) => "Invalid Configuration",
+            Self::Cache(_) => "Cache Error",
+            Self::Aoi(_) => "Invalid Area of Interest",
+        }
+    }
+}
+
+impl From<js_sys::Error> for IngestError {
+    fn from(err: js_sys::Error) -> Self {
+        Self::Query(String::from(err.message()))
+    }
+}
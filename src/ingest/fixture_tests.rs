@@ -0,0 +1,307 @@
+//! End to end tests of the ingest pipeline against the bundled fixture, from populating `DuckDB`
+//!     through `generate_sql`, decoding and meshing. `DuckDB` runs in a Web Worker so these run in a
+//!     headless browser, see the README for how to run them
+use geo::{BoundingRect as _, Geometry, MultiPolygon, Rect, coord};
+use strum::IntoEnumIterator as _;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+use crate::ingest::async_duckdb::{AsyncDuckDBConnection, decode_ipc};
+use crate::ingest::load::{Data, populate_sat_data, prepare_connection};
+use crate::ingest::lod::LodConfig;
+use crate::ingest::mesh::mesh_data;
+use crate::ingest::request::{
+    decode_granules, decode_outline, request, request_granule_list, request_inspect,
+};
+use crate::ingest::source::{DataSource as _, FixtureSource};
+use crate::mission::Mission;
+use crate::test_support::{date, sentinel1_filter};
+use crate::types::{
+    BeamMode, DateRange, Filter, Granule, OrbitDirection, PlatformType, Polarization, ProductTypes,
+};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// Connect to `DuckDB` and ingest every month of the fixture that falls in the filter
+async fn fixture_connection(filter: &Filter) -> AsyncDuckDBConnection {
+    let connection = prepare_connection(&FixtureSource)
+        .await
+        .expect("Failed to prepare DuckDB with fixture");
+    ingest(&connection, &filter.date_range).await;
+    connection
+}
+
+/// Ingest every mission, only Sentinel-1 has data in the fixture
+async fn ingest(connection: &AsyncDuckDBConnection, date_range: &DateRange) {
+    for mission in Mission::iter() {
+        let files = FixtureSource.sat_data_files(mission, std::slice::from_ref(date_range));
+        for (file, days) in files {
+            populate_sat_data(connection, &file, &days)
+                .await
+                .expect("Failed to ingest fixture");
+        }
+    }
+}
+
+fn fixture_month() -> Filter {
+    sentinel1_filter(FixtureSource::MONTH, date(2026, 7, 1))
+}
+
+async fn request_granules(filter: Filter) -> Vec<Granule> {
+    let connection = fixture_connection(&filter).await;
+    let (granule_ipc, _) = request(&connection, filter)
+        .await
+        .expect("Failed to request fixture");
+    let batches = decode_ipc(granule_ipc.to_vec()).expect("Failed to decode granule IPC");
+    decode_granules(&batches).expect("Failed to decode granules")
+}
+
+#[wasm_bindgen_test]
+async fn fixture_month_is_decoded_and_meshed() {
+    let filter = fixture_month();
+    let connection = fixture_connection(&filter).await;
+    let (granule_ipc, outline_ipc) = request(&connection, filter)
+        .await
+        .expect("Failed to request fixture");
+
+    // 5 frames, the antimeridian crossing and MultiPolygon frames are each split in two
+    let granules = decode_granules(&decode_ipc(granule_ipc.to_vec()).expect("Invalid IPC"))
+        .expect("Failed to decode granules");
+    assert_eq!(granules.len(), 7);
+    assert_eq!(granules.iter().map(|x| x.weight).sum::<u64>(), 8);
+
+    let outline = decode_outline(&decode_ipc(outline_ipc.to_vec()).expect("Invalid IPC"))
+        .expect("Failed to decode outline");
+    assert_eq!(outline.len(), 4);
+
+    let lod_config = LodConfig::default();
+    let meshed = mesh_data(Data::Heatmap(granules), &lod_config);
+    assert_eq!(meshed.len(), lod_config.levels.len());
+    for lod in &meshed {
+        assert!(lod.num_indices > 0);
+        assert_eq!(lod.indices.len(), lod.num_indices as usize);
+        assert_eq!(lod.indices.len() % 3, 0);
+        assert!(
+            lod.indices
+                .iter()
+                .all(|x| (*x as usize) < lod.vertices.len())
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn fixture_filters_by_platform() {
+    let mut filter = fixture_month();
+    filter.platform_type = vec![PlatformType::Sentinel1C];
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 1);
+    assert_eq!(granules[0].weight, 1);
+}
+
+#[wasm_bindgen_test]
+async fn fixture_other_missions_keep_sentinel1() {
+    // The fixture only has Sentinel-1 data, selecting other missions must not drop any of it
+    let mut filter = fixture_month();
+    filter
+        .platform_type
+        .extend([PlatformType::Nisar, PlatformType::Ers1]);
+    filter.product_type.push(ProductTypes::GeocodedCovariance);
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 7);
+    assert_eq!(granules.iter().map(|x| x.weight).sum::<u64>(), 8);
+
+    let mut filter = fixture_month();
+    filter.platform_type = vec![PlatformType::Nisar];
+    assert!(request_granules(filter).await.is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn fixture_filters_by_beam_mode_and_polarization() {
+    // Both Alaska frames and the MultiPolygon frame are IW dual-pol VV
+    let mut filter = fixture_month();
+    filter.beam_mode = vec![BeamMode::InterferometricWideSwath];
+    filter.polarization = vec![Polarization::DualHH, Polarization::DualVV];
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 4);
+    assert_eq!(granules.iter().map(|x| x.weight).sum::<u64>(), 5);
+
+    // Only the EW frame is dual-pol HH
+    let mut filter = fixture_month();
+    filter.polarization = vec![Polarization::DualHH];
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 1);
+}
+
+#[wasm_bindgen_test]
+async fn fixture_filters_by_orbit() {
+    // Ascending passes over relative orbit 94 are the first Alaska frame and the MultiPolygon frame
+    let mut filter = fixture_month();
+    filter.orbit_direction = Some(OrbitDirection::Ascending);
+    filter.relative_orbit = vec![90..=100];
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 3);
+    assert_eq!(granules.iter().map(|x| x.weight).sum::<u64>(), 4);
+
+    // The only descending pass over Alaska
+    let mut filter = fixture_month();
+    filter.orbit_direction = Some(OrbitDirection::Descending);
+    filter.relative_orbit = vec![102..=102];
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 1);
+}
+
+#[wasm_bindgen_test]
+async fn fixture_clips_to_aoi() {
+    // Both Alaska frames overlap the AOI, every other frame is outside of it
+    let mut filter = fixture_month();
+    let aoi = Rect::new(coord! { x: -149.0, y: 61.0 }, coord! { x: -146.0, y: 64.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 2);
+    assert_eq!(granules.iter().map(|x| x.weight).sum::<u64>(), 3);
+
+    for granule in &granules {
+        let bounds = granule
+            .geometry
+            .bounding_rect()
+            .expect("Clipped granule is empty");
+        assert!(bounds.min().x >= aoi.min().x && bounds.min().y >= aoi.min().y);
+        assert!(bounds.max().x <= aoi.max().x && bounds.max().y <= aoi.max().y);
+    }
+}
+
+#[wasm_bindgen_test]
+async fn fixture_inspects_point() {
+    // Inside both Alaska frames, the first is acquired on the 2nd and 14th and the second on the 20th
+    let filter = fixture_month();
+    let connection = fixture_connection(&filter).await;
+    let point = coord! { x: -146.5, y: 63.5 };
+
+    let granules = request_inspect(&connection, &filter, point)
+        .await
+        .expect("Failed to inspect fixture");
+    assert_eq!(granules.len(), 3);
+    assert_eq!(granules[0].start_time, "2026-06-02 12:00:00");
+    assert_eq!(granules[2].start_time, "2026-06-20 12:00:00");
+
+    // Nothing counts outside of the area of interest
+    let mut filter = filter;
+    let aoi = Rect::new(coord! { x: -149.0, y: 61.0 }, coord! { x: -147.0, y: 63.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+    let granules = request_inspect(&connection, &filter, point)
+        .await
+        .expect("Failed to inspect fixture");
+    assert!(granules.is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn fixture_lists_granules_in_view() {
+    // Only the Alaska frames are in view, the footprint is the whole frame
+    let filter = fixture_month();
+    let connection = fixture_connection(&filter).await;
+    let viewport = Rect::new(coord! { x: -151.0, y: 59.0 }, coord! { x: -142.0, y: 68.0 });
+
+    let granules = request_granule_list(&connection, &filter, viewport)
+        .await
+        .expect("Failed to list fixture granules");
+    assert_eq!(granules.len(), 3);
+    assert_eq!(granules[0].start_time, "2026-06-02 12:00:00");
+    assert_eq!(granules[2].platform_type, "5C");
+    assert_eq!(granules[2].product_type.as_deref(), Some("GRD"));
+    let bounds = granules[2]
+        .footprint
+        .bounding_rect()
+        .expect("Footprint is empty");
+    assert_eq!((bounds.min().x, bounds.max().y), (-148.0, 67.0));
+
+    // The area of interest is listed instead of the viewport, it only overlaps the first frame
+    let mut filter = filter;
+    let aoi = Rect::new(coord! { x: -150.0, y: 60.0 }, coord! { x: -149.0, y: 61.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+    let granules = request_granule_list(&connection, &filter, viewport)
+        .await
+        .expect("Failed to list fixture granules");
+    assert_eq!(granules.len(), 2);
+    assert!(granules.iter().all(|x| x.platform_type == "SA"));
+}
+
+#[wasm_bindgen_test]
+async fn fixture_splits_antimeridian_frame() {
+    // The WV frame spans 178 to -178, taken as is its footprint would cover every other longitude
+    let filter = fixture_month();
+    let connection = fixture_connection(&filter).await;
+
+    for lon in [179.0, -179.0] {
+        let granules = request_inspect(&connection, &filter, coord! { x: lon, y: -17.5 })
+            .await
+            .expect("Failed to inspect fixture");
+        assert_eq!(granules.len(), 1);
+        assert_eq!(granules[0].start_time, "2026-06-10 12:00:00");
+    }
+    let granules = request_inspect(&connection, &filter, coord! { x: 0.0, y: -17.5 })
+        .await
+        .expect("Failed to inspect fixture");
+    assert!(granules.is_empty());
+
+    let viewport = Rect::new(coord! { x: -10.0, y: -25.0 }, coord! { x: 10.0, y: -10.0 });
+    let granules = request_granule_list(&connection, &filter, viewport)
+        .await
+        .expect("Failed to list fixture granules");
+    assert!(granules.is_empty());
+
+    let viewport = Rect::new(coord! { x: 170.0, y: -25.0 }, coord! { x: 180.0, y: -10.0 });
+    let granules = request_granule_list(&connection, &filter, viewport)
+        .await
+        .expect("Failed to list fixture granules");
+    assert_eq!(granules.len(), 1);
+    assert!(matches!(&granules[0].footprint, Geometry::MultiPolygon(x) if x.0.len() == 2));
+
+    // An area of interest between the two halves does not clip anything out of the frame
+    let mut filter = filter;
+    let aoi = Rect::new(coord! { x: -10.0, y: -25.0 }, coord! { x: 10.0, y: -10.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+    assert!(request_granules(filter).await.is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn fixture_filters_by_date() {
+    // Only the first Alaska frame has an acquisition before the 3rd
+    let filter = sentinel1_filter(FixtureSource::MONTH, date(2026, 6, 3));
+
+    let granules = request_granules(filter).await;
+    assert_eq!(granules.len(), 1);
+    assert_eq!(granules[0].weight, 1);
+}
+
+#[wasm_bindgen_test]
+async fn fixture_months_without_data_are_empty() {
+    let filter = sentinel1_filter(date(2026, 8, 1), date(2026, 10, 1));
+
+    assert!(request_granules(filter).await.is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn fixture_split_ingest_does_not_double_count() {
+    // The first Alaska frame has acquisitions on both sides of the 14th
+    let first = sentinel1_filter(FixtureSource::MONTH, date(2026, 6, 14));
+    let filter = fixture_month();
+
+    let connection = fixture_connection(&first).await;
+    let remaining = filter.date_range.subtract(&[first.date_range]);
+    for gap in &remaining {
+        ingest(&connection, gap).await;
+    }
+
+    let (granule_ipc, _) = request(&connection, filter)
+        .await
+        .expect("Failed to request fixture");
+    let granules = decode_granules(&decode_ipc(granule_ipc.to_vec()).expect("Invalid IPC"))
+        .expect("Failed to decode granules");
+    assert_eq!(granules.iter().map(|x| x.weight).sum::<u64>(), 8);
+}
@@ -0,0 +1,92 @@
+// Writes the granules listed for an export as CSV or GeoJSON, footprints are written as WKT in
+//     the CSV and as geometries in the GeoJSON
+
+use geojson::{Feature, FeatureCollection, JsonObject, JsonValue};
+use wkt::ToWkt as _;
+
+use crate::types::{ExportedGranule, GranuleFormat};
+
+const CSV_HEADER: &str = "granule_name,platform_type,product_type,start_time,footprint";
+
+/// Write the granules in the given format
+pub fn write_granules(granules: &[ExportedGranule], format: GranuleFormat) -> String {
+    match format {
+        GranuleFormat::Csv => granules_csv(granules),
+        GranuleFormat::GeoJson => granules_geojson(granules),
+    }
+}
+
+/// One row for each granule after a header, a missing product type is left empty
+pub fn granules_csv(granules: &[ExportedGranule]) -> String {
+    let rows = granules.iter().map(|granule| {
+        [
+            granule.granule_name.as_str(),
+            granule.platform_type.as_str(),
+            granule.product_type.as_deref().unwrap_or_default(),
+            granule.start_time.as_str(),
+            &granule.footprint.wkt_string(),
+        ]
+        .map(csv_field)
+        .join(",")
+    });
+
+    std::iter::once(CSV_HEADER.to_string())
+        .chain(rows)
+        .map(|row| row + "\n")
+        .collect()
+}
+
+/// A `FeatureCollection` with a feature for each granule, a missing product type is null
+pub fn granules_geojson(granules: &[ExportedGranule]) -> String {
+    let features = granules
+        .iter()
+        .map(|granule| {
+            let mut properties = JsonObject::new();
+            properties.insert(
+                "granule_name".to_string(),
+                JsonValue::from(granule.granule_name.clone()),
+            );
+            properties.insert(
+                "platform_type".to_string(),
+                JsonValue::from(granule.platform_type.clone()),
+            );
+            properties.insert(
+                "product_type".to_string(),
+                granule
+                    .product_type
+                    .clone()
+                    .map_or(JsonValue::Null, JsonValue::from),
+            );
+            properties.insert(
+                "start_time".to_string(),
+                JsonValue::from(granule.start_time.clone()),
+            );
+
+            Feature {
+                bbox: None,
+                geometry: Some(geojson::Geometry::new(geojson::Value::from(
+                    &granule.footprint,
+                ))),
+                id: None,
+                properties: Some(properties),
+                foreign_members: None,
+            }
+        })
+        .collect();
+
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+    .to_string()
+}
+
+// Fields with a comma, quote or line break are quoted, ie. every WKT footprint
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,75 @@
+//! Tests of writing the granules listed for an export as CSV and GeoJSON
+use geo::{Geometry, Rect, coord};
+use geojson::GeoJson;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::granule_export::{granules_csv, granules_geojson};
+use crate::types::ExportedGranule;
+
+fn granules() -> Vec<ExportedGranule> {
+    let frame = Rect::new(coord! { x: -150.0, y: 60.0 }, coord! { x: -145.0, y: 65.0 });
+    vec![
+        ExportedGranule {
+            granule_name: "S1A_IW_GRDH_1SDV_20260602T120000".to_string(),
+            platform_type: "SA".to_string(),
+            product_type: Some("GRD".to_string()),
+            start_time: "2026-06-02 12:00:00".to_string(),
+            footprint: Geometry::Polygon(frame.to_polygon()),
+        },
+        ExportedGranule {
+            granule_name: "ALOS2_\"quoted\"".to_string(),
+            platform_type: "ALOS-2".to_string(),
+            product_type: None,
+            start_time: "2026-06-20 12:00:00".to_string(),
+            footprint: Geometry::Polygon(frame.to_polygon()),
+        },
+    ]
+}
+
+#[wasm_bindgen_test]
+fn granules_as_csv() {
+    assert_eq!(
+        granules_csv(&granules()),
+        "granule_name,platform_type,product_type,start_time,footprint
+S1A_IW_GRDH_1SDV_20260602T120000,SA,GRD,2026-06-02 12:00:00,\"POLYGON((-145 60,-145 65,-150 65,-150 60,-145 60))\"
+\"ALOS2_\"\"quoted\"\"\",ALOS-2,,2026-06-20 12:00:00,\"POLYGON((-145 60,-145 65,-150 65,-150 60,-145 60))\"
+"
+    );
+
+    // Only the header is written when nothing matches
+    assert_eq!(
+        granules_csv(&[]),
+        "granule_name,platform_type,product_type,start_time,footprint\n"
+    );
+}
+
+#[wasm_bindgen_test]
+fn granules_as_geojson() {
+    let GeoJson::FeatureCollection(collection) = granules_geojson(&granules())
+        .parse::<GeoJson>()
+        .expect("Failed to parse exported GeoJSON")
+    else {
+        panic!("Expected a FeatureCollection");
+    };
+
+    assert_eq!(collection.features.len(), 2);
+    let feature = &collection.features[0];
+    assert_eq!(
+        feature.property("granule_name").and_then(|x| x.as_str()),
+        Some("S1A_IW_GRDH_1SDV_20260602T120000")
+    );
+    assert_eq!(
+        feature.property("product_type").and_then(|x| x.as_str()),
+        Some("GRD")
+    );
+    assert!(matches!(
+        feature.geometry.as_ref().map(|x| &x.value),
+        Some(geojson::Value::Polygon(_))
+    ));
+
+    assert!(
+        collection.features[1]
+            .property("product_type")
+            .is_some_and(serde_json::Value::is_null)
+    );
+}
@@ -1,25 +1,31 @@
 extern crate earcutr;
-use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
 use std::sync::Mutex;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 
-use geo::geometry::Polygon;
+use geo::geometry::{Coord, MultiPolygon, Polygon, Rect};
 use leptos::logging::log;
-use leptos::prelude::{GetUntracked, Set, Update, signal};
+use leptos::prelude::Set;
 use winit::event_loop::EventLoopProxy;
 
-use super::request::request;
+use super::request::{request, request_granule_list, request_inspect, split_antimeridian_granules};
 use crate::canvas::app::UserMessage;
 use crate::canvas::geometry::BlendVertex;
+use crate::ingest::aoi;
 use crate::ingest::async_duckdb::{AsyncDuckDBConnection, generate_duckdb_connection};
-use crate::ingest::mesh::mesh_data;
+use crate::ingest::cache;
+use crate::ingest::error::IngestError;
+use crate::ingest::lod::LodConfig;
+use crate::ingest::source::{DataSource, configured_source};
 use crate::ingest::sql::generate_ingest_world_outline_sql;
 use crate::ingest::sql::{generate_create_sat_data_sql, generate_populate_sat_data_sql};
-use crate::types::DateRange;
-use crate::types::{Filter, Granule};
+use crate::ingest::worker::{MeshKind, MeshWorker};
+use crate::mission::Mission;
+use crate::types::{AoiFile, DateRange, LoadState};
+use crate::types::{ExportedGranule, Filter, Granule, InspectedGranule};
 
 pub enum Data {
     Outline(Vec<Polygon>),
@@ -34,127 +40,106 @@ pub struct BufferStorage {
 }
 
 // Struct that is responsible for submitting storing/subsetting data with DuckDB
+#[derive(Clone)]
 pub struct DataLoader {
     pub event_loop_proxy: Rc<EventLoopProxy<UserMessage<'static>>>,
-    pub active_requests: Rc<leptos::prelude::ReadSignal<u32>>,
-    pub set_active_requests: Rc<leptos::prelude::WriteSignal<u32>>,
-    pub set_ready: Rc<leptos::prelude::WriteSignal<bool>>,
+    pub set_ready: leptos::prelude::WriteSignal<bool>,
+    pub set_load_state: leptos::prelude::WriteSignal<LoadState>,
     pub connection: Rc<AsyncDuckDBConnection>,
-    ingested_data: Mutex<Vec<DateRange>>,
-    ingest_queue: Rc<Mutex<VecDeque<String>>>,
+    pub mesh_worker: Rc<MeshWorker>,
+    pub source: Rc<dyn DataSource>,
+    // Ranges of each mission that have been successfully ingested into DuckDB
+    ingested_data: Rc<Mutex<HashMap<Mission, Vec<DateRange>>>>,
+    // Ranges currently being ingested, they are neither queued nor ingested yet
+    in_flight: Rc<RefCell<Option<(Mission, Vec<DateRange>)>>>,
+    // Monthly files left to ingest along with the days to ingest from each
+    ingest_queue: Rc<Mutex<VecDeque<(Mission, String, Vec<DateRange>)>>>,
     ingest_flag: Rc<RefCell<AtomicBool>>,
     ingest_filter: Rc<RefCell<Filter>>,
+    // Incremented by every call to load_data, results from an older generation are discarded
+    generation: Rc<Cell<u64>>,
 }
 
 impl DataLoader {
     pub async fn new(
         event_loop_proxy: EventLoopProxy<UserMessage<'static>>,
         set_ready: leptos::prelude::WriteSignal<bool>,
+        set_load_state: leptos::prelude::WriteSignal<LoadState>,
         filter: &Filter,
-    ) -> Self {
-        let (active_requests, set_active_requests) = signal(0);
-        // TO-DO: Make data ingest incremental based on needed data
-        let connection = Rc::new(
-            generate_duckdb_connection()
-                .await
-                .expect("Failed to get connection to DuckDB"),
-        );
-        connection
-            .query("LOAD httpfs;")
-            .await
-            .expect("Failed to install/load httpfs in DuckDB");
-        connection
-            .query("LOAD spatial;")
-            .await
-            .expect("Failed to install/load spatial in DuckDB");
-        connection
-            .query(&generate_create_sat_data_sql())
-            .await
-            .expect("Failed to create sat_data table");
-
-        connection
-            .query(&generate_ingest_world_outline_sql())
-            .await
-            .expect("Failed to ingest world outlines in DuckDB");
-        Self {
+        lod_config: Rc<LodConfig>,
+    ) -> Result<Self, IngestError> {
+        let source: Rc<dyn DataSource> = configured_source()?.into();
+
+        let connection = Rc::new(prepare_connection(source.as_ref()).await?);
+
+        // Decoding and meshing happens in a worker to keep the main thread responsive
+        let mesh_worker = Rc::new(MeshWorker::spawn(lod_config).await?);
+
+        Ok(Self {
             event_loop_proxy: Rc::new(event_loop_proxy),
-            active_requests: Rc::new(active_requests),
-            set_active_requests: Rc::new(set_active_requests),
-            set_ready: Rc::new(set_ready),
+            set_ready,
+            set_load_state,
             connection,
-            ingested_data: Mutex::new(vec![]),
+            mesh_worker,
+            source,
+            ingested_data: Rc::new(Mutex::new(HashMap::new())),
+            in_flight: Rc::new(RefCell::new(None)),
             ingest_queue: Rc::new(Mutex::new(VecDeque::new())),
             ingest_flag: Rc::new(RefCell::new(AtomicBool::new(false))),
             ingest_filter: Rc::new(RefCell::new(filter.clone())),
-        }
+            generation: Rc::new(Cell::new(0)),
+        })
     }
 
-    // Updates signals and starts the process of requesting new data based on filter
+    // Updates signals and starts the process of requesting new data based on filter,
+    //     any load that is still running for a previous filter is superseded
     pub fn load_data(&self, filter: Filter) {
-        self.set_active_requests.update(|n| *n += 1);
+        self.generation.set(self.generation.get().wrapping_add(1));
         self.set_ready.set(false);
+        self.set_load_state.set(LoadState::Ingesting);
 
         {
-            // Check for missing data in DuckDB
-            let mut data_guard = self
+            let data_guard = self
                 .ingested_data
                 .lock()
                 .expect("Failed to get mutex lock for ingested data, mutex poisoned");
-            let missing: Vec<DateRange> = if data_guard.is_empty() {
-                vec![filter.date_range.clone()]
-            } else {
-                (*data_guard)
-                    .iter()
-                    .filter_map(|x| x.get_disjoint(&filter.date_range))
-                    .flatten()
-                    .fold(Vec::<DateRange>::new(), |mut acc, x| {
-                        log!("acc: {acc:?}");
-                        if acc.is_empty() {
-                            log!("First DateRange: {x:?}");
-                            acc.push(x);
-                            acc
-                        } else {
-                            acc.into_iter()
-                                .flat_map(|mut y| {
-                                    if y.merge(&x).is_err() {
-                                        log!("Failed to merge {x:?}, pushing to acc");
-                                        return vec![y, x.clone()];
-                                    }
-                                    log!("Merged {x:?} and {y:?}");
-                                    vec![y]
-                                })
-                                .collect()
-                        }
-                    })
-            };
+            let in_flight = self.in_flight.borrow();
+
+            // Days of each selected mission already in DuckDB or currently being ingested are skipped
+            let missing: Vec<(Mission, String, Vec<DateRange>)> = filter
+                .missions()
+                .into_iter()
+                .flat_map(|mission| {
+                    let covered: Vec<DateRange> = data_guard
+                        .get(&mission)
+                        .into_iter()
+                        .flatten()
+                        .chain(
+                            in_flight
+                                .iter()
+                                .filter(|(x, _)| *x == mission)
+                                .flat_map(|(_, ranges)| ranges),
+                        )
+                        .cloned()
+                        .collect();
+                    self.source
+                        .sat_data_files(mission, &filter.date_range.subtract(&covered))
+                        .into_iter()
+                        .map(|(url, days)| (mission, url, days))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
 
             log!("Filter: {:?}", filter.date_range);
-            log!("Missing: {missing:?}");
+            log!("Ingested: {data_guard:?}");
 
+            // Drop files queued for a previous filter, they are no longer needed
             let mut queue_guard = self
                 .ingest_queue
                 .lock()
                 .expect("Failed to lock ingest queue, mutex poisoned");
-            for range in missing {
-                // new_range is range clipped to file resolution so start: 2020-01-05, end: 2020-01-07
-                // becomes start: 2020-01-01, end 2020-02-01 since the smallest time unit we can ingest is one month
-                let (sql, new_range) = generate_populate_sat_data_sql(&range);
-                for stmt in sql {
-                    queue_guard.push_back(stmt);
-                }
-
-                let mut merged = false;
-                for ingested_range in &mut (*data_guard) {
-                    if ingested_range.merge(&new_range).is_ok() {
-                        merged = true;
-                    }
-                }
-                if !merged {
-                    data_guard.push(new_range);
-                }
-
-                log!("New Ingested Data Range: {data_guard:?}");
-            }
+            queue_guard.clear();
+            queue_guard.extend(missing);
         }
 
         *self.ingest_filter.borrow_mut() = filter;
@@ -162,71 +147,184 @@ impl DataLoader {
         if !(self.ingest_flag.borrow().load(Ordering::Acquire)) {
             // Disallow further threads until we finish loading data
             self.ingest_flag.borrow_mut().store(true, Ordering::Release);
-            leptos::task::spawn_local(load_data_async(
-                self.event_loop_proxy.clone(),
-                self.active_requests.clone(),
-                self.set_active_requests.clone(),
-                self.connection.clone(),
-                self.ingest_queue.clone(),
-                self.ingest_flag.clone(),
-                self.ingest_filter.clone(),
-            ));
+            leptos::task::spawn_local(self.clone().load_data_async());
         }
     }
-}
 
-async fn load_data_async(
-    event_loop_proxy: Rc<EventLoopProxy<UserMessage<'static>>>,
-    active_requests: Rc<leptos::prelude::ReadSignal<u32>>,
-    set_active_requests: Rc<leptos::prelude::WriteSignal<u32>>,
-    connection: Rc<AsyncDuckDBConnection>,
-    ingest_queue: Rc<Mutex<VecDeque<String>>>,
-    ingest_flag: Rc<RefCell<AtomicBool>>,
-    ingest_filter: Rc<RefCell<Filter>>,
-) {
-    loop {
-        let sql_vec: Option<String>;
-        {
-            let mut guard = ingest_queue
-                .lock()
-                .expect("Failed to get lock for ingest queue, mutex poisoned");
-            sql_vec = guard.pop_front().clone();
+    /// Acquisitions that count towards the heatmap of the filter at a point, only data that has
+    ///     already been ingested is searched
+    pub async fn inspect(
+        &self,
+        filter: &Filter,
+        point: Coord,
+    ) -> Result<Vec<InspectedGranule>, IngestError> {
+        request_inspect(&self.connection, filter, point).await
+    }
+
+    /// Acquisitions that count towards the heatmap of the filter in its area of interest, or the
+    ///     viewport when it has none, only data that has already been ingested is listed
+    pub async fn list_granules(
+        &self,
+        filter: &Filter,
+        viewport: Rect,
+    ) -> Result<Vec<ExportedGranule>, IngestError> {
+        request_granule_list(&self.connection, filter, viewport).await
+    }
+
+    /// Read the polygons out of a file the user imported as their area of interest
+    pub async fn import_aoi(&self, file: &AoiFile) -> Result<MultiPolygon, IngestError> {
+        aoi::import_aoi(&self.connection, file).await
+    }
+
+    /// Insert the days of a monthly file into `sat_data`, going through the persistent cache
+    ///     when the source allows it and the month is complete
+    async fn ingest_file(&self, url: &str, days: &[DateRange]) -> Result<(), IngestError> {
+        // Every range passed for a file lies in its month
+        let complete = days
+            .first()
+            .is_some_and(|range| cache::is_complete(range.start, cache::today()));
+        if !self.source.cacheable() || !complete {
+            return populate_sat_data(&self.connection, url, days).await;
         }
 
-        if let Some(sql) = sql_vec {
-            log!("SQL: {sql:?}");
+        let file = cache::open(&self.connection, url).await?;
+        let result = populate_sat_data(&self.connection, &file, days).await;
+        cache::release(&self.connection, &file).await;
+        result
+    }
+
+    async fn load_data_async(self) {
+        loop {
+            let task: Option<(Mission, String, Vec<DateRange>)>;
+            {
+                let mut guard = self
+                    .ingest_queue
+                    .lock()
+                    .expect("Failed to get lock for ingest queue, mutex poisoned");
+                task = guard.pop_front();
+            }
+
+            let Some((mission, url, ranges)) = task else {
+                break;
+            };
 
             // Ingest Missing Data
-            log!("Executing: {sql}");
-            if let Err(e) = connection.query(&sql).await {
-                log!("Error while ingesting data: {e:?}");
+            log!("Ingesting {mission} {ranges:?} from {url}");
+            *self.in_flight.borrow_mut() = Some((mission, ranges.clone()));
+            let result = self.ingest_file(&url, &ranges).await;
+            *self.in_flight.borrow_mut() = None;
+
+            match result {
+                Ok(_) => {
+                    let mut data_guard = self
+                        .ingested_data
+                        .lock()
+                        .expect("Failed to get mutex lock for ingested data, mutex poisoned");
+                    let ingested = data_guard.entry(mission).or_default();
+                    for range in ranges {
+                        DateRange::insert_into(ingested, range);
+                    }
+                    log!("New Ingested Data Range: {data_guard:?}");
+                }
+                Err(e) => {
+                    log!("Error while ingesting data: {e:?}");
+                    let _ = self
+                        .event_loop_proxy
+                        .send_event(UserMessage::IngestFailed(e));
+                }
             }
-        } else {
-            break;
         }
-    }
 
-    // Unset ingest flag to allow future data loading
-    ingest_flag.borrow_mut().store(false, Ordering::Release);
+        // Unset ingest flag to allow future data loading
+        self.ingest_flag
+            .borrow_mut()
+            .store(false, Ordering::Release);
 
-    log!("Active Requests: {:?}", active_requests.get_untracked());
-    // Convert the data into a triangular mesh
-    if active_requests.get_untracked() == 1 {
-        // Request data from the server
-        let filter: Filter;
-        {
-            filter = ingest_filter.borrow().clone();
+        // Everything queued has been ingested, the latest filter is the one to mesh
+        let generation = self.generation.get();
+        let filter = self.ingest_filter.borrow().clone();
+        self.set_load_state.set(LoadState::Meshing);
+
+        let result = request_and_mesh(&self.connection, &self.mesh_worker, filter).await;
+
+        // A newer filter was submitted while meshing, its own load will send the results
+        if self.generation.get() != generation {
+            log!("Discarding results for superseded load {generation}");
+            return;
         }
-        let (data, outline_data) = request(&connection, filter).await;
 
-        log!("Meshing data...");
-        let meshed_data = mesh_data(Data::Heatmap(data));
-        let meshed_outline_data = mesh_data(Data::Outline(outline_data));
+        match result {
+            Ok((meshed_data, meshed_outline_data)) => {
+                // Send the triangular mesh to the event loop
+                log!("Sending Mesh to event loop");
+                let _ = self
+                    .event_loop_proxy
+                    .send_event(UserMessage::IncomingData(meshed_data, meshed_outline_data));
+            }
+            Err(e) => {
+                log!("Error while requesting data: {e}");
+                let _ = self
+                    .event_loop_proxy
+                    .send_event(UserMessage::IngestFailed(e));
+            }
+        }
+        self.set_load_state.set(LoadState::Idle);
+    }
+}
 
-        // Send the triangular mesh to the event loop
-        log!("Sending Mesh to event loop");
-        let _ = event_loop_proxy
-            .send_event(UserMessage::IncomingData(meshed_data, meshed_outline_data));
+/// Connect to `DuckDB` and create the tables data is ingested into, files bundled with the
+///     source are registered first so they can be read like any other parquet file
+pub async fn prepare_connection(
+    source: &dyn DataSource,
+) -> Result<AsyncDuckDBConnection, IngestError> {
+    let connection = generate_duckdb_connection().await.map_err(|err| {
+        IngestError::Query(format!("Failed to get connection to DuckDB: {err:?}"))
+    })?;
+    for (name, bytes) in source.bundled_files() {
+        connection.register_file(name, bytes).await?;
     }
-    set_active_requests.update(|n| *n -= 1);
+    connection.query("LOAD httpfs;").await?;
+    connection.query("LOAD spatial;").await?;
+    connection.query(&generate_create_sat_data_sql()).await?;
+    connection
+        .query(&generate_ingest_world_outline_sql(source))
+        .await?;
+
+    Ok(connection)
+}
+
+/// Insert the days of a parquet file into `sat_data` and split the footprints it added that cross
+///     the antimeridian
+pub async fn populate_sat_data(
+    connection: &AsyncDuckDBConnection,
+    file: &str,
+    days: &[DateRange],
+) -> Result<(), IngestError> {
+    connection
+        .query(&generate_populate_sat_data_sql(file, days))
+        .await?;
+    split_antimeridian_granules(connection).await
+}
+
+/// Query `DuckDB` for the filtered data and mesh it in the worker, the previous heatmap
+///     keeps rendering until the new mesh is sent to the event loop
+async fn request_and_mesh(
+    connection: &AsyncDuckDBConnection,
+    mesh_worker: &MeshWorker,
+    filter: Filter,
+) -> Result<(Vec<BufferStorage>, Vec<BufferStorage>), IngestError> {
+    let (granule_ipc, outline_ipc) = request(connection, filter).await?;
+
+    log!("Meshing data in worker...");
+    let meshed_data = mesh_worker.mesh(granule_ipc, MeshKind::Heatmap).await?;
+    let meshed_outline_data = mesh_worker.mesh(outline_ipc, MeshKind::Outline).await?;
+
+    for summary in mesh_worker.lod_config().summarize(&meshed_data) {
+        log!("Heatmap {summary}");
+    }
+    for summary in mesh_worker.lod_config().summarize(&meshed_outline_data) {
+        log!("Outline {summary}");
+    }
+
+    Ok((meshed_data, meshed_outline_data))
 }
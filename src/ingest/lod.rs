@@ -0,0 +1,162 @@
+//! Levels of detail the heatmap and world outline are meshed at
+//!
+//! The ladder can be configured with a `<meta name="heatmap-lod" content="...">` tag in the page,
+//!     which takes precedence over the `HEATMAP_LOD` variable set at build time. Levels are written
+//!     as `tolerance@min_zoom` from most to least detailed, ie. `0@30, 0.2@15, 0.4@0`
+use std::fmt;
+
+use leptos::logging::log;
+
+use crate::ingest::error::IngestError;
+use crate::ingest::load::BufferStorage;
+use crate::ingest::source::page_meta;
+
+const META_NAME: &str = "heatmap-lod";
+
+/// A single level of detail, the polygons are simplified with `tolerance` and
+///     are displayed when the camera zoom is at least `min_zoom`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodLevel {
+    pub tolerance: f64,
+    pub min_zoom: f64,
+}
+
+/// Describes which levels of detail are meshed and when each one is rendered,
+///     levels are ordered from most to least detailed
+#[derive(Clone, Debug, PartialEq)]
+pub struct LodConfig {
+    pub levels: Vec<LodLevel>,
+}
+
+impl Default for LodConfig {
+    fn default() -> Self {
+        Self {
+            levels: vec![
+                LodLevel {
+                    tolerance: 0.0,
+                    min_zoom: 30.0,
+                },
+                LodLevel {
+                    tolerance: 0.2,
+                    min_zoom: 15.0,
+                },
+                LodLevel {
+                    tolerance: 0.4,
+                    min_zoom: 0.0,
+                },
+            ],
+        }
+    }
+}
+
+impl LodConfig {
+    /// Levels of detail ordered from most to least detailed, tolerances may not decrease and
+    ///     zoom thresholds must decrease so every level is rendered at some zoom
+    pub fn new(levels: Vec<LodLevel>) -> Result<Self, IngestError> {
+        if levels.is_empty() {
+            return Err(IngestError::Config(
+                "At least 1 level of detail is needed".to_string(),
+            ));
+        }
+        if let Some(level) = levels.iter().find(|x| {
+            !(x.tolerance.is_finite() && x.tolerance >= 0.0)
+                || !(x.min_zoom.is_finite() && x.min_zoom >= 0.0)
+        }) {
+            return Err(IngestError::Config(format!(
+                "Level of detail {}@{} must have a tolerance and zoom of at least 0",
+                level.tolerance, level.min_zoom
+            )));
+        }
+        if levels
+            .windows(2)
+            .any(|x| x[1].tolerance < x[0].tolerance || x[1].min_zoom >= x[0].min_zoom)
+        {
+            return Err(IngestError::Config(
+                "Levels of detail must be ordered from most to least detailed".to_string(),
+            ));
+        }
+
+        Ok(Self { levels })
+    }
+
+    /// Parse levels of detail written as a comma separated list of `tolerance@min_zoom`
+    pub fn parse(spec: &str) -> Result<Self, IngestError> {
+        let levels = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|level| {
+                level
+                    .split_once('@')
+                    .and_then(|(tolerance, min_zoom)| {
+                        Some(LodLevel {
+                            tolerance: tolerance.trim().parse().ok()?,
+                            min_zoom: min_zoom.trim().parse().ok()?,
+                        })
+                    })
+                    .ok_or_else(|| {
+                        IngestError::Config(format!(
+                            "Unknown level of detail \"{level}\", expected tolerance@min_zoom"
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<LodLevel>, IngestError>>()?;
+
+        Self::new(levels)
+    }
+
+    /// Levels of detail described by the page or build configuration, the default ladder is used
+    ///     if neither sets one
+    pub fn configured() -> Result<Self, IngestError> {
+        let Some(spec) =
+            page_meta(META_NAME).or_else(|| option_env!("HEATMAP_LOD").map(ToString::to_string))
+        else {
+            return Ok(Self::default());
+        };
+        log!("Levels of detail: {spec}");
+
+        Self::parse(&spec)
+    }
+
+    /// Index of the level of detail to render at the passed zoom, falls back to the least detailed level
+    pub fn select(&self, zoom: f64) -> usize {
+        self.levels
+            .iter()
+            .position(|level| zoom >= level.min_zoom)
+            .unwrap_or_else(|| self.levels.len().saturating_sub(1))
+    }
+
+    /// Count the vertices and triangles in each meshed level of detail
+    pub fn summarize(&self, lods: &[BufferStorage]) -> Vec<LodSummary> {
+        self.levels
+            .iter()
+            .zip(lods)
+            .enumerate()
+            .map(|(index, (level, lod))| LodSummary {
+                index,
+                level: *level,
+                vertices: lod.vertices.len(),
+                triangles: lod.num_indices as usize / 3,
+            })
+            .collect()
+    }
+}
+
+/// Size of a meshed level of detail, used to tune the zoom thresholds against real data
+#[derive(Clone, Copy, Debug)]
+pub struct LodSummary {
+    pub index: usize,
+    pub level: LodLevel,
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+impl fmt::Display for LodSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LOD {} (tolerance: {}, zoom >= {}): {} vertices, {} triangles",
+            self.index, self.level.tolerance, self.level.min_zoom, self.vertices, self.triangles
+        )
+    }
+}
@@ -0,0 +1,41 @@
+//! Tests of configuring the levels of detail
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::lod::{LodConfig, LodLevel};
+
+#[wasm_bindgen_test]
+fn lod_default_parses() {
+    assert_eq!(
+        LodConfig::parse("0@30, 0.2@15, 0.4@0").expect("Failed to parse levels of detail"),
+        LodConfig::default()
+    );
+}
+
+#[wasm_bindgen_test]
+fn lod_select_by_zoom() {
+    let lod_config = LodConfig::parse("0@40,0.1@20,0.3@5,0.6@0").expect("Failed to parse levels");
+    assert_eq!(lod_config.levels.len(), 4);
+    assert_eq!(
+        lod_config.levels[1],
+        LodLevel {
+            tolerance: 0.1,
+            min_zoom: 20.0
+        }
+    );
+
+    assert_eq!(lod_config.select(50.0), 0);
+    assert_eq!(lod_config.select(20.0), 1);
+    assert_eq!(lod_config.select(10.0), 2);
+    assert_eq!(lod_config.select(0.0), 3);
+}
+
+#[wasm_bindgen_test]
+fn lod_rejects_invalid() {
+    assert!(LodConfig::parse("").is_err());
+    assert!(LodConfig::parse("0.2").is_err());
+    assert!(LodConfig::parse("a@30").is_err());
+    assert!(LodConfig::parse("-0.1@30, 0.2@0").is_err());
+    // Must go from most to least detailed
+    assert!(LodConfig::parse("0.4@0, 0.2@15, 0@30").is_err());
+    assert!(LodConfig::parse("0@30, 0.2@30").is_err());
+}
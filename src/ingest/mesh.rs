@@ -1,68 +1,41 @@
 extern crate earcutr;
 use std::collections::VecDeque;
 
-use geo::geometry::{Coord, LineString, Polygon};
-use geo::{Simplify, TriangulateEarcut, coord};
+use geo::geometry::{LineString, Polygon};
+use geo::{Simplify, TriangulateEarcut};
 
 use crate::canvas::geometry::BlendVertex;
 use crate::ingest::load::BufferStorage;
 use crate::ingest::load::Data;
+use crate::ingest::lod::LodConfig;
 
 /// Converts the passed data into a triangular mesh using the earcutting algorithm,
 ///     this is done for a varying level of detail to allow for LODs, polygon simplification
-///     is done using the Ramer-Douglas-Peucker algorithm
-pub fn mesh_data(data_exterior: Data) -> Vec<BufferStorage> {
-    let mut positions: Vec<Vec<(f64, f64)>> = Vec::new();
-    let mut weights: Vec<u64> = Vec::new();
-
-    match data_exterior {
-        Data::Outline(outline_data) => {
-            for poly in outline_data {
-                positions.push(poly.exterior().points().map(|x| (x.x(), x.y())).collect());
-                weights.push(0);
-            }
-        }
+///     is done using the Ramer-Douglas-Peucker algorithm. Interior rings are kept so holes
+///     in the data are not filled in. One mesh is generated for each level in `lod_config`
+pub fn mesh_data(data_exterior: Data, lod_config: &LodConfig) -> Vec<BufferStorage> {
+    let (polygons, weights): (Vec<Polygon>, Vec<u64>) = match data_exterior {
+        Data::Outline(outline_data) => outline_data.into_iter().map(|poly| (poly, 0)).unzip(),
 
-        Data::Heatmap(heatmap_data) => {
-            for gran in heatmap_data {
-                positions.push(
-                    gran.geometry
-                        .exterior()
-                        .points()
-                        .map(|x| (x.x(), x.y()))
-                        .collect(),
-                );
-                weights.push(gran.weight);
-            }
-        }
-    }
+        Data::Heatmap(heatmap_data) => heatmap_data
+            .into_iter()
+            .map(|gran| (gran.geometry, gran.weight))
+            .unzip(),
+    };
 
     let mut lods: Vec<BufferStorage> = Vec::new();
 
-    let mut polygons: Vec<Polygon> = positions
-        .iter()
-        .map(|poly| {
-            poly.iter()
-                .map(|(x, y)| {
-                    coord! {x: *x, y: *y}
-                })
-                .collect()
-        })
-        .map(|mut exterior: Vec<Coord>| {
-            // Last entry is a duplicate of the first
-            let _ = exterior.pop();
-            Polygon::new(LineString(exterior.clone()), Vec::new())
-        })
-        .collect();
-
-    let mut level: usize = 0;
-    while level <= 2 {
+    for level in &lod_config.levels {
         let mut weights = VecDeque::from(weights.clone());
         let mut total_vertices: Vec<BlendVertex> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
-        for poly in &mut polygons {
-            let simplified = poly.simplify(&(0.2 * level as f64));
+        for poly in &polygons {
+            let Some(simplified) = simplify_polygon(poly, level.tolerance) else {
+                // Polygon collapsed during simplification, skip it but keep weights aligned
+                let _ = weights.pop_front();
+                continue;
+            };
             // Run the ear cutting algorithm, triangles contains a list of indices after
             let triangles_raw = simplified.earcut_triangles_raw();
 
@@ -105,8 +78,25 @@ pub fn mesh_data(data_exterior: Data) -> Vec<BufferStorage> {
             indices,
             num_indices,
         });
-
-        level += 1;
     }
     lods
 }
+
+/// Simplify the exterior and interior rings of a polygon, holes that collapse are removed
+///     and None is returned if the exterior collapses
+fn simplify_polygon(poly: &Polygon, epsilon: f64) -> Option<Polygon> {
+    let simplified = poly.simplify(&epsilon);
+    let (exterior, interiors) = simplified.into_inner();
+
+    // A closed ring needs at least 3 distinct points, the first point is repeated at the end
+    if exterior.0.len() < 4 {
+        return None;
+    }
+
+    let interiors: Vec<LineString> = interiors
+        .into_iter()
+        .filter(|ring| ring.0.len() >= 4)
+        .collect();
+
+    Some(Polygon::new(exterior, interiors))
+}
@@ -0,0 +1,120 @@
+//! Tests of meshing outlines and granules into triangles
+use geo::{
+    Area as _, Centroid as _, Contains as _, Geometry, MultiPolygon, Polygon, Rect, Triangle, coord,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::load::{BufferStorage, Data};
+use crate::ingest::lod::LodConfig;
+use crate::ingest::mesh::mesh_data;
+use crate::ingest::validate::{ValidationReport, validate_granule};
+use crate::types::Granule;
+
+fn square(min: f64, max: f64) -> Rect {
+    Rect::new(coord! { x: min, y: min }, coord! { x: max, y: max })
+}
+
+// A square with a hole covering the middle 40% of it
+fn square_with_hole(min: f64, size: f64) -> Polygon {
+    Polygon::new(
+        square(min, min + size).to_polygon().exterior().clone(),
+        vec![
+            square(min + size * 0.3, min + size * 0.7)
+                .to_polygon()
+                .exterior()
+                .clone(),
+        ],
+    )
+}
+
+fn triangles(buffer: &BufferStorage) -> Vec<Triangle> {
+    buffer
+        .indices
+        .chunks(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| {
+                let [x, y, _] = buffer.vertices[triangle[i] as usize].position;
+                coord! { x: f64::from(x), y: f64::from(y) }
+            });
+            Triangle::new(a, b, c)
+        })
+        .collect()
+}
+
+fn area(triangles: &[Triangle]) -> f64 {
+    triangles
+        .iter()
+        .map(|triangle| triangle.unsigned_area())
+        .sum()
+}
+
+// No triangle may sit inside of the passed holes
+fn outside(triangles: &[Triangle], holes: &[Rect]) -> bool {
+    triangles
+        .iter()
+        .all(|triangle| !holes.iter().any(|hole| hole.contains(&triangle.centroid())))
+}
+
+#[wasm_bindgen_test]
+fn mesh_outline_keeps_holes() {
+    let lod_config = LodConfig::default();
+    let lods = mesh_data(
+        Data::Outline(vec![square_with_hole(0.0, 10.0)]),
+        &lod_config,
+    );
+    assert_eq!(lods.len(), lod_config.levels.len());
+
+    for lod in &lods {
+        let triangles = triangles(lod);
+        assert_eq!(lod.num_indices as usize, triangles.len() * 3);
+        assert!(lod.vertices.iter().all(|vertex| vertex.weight == 0));
+
+        assert!(outside(&triangles, &[square(3.0, 7.0)]));
+        assert!((area(&triangles) - 84.0).abs() < 1e-3);
+    }
+}
+
+#[wasm_bindgen_test]
+fn mesh_heatmap_keeps_holes_and_weights() {
+    let lods = mesh_data(
+        Data::Heatmap(vec![Granule {
+            geometry: square_with_hole(-20.0, 10.0),
+            weight: 5,
+        }]),
+        &LodConfig::default(),
+    );
+
+    for lod in &lods {
+        let triangles = triangles(lod);
+        assert!(lod.vertices.iter().all(|vertex| vertex.weight == 5));
+
+        assert!(outside(&triangles, &[square(-17.0, -13.0)]));
+        assert!((area(&triangles) - 84.0).abs() < 1e-3);
+    }
+}
+
+#[wasm_bindgen_test]
+fn mesh_heatmap_multipolygon() {
+    // A granule made of 2 squares with holes, every part keeps the weight of the granule
+    let geometry = Geometry::MultiPolygon(MultiPolygon::new(vec![
+        square_with_hole(0.0, 10.0),
+        square_with_hole(20.0, 10.0),
+    ]));
+    let mut report = ValidationReport::default();
+    let granules = validate_granule(geometry, 2, &mut report);
+    assert_eq!(granules.len(), 2);
+
+    let lods = mesh_data(Data::Heatmap(granules), &LodConfig::default());
+    for lod in &lods {
+        let triangles = triangles(lod);
+        assert!(lod.vertices.iter().all(|vertex| vertex.weight == 2));
+
+        // Neither hole or the gap between the squares is filled in
+        let gap = Rect::new(coord! { x: 10.0, y: 0.0 }, coord! { x: 20.0, y: 30.0 });
+        assert!(outside(
+            &triangles,
+            &[square(3.0, 7.0), square(23.0, 27.0), gap]
+        ));
+        assert!((area(&triangles) - 168.0).abs() < 1e-3);
+    }
+}
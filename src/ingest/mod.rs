@@ -1,5 +1,30 @@
+mod aoi;
+#[cfg(test)]
+mod aoi_tests;
 mod async_duckdb;
+pub mod cache;
+#[cfg(test)]
+mod cache_tests;
+pub mod error;
+#[cfg(test)]
+mod fixture_tests;
+pub mod granule_export;
+#[cfg(test)]
+mod granule_export_tests;
 pub mod load;
+pub mod lod;
+#[cfg(test)]
+mod lod_tests;
 mod mesh;
+#[cfg(test)]
+mod mesh_tests;
+mod query;
 mod request;
+pub mod source;
 mod sql;
+#[cfg(test)]
+mod sql_tests;
+mod validate;
+#[cfg(test)]
+mod validate_tests;
+pub mod worker;
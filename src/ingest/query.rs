@@ -0,0 +1,148 @@
+//! Small builder for the SQL sent to `DuckDB`, values are kept separate from the SQL text so they
+//!     can either be bound to a prepared statement or rendered as properly quoted literals
+use std::fmt;
+
+use chrono::NaiveDate;
+use wasm_bindgen::JsValue;
+
+/// A value in a query, bound to a placeholder when the query is prepared
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Param {
+    Text(String),
+    Date(NaiveDate),
+    Int(i64),
+}
+
+impl Param {
+    /// Render the value as a `DuckDB` literal
+    pub fn literal(&self) -> String {
+        match self {
+            Self::Text(text) => format!("'{}'", text.replace('\'', "''")),
+            Self::Date(date) => format!("DATE '{}'", date.format("%Y-%m-%d")),
+            Self::Int(int) => int.to_string(),
+        }
+    }
+
+    // Parameters are bound as JS values so dates are sent as strings and numbers as doubles,
+    //     both are cast back in the SQL
+    const fn placeholder(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "?",
+            Self::Date(_) => "CAST(? AS DATE)",
+            Self::Int(_) => "CAST(? AS INTEGER)",
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        match self {
+            Self::Text(text) => JsValue::from_str(text),
+            Self::Date(date) => JsValue::from_str(&date.format("%Y-%m-%d").to_string()),
+            Self::Int(int) => JsValue::from_f64(*int as f64),
+        }
+    }
+}
+
+impl<T: fmt::Display> From<&T> for Param {
+    fn from(value: &T) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Sql(String),
+    Param(Param),
+}
+
+/// SQL text interleaved with the values it uses
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    parts: Vec<Part>,
+}
+
+impl Query {
+    pub fn new(sql: &str) -> Self {
+        Self::default().sql(sql)
+    }
+
+    /// Append SQL text, this must never contain user input
+    pub fn sql(mut self, sql: &str) -> Self {
+        self.parts.push(Part::Sql(sql.to_string()));
+        self
+    }
+
+    /// Append a single value
+    pub fn param(mut self, param: Param) -> Self {
+        self.parts.push(Part::Param(param));
+        self
+    }
+
+    /// Append a parenthesized list of values for use with IN, an empty list matches nothing
+    pub fn list(mut self, params: impl IntoIterator<Item = Param>) -> Self {
+        let start = self.parts.len();
+        for (i, param) in params.into_iter().enumerate() {
+            self.parts
+                .push(Part::Sql(if i == 0 { "(" } else { ", " }.to_string()));
+            self.parts.push(Part::Param(param));
+        }
+
+        if self.parts.len() == start {
+            self.sql("(NULL)")
+        } else {
+            self.sql(")")
+        }
+    }
+
+    /// Append another query
+    pub fn append(mut self, other: Self) -> Self {
+        self.parts.extend(other.parts);
+        self
+    }
+
+    /// Join queries with a separator, ie. `Query::join(conditions, " AND ")`
+    pub fn join(queries: impl IntoIterator<Item = Self>, separator: &str) -> Self {
+        queries
+            .into_iter()
+            .enumerate()
+            .fold(Self::default(), |acc, (i, query)| {
+                if i == 0 {
+                    acc.append(query)
+                } else {
+                    acc.sql(separator).append(query)
+                }
+            })
+    }
+
+    /// SQL with a placeholder for each value along with the values to bind, in order
+    pub fn prepared(&self) -> (String, Vec<Param>) {
+        let mut sql = String::new();
+        let mut params = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Sql(text) => sql.push_str(text),
+                Part::Param(param) => {
+                    sql.push_str(param.placeholder());
+                    params.push(param.clone());
+                }
+            }
+        }
+        (sql, params)
+    }
+
+    /// SQL with every value rendered as a literal
+    pub fn inline(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Sql(text) => text.clone(),
+                Part::Param(param) => param.literal(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.inline())
+    }
+}
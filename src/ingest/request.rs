@@ -1,99 +1,273 @@
-use arrow::array::{BinaryArray, Int64Array};
-use geo::Polygon;
+use arrow::array::{Array as _, BinaryArray, Int64Array, StringArray};
+use arrow::record_batch::RecordBatch;
+use geo::{Coord, Geometry, MultiPolygon, Polygon, Rect};
 use geo_traits::to_geo::ToGeoGeometry;
+use js_sys::Uint8Array;
 use leptos::logging::log;
 use wkb::reader::read_wkb;
 
 use crate::{
-    ingest::{async_duckdb::AsyncDuckDBConnection, sql::generate_sql},
-    types::{Filter, Granule},
+    ingest::{
+        async_duckdb::{AsyncDuckDBConnection, decode_ipc},
+        error::IngestError,
+        sql::{
+            generate_antimeridian_candidates_sql, generate_granule_list_sql, generate_inspect_sql,
+            generate_split_antimeridian_sql, generate_sql,
+        },
+        validate::{ValidationReport, split_footprint, validate_granule},
+    },
+    types::{ExportedGranule, Filter, Granule, InspectedGranule},
 };
 
-// Send a request to the service for data based on the filter
-pub async fn request(conn: &AsyncDuckDBConnection, filter: Filter) -> (Vec<Granule>, Vec<Polygon>) {
+// Send a request to DuckDB for data based on the filter, the results are left as Arrow IPC
+//     bytes so they can be decoded by the mesh worker instead of on the main thread
+pub async fn request(
+    conn: &AsyncDuckDBConnection,
+    filter: Filter,
+) -> Result<(Uint8Array, Uint8Array), IngestError> {
     log!("Request started...\n\t{:?}", filter.date_range);
 
-    //////////////////////////////
-    //  Process Satellite Data  //
-    //////////////////////////////
-
-    let sql = &generate_sql(&filter);
-    let gran_vec: Vec<Granule> = conn
-        .query(sql)
-        .await
-        .expect("Failed to get data from DuckDB")
-        .iter()
-        .flat_map(|batch| {
-            batch
-                .column(0)
-                .as_any()
-                .downcast_ref::<BinaryArray>()
-                .expect("Sat_data geometry was not a BinaryArray")
-                .iter()
-                .zip(
-                    batch
-                        .column(1)
-                        .as_any()
-                        .downcast_ref::<Int64Array>()
-                        .expect("Weights were not a Int64Array"),
-                )
-                .map(|(wkb_binary, weight)| {
-                    use geo_traits::to_geo::ToGeoGeometry;
-                    let poly = TryInto::<Polygon>::try_into(
-                        read_wkb(wkb_binary.expect("Failed to get [u8] from wkb"))
-                            .expect("Failed to convert sat_data geometry to geo::geometry")
-                            .to_geometry(),
-                    )
-                    .expect("Failed to convert sat data to Polygon");
-                    Granule {
-                        geometry: poly,
-                        weight: weight.expect("Failed to get weight for a polygon") as u64,
-                    }
-                })
-                .collect::<Vec<Granule>>()
-        })
-        .collect();
+    let query = generate_sql(&filter);
+    log!("Query: {query}");
+    let granule_ipc = conn.query_prepared_ipc(&query).await?;
+    let outline_ipc = conn.query_ipc("SELECT geom FROM world_outline;").await?;
+
+    log!("Data succesfully requested");
+    Ok((granule_ipc, outline_ipc))
+}
+
+//////////////////////////////
+//  Process Satellite Data  //
+//////////////////////////////
+
+/// Decode the batches returned by the `generate_sql` query into validated granules
+pub fn decode_granules(batches: &[RecordBatch]) -> Result<Vec<Granule>, IngestError> {
+    let mut gran_vec: Vec<Granule> = Vec::new();
+    let mut report = ValidationReport::default();
+    for batch in batches {
+        let geometries = downcast_column::<BinaryArray>(batch, 0, "sat_data geometry")?;
+        let weights = downcast_column::<Int64Array>(batch, 1, "sat_data weight")?;
+
+        for (wkb_binary, weight) in geometries.iter().zip(weights) {
+            let geometry = decode_wkb(wkb_binary)?;
+            let weight =
+                weight.ok_or_else(|| IngestError::Schema("Granule weight was null".to_string()))?;
+
+            // Splits MultiPolygons and antimeridian crossings, drops anything that is not a polygon
+            gran_vec.extend(validate_granule(geometry, weight as u64, &mut report));
+        }
+    }
 
     log!("Granule Data: {gran_vec:?}");
+    log!(
+        "Granule Validation: {} valid, {} repaired, {} dropped",
+        report.valid,
+        report.repaired,
+        report.dropped
+    );
 
-    ////////////////////////////////
-    //  Ingest World Border Data  //
-    ////////////////////////////////
-
-    let outline_vec: Vec<Polygon> = conn
-        .query("SELECT geom FROM world_outline;")
-        .await
-        .expect("Failed to get world border data")
-        .iter()
-        .flat_map(|batch| {
-            batch
-                .column(0)
-                .as_any()
-                .downcast_ref::<BinaryArray>()
-                .expect(
-                    "DuckDB did not return a BinaryArray for the geometry column of world outline",
-                )
-                .iter()
-                .flat_map(|wkb_binary| {
-                    match read_wkb(
-                        wkb_binary.expect("Failed to read wkb_binary from geometry column"),
-                    )
-                    .expect("Failed to convert wkb to geometry")
-                    .to_geometry()
-                    {
-                        geo::Geometry::MultiPolygon(multi_poly) => {
-                            multi_poly.iter().cloned().collect::<Vec<Polygon>>()
-                        }
-                        geo::Geometry::Polygon(poly) => vec![poly],
-                        _ => vec![],
-                    }
-                })
-                .collect::<Vec<Polygon>>()
-        })
-        .collect();
+    Ok(gran_vec)
+}
+
+/// Split the footprints of newly ingested granules that cross the antimeridian and store the
+///     parts in `sat_data`, so inspecting, listing and clipping granules in `DuckDB` sees the same
+///     footprints as the heatmap
+pub async fn split_antimeridian_granules(conn: &AsyncDuckDBConnection) -> Result<(), IngestError> {
+    let batches = conn.query(&generate_antimeridian_candidates_sql()).await?;
+    if batches.iter().all(|x| x.num_rows() == 0) {
+        return Ok(());
+    }
+
+    let split = decode_split_footprints(&batches)?;
+    log!("Splitting {} granules at the antimeridian", split.len());
+    conn.query(&generate_split_antimeridian_sql(&split)).await?;
+
+    Ok(())
+}
+
+/// Decode the batches returned by the `generate_antimeridian_candidates_sql` query into the row id
+///     and parts of every footprint that crosses the antimeridian
+pub fn decode_split_footprints(
+    batches: &[RecordBatch],
+) -> Result<Vec<(i64, MultiPolygon)>, IngestError> {
+    let mut split_vec: Vec<(i64, MultiPolygon)> = Vec::new();
+    for batch in batches {
+        let rowids = downcast_column::<Int64Array>(batch, 0, "sat_data rowid")?;
+        let geometries = downcast_column::<BinaryArray>(batch, 1, "sat_data geometry")?;
+
+        for (rowid, wkb_binary) in rowids.iter().zip(geometries) {
+            let rowid =
+                rowid.ok_or_else(|| IngestError::Schema("Granule row id was null".to_string()))?;
+            if let Some(parts) = split_footprint(&decode_wkb(wkb_binary)?) {
+                split_vec.push((rowid, parts));
+            }
+        }
+    }
+
+    Ok(split_vec)
+}
+
+////////////////////////////////
+//  Ingest World Border Data  //
+////////////////////////////////
+
+/// Decode the batches returned from the `world_outline` table into polygons
+pub fn decode_outline(batches: &[RecordBatch]) -> Result<Vec<Polygon>, IngestError> {
+    let mut outline_vec: Vec<Polygon> = Vec::new();
+    for batch in batches {
+        let geometries = downcast_column::<BinaryArray>(batch, 0, "world outline geometry")?;
+
+        for wkb_binary in geometries {
+            match decode_wkb(wkb_binary)? {
+                Geometry::MultiPolygon(multi_poly) => outline_vec.extend(multi_poly),
+                Geometry::Polygon(poly) => outline_vec.push(poly),
+                _ => (),
+            }
+        }
+    }
     log!("Outline Data: {outline_vec:?}");
 
-    // Deserialize the json into a HeatmapData struct
-    log!("Data succesfully deserialized");
-    (gran_vec, outline_vec)
+    Ok(outline_vec)
+}
+
+///////////////////////
+//  Inspect a Point  //
+///////////////////////
+
+/// Request the acquisitions that count towards the heatmap at a point, oldest first
+pub async fn request_inspect(
+    conn: &AsyncDuckDBConnection,
+    filter: &Filter,
+    point: Coord,
+) -> Result<Vec<InspectedGranule>, IngestError> {
+    let query = generate_inspect_sql(filter, point);
+    log!("Inspect Query: {query}");
+    let ipc = conn.query_prepared_ipc(&query).await?;
+    let batches = decode_ipc(ipc.to_vec()).map_err(|err| IngestError::Schema(err.to_string()))?;
+
+    decode_inspected(&batches)
+}
+
+/// Decode the batches returned by the `generate_inspect_sql` query
+pub fn decode_inspected(batches: &[RecordBatch]) -> Result<Vec<InspectedGranule>, IngestError> {
+    let mut granule_vec: Vec<InspectedGranule> = Vec::new();
+    for batch in batches {
+        let names = downcast_column::<StringArray>(batch, 0, "granule name")?;
+        let start_times = downcast_column::<StringArray>(batch, 1, "start time")?;
+
+        for (name, start_time) in names.iter().zip(start_times) {
+            let (Some(name), Some(start_time)) = (name, start_time) else {
+                return Err(IngestError::Schema(
+                    "Granule name or start time was null".to_string(),
+                ));
+            };
+            granule_vec.push(InspectedGranule {
+                granule_name: name.to_string(),
+                start_time: start_time.to_string(),
+            });
+        }
+    }
+
+    Ok(granule_vec)
+}
+
+///////////////////////
+//  Export Granules  //
+///////////////////////
+
+/// Request the acquisitions that count towards the heatmap in the area of interest, or the
+///     viewport when there is none, oldest first
+pub async fn request_granule_list(
+    conn: &AsyncDuckDBConnection,
+    filter: &Filter,
+    viewport: Rect,
+) -> Result<Vec<ExportedGranule>, IngestError> {
+    let query = generate_granule_list_sql(filter, viewport);
+    log!("Granule List Query: {query}");
+    let ipc = conn.query_prepared_ipc(&query).await?;
+    let batches = decode_ipc(ipc.to_vec()).map_err(|err| IngestError::Schema(err.to_string()))?;
+
+    decode_exported(&batches)
+}
+
+/// Decode the batches returned by the `generate_granule_list_sql` query
+pub fn decode_exported(batches: &[RecordBatch]) -> Result<Vec<ExportedGranule>, IngestError> {
+    let mut granule_vec: Vec<ExportedGranule> = Vec::new();
+    for batch in batches {
+        let names = downcast_column::<StringArray>(batch, 0, "granule name")?;
+        let platforms = downcast_column::<StringArray>(batch, 1, "platform type")?;
+        let products = downcast_column::<StringArray>(batch, 2, "product type")?;
+        let start_times = downcast_column::<StringArray>(batch, 3, "start time")?;
+        let footprints = downcast_column::<BinaryArray>(batch, 4, "footprint")?;
+
+        for i in 0..batch.num_rows() {
+            if names.is_null(i) || platforms.is_null(i) || start_times.is_null(i) {
+                return Err(IngestError::Schema(
+                    "Granule name, platform or start time was null".to_string(),
+                ));
+            }
+            granule_vec.push(ExportedGranule {
+                granule_name: names.value(i).to_string(),
+                platform_type: platforms.value(i).to_string(),
+                // Missions without a product type in their granule names have none
+                product_type: (!products.is_null(i)).then(|| products.value(i).to_string()),
+                start_time: start_times.value(i).to_string(),
+                footprint: decode_wkb((!footprints.is_null(i)).then(|| footprints.value(i)))?,
+            });
+        }
+    }
+
+    Ok(granule_vec)
+}
+
+////////////////////////////////////
+//  Import Area of Interest Data  //
+////////////////////////////////////
+
+/// Decode every geometry in the first column of the batches, ie. the features of a shapefile
+pub fn decode_geometries(batches: &[RecordBatch]) -> Result<Vec<Geometry>, IngestError> {
+    let mut geometry_vec: Vec<Geometry> = Vec::new();
+    for batch in batches {
+        let geometries = downcast_column::<BinaryArray>(batch, 0, "imported geometry")?;
+
+        for wkb_binary in geometries {
+            geometry_vec.push(decode_wkb(wkb_binary)?);
+        }
+    }
+
+    Ok(geometry_vec)
+}
+
+/// Get a column from a `RecordBatch` as a concrete arrow array type
+fn downcast_column<'a, T: 'static>(
+    batch: &'a RecordBatch,
+    index: usize,
+    name: &str,
+) -> Result<&'a T, IngestError> {
+    if index >= batch.num_columns() {
+        return Err(IngestError::Schema(format!(
+            "Column {index} ({name}) is missing, only {} columns were returned",
+            batch.num_columns()
+        )));
+    }
+
+    batch
+        .column(index)
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| {
+            IngestError::Schema(format!(
+                "Column {index} ({name}) had type {}",
+                batch.column(index).data_type()
+            ))
+        })
+}
+
+/// Decode a single, possibly null, WKB value into a `geo::Geometry`
+fn decode_wkb(wkb_binary: Option<&[u8]>) -> Result<Geometry, IngestError> {
+    let bytes = wkb_binary.ok_or_else(|| IngestError::Schema("Geometry was null".to_string()))?;
+
+    Ok(read_wkb(bytes)
+        .map_err(|err| IngestError::WkbDecode(err.to_string()))?
+        .to_geometry())
 }
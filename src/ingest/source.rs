@@ -0,0 +1,237 @@
+//! Where the parquet files ingested into `DuckDB` are read from
+//!
+//! The source is configured with a spec string, a `<meta name="heatmap-data-source" content="...">`
+//!     tag in the page takes precedence over the `HEATMAP_DATA_SOURCE` variable set at build time.
+//!     Supported specs are:
+//!  - `s3` or `s3:<maturity>` reads from the ASF S3 bucket, maturity defaults to `DEPLOY_PREFIX` or dev
+//!  - `http:<base url>` reads from any HTTP server, ie. a local mirror
+//!  - `static:<directory>` reads from a directory served from the same origin as the client
+//!  - `fixture` reads the sample parquet bundled into the client, no requests are made for data
+use std::iter::successors;
+
+use chrono::{Datelike, Months, NaiveDate};
+use leptos::logging::log;
+use web_sys::Url;
+
+use crate::ingest::error::IngestError;
+use crate::mission::Mission;
+use crate::types::DateRange;
+
+const META_NAME: &str = "heatmap-data-source";
+
+/// Resolves the location of every parquet file the client ingests, implementors only need to
+///     provide the root the files are stored under
+pub trait DataSource {
+    /// URL that every mission's data and the world outline files are stored under, without a
+    ///     trailing slash
+    fn root(&self) -> String;
+
+    /// URL of the parquet file holding a month of a mission's data, month must be the first of
+    ///     the month
+    fn sat_data_url(&self, mission: Mission, month: NaiveDate) -> String {
+        let next = month.checked_add_months(Months::new(1)).unwrap_or(month);
+        format!(
+            "{}/{}/monthly/{}/{}/{}_{}.parquet",
+            self.root(),
+            mission.directory(),
+            month.format("%Y"),
+            month.format("%m"),
+            month.format("%Y-%m-01"),
+            next.format("%Y-%m-01")
+        )
+    }
+
+    /// Monthly files of a mission that hold the passed ranges along with the days of the ranges in
+    ///     each month, a month that holds several of the ranges is listed once so its file is only
+    ///     read once
+    fn sat_data_files(
+        &self,
+        mission: Mission,
+        date_ranges: &[DateRange],
+    ) -> Vec<(String, Vec<DateRange>)> {
+        let mut files: Vec<(String, Vec<DateRange>)> = Vec::new();
+
+        for date_range in date_ranges {
+            let range_start =
+                NaiveDate::from_ymd_opt(date_range.start.year(), date_range.start.month(), 1)
+                    .expect("Failed to create start month");
+
+            for month in successors(Some(range_start), |x| x.checked_add_months(Months::new(1)))
+                .take_while(|x| *x < date_range.end)
+            {
+                let month_end = month
+                    .checked_add_months(Months::new(1))
+                    .expect("Failed to add a month");
+                let days = DateRange {
+                    start: date_range.start.max(month),
+                    end: date_range.end.min(month_end),
+                };
+
+                let url = self.sat_data_url(mission, month);
+                match files.iter_mut().find(|(x, _)| *x == url) {
+                    Some((_, ranges)) => ranges.push(days),
+                    None => files.push((url, vec![days])),
+                }
+            }
+        }
+
+        files
+    }
+
+    /// URL of the parquet file holding the world outline
+    fn world_outline_url(&self) -> String {
+        format!("{}/world_continents.parquet", self.root())
+    }
+
+    /// Files that must be registered with `DuckDB` under the given name before they can be read
+    fn bundled_files(&self) -> &[(&'static str, &'static [u8])] {
+        &[]
+    }
+
+    /// Whether files from this source should be kept in the persistent browser cache
+    fn cacheable(&self) -> bool {
+        true
+    }
+}
+
+/// The public ASF S3 bucket, one bucket exists per deployment maturity
+pub struct AsfS3Source {
+    pub maturity: String,
+}
+
+impl DataSource for AsfS3Source {
+    fn root(&self) -> String {
+        format!("s3://archive-heatmap-storage-{}", self.maturity)
+    }
+}
+
+/// Any HTTP server that mirrors the layout of the ASF S3 bucket
+pub struct HttpSource {
+    pub base_url: String,
+}
+
+impl DataSource for HttpSource {
+    fn root(&self) -> String {
+        self.base_url.trim_end_matches('/').to_string()
+    }
+}
+
+/// A directory served alongside the client, `DuckDB` needs an absolute URL so the
+///     directory is resolved against the page URL when the source is created
+pub struct StaticSource {
+    url: String,
+}
+
+impl StaticSource {
+    pub fn new(directory: &str) -> Result<Self, IngestError> {
+        let page_url = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.url().ok())
+            .ok_or_else(|| IngestError::Config("Failed to get the page URL".to_string()))?;
+
+        let url = Url::new_with_base(directory, &page_url).map_err(|err| {
+            IngestError::Config(format!("Invalid static directory {directory}: {err:?}"))
+        })?;
+
+        Ok(Self { url: url.href() })
+    }
+}
+
+impl DataSource for StaticSource {
+    fn root(&self) -> String {
+        self.url.trim_end_matches('/').to_string()
+    }
+}
+
+/// Sample Sentinel-1 data bundled into the client for developing and testing offline, every month
+///     other than `FixtureSource::MONTH` and every other mission reads an empty file so any
+///     filter can be requested
+pub struct FixtureSource;
+
+impl FixtureSource {
+    /// The only month with data in the fixture
+    pub const MONTH: NaiveDate =
+        NaiveDate::from_ymd_opt(2026, 6, 1).expect("Invalid fixture month");
+
+    const FILES: [(&'static str, &'static [u8]); 3] = [
+        (
+            "fixtures/sat_data_sample.parquet",
+            include_bytes!("../../assets/fixtures/sat_data_sample.parquet"),
+        ),
+        (
+            "fixtures/sat_data_empty.parquet",
+            include_bytes!("../../assets/fixtures/sat_data_empty.parquet"),
+        ),
+        (
+            "fixtures/world_continents.parquet",
+            include_bytes!("../../assets/fixtures/world_continents.parquet"),
+        ),
+    ];
+}
+
+impl DataSource for FixtureSource {
+    fn root(&self) -> String {
+        "fixtures".to_string()
+    }
+
+    fn sat_data_url(&self, mission: Mission, month: NaiveDate) -> String {
+        if mission == Mission::Sentinel1 && month == Self::MONTH {
+            format!("{}/sat_data_sample.parquet", self.root())
+        } else {
+            format!("{}/sat_data_empty.parquet", self.root())
+        }
+    }
+
+    fn bundled_files(&self) -> &[(&'static str, &'static [u8])] {
+        &Self::FILES
+    }
+
+    // Already bundled in the client
+    fn cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// Create the data source described by the page or build configuration, defaults to the ASF S3 bucket
+pub fn configured_source() -> Result<Box<dyn DataSource>, IngestError> {
+    let spec = page_meta(META_NAME)
+        .or_else(|| option_env!("HEATMAP_DATA_SOURCE").map(ToString::to_string))
+        .unwrap_or_else(|| "s3".to_string());
+    log!("Data source: {spec}");
+
+    parse_spec(&spec)
+}
+
+fn parse_spec(spec: &str) -> Result<Box<dyn DataSource>, IngestError> {
+    let (kind, value) = spec
+        .split_once(':')
+        .map_or((spec, None), |(kind, value)| (kind, Some(value)));
+
+    match (kind.trim(), value.map(str::trim)) {
+        ("s3", None | Some("")) => Ok(Box::new(AsfS3Source {
+            maturity: option_env!("DEPLOY_PREFIX").unwrap_or("dev").to_string(),
+        })),
+        ("s3", Some(maturity)) => Ok(Box::new(AsfS3Source {
+            maturity: maturity.to_string(),
+        })),
+        ("http", Some(base_url)) if !base_url.is_empty() => Ok(Box::new(HttpSource {
+            base_url: base_url.to_string(),
+        })),
+        ("static", Some(directory)) if !directory.is_empty() => {
+            Ok(Box::new(StaticSource::new(directory)?))
+        }
+        ("fixture", None) => Ok(Box::new(FixtureSource)),
+        _ => Err(IngestError::Config(format!(
+            "Unknown data source \"{spec}\", expected s3[:maturity], http:<base url>, static:<directory> or fixture"
+        ))),
+    }
+}
+
+/// Read the content of the page's meta tag with the passed name if one exists
+pub fn page_meta(name: &str) -> Option<String> {
+    web_sys::window()?
+        .document()?
+        .query_selector(&format!("meta[name={name}]"))
+        .ok()??
+        .get_attribute("content")
+}
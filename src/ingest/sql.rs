@@ -1,119 +1,389 @@
-use std::fmt::Write;
-use std::iter::successors;
-
-use chrono::NaiveDate;
-use chrono::{Datelike, Months};
-use leptos::logging::log;
+use geo::{Coord, LineString, MultiPolygon, Polygon, Rect};
+use strum::IntoEnumIterator as _;
 
 use crate::DateRange;
+use crate::ingest::query::{Param, Query};
+use crate::ingest::source::DataSource;
+use crate::mission::Mission;
 use crate::types::Filter;
 
-/// Generate sql to create table to store satellite data
+// Granules whose footprint may cross the antimeridian and have not been split at it yet, a
+//     crossing footprint spans more than half of the globe in longitude
+const ANTIMERIDIAN_CANDIDATES: &str =
+    "NOT antimeridian_checked AND ST_XMax(geometry) - ST_XMin(geometry) > 180";
+
+/// Generate sql to create table to store satellite data, `antimeridian_checked` is set once a
+///     footprint crossing the antimeridian has been split at it
 pub fn generate_create_sat_data_sql() -> String {
     "CREATE TABLE sat_data (
         geometry GEOMETRY('EPSG:4326'),
-        ancestors STRUCT(granule_name VARCHAR, platform_type VARCHAR, data_sensor_type VARCHAR, start_time TIMESTAMP)[]
+        ancestors STRUCT(granule_name VARCHAR, platform_type VARCHAR, data_sensor_type VARCHAR, start_time TIMESTAMP, flight_direction VARCHAR, relative_orbit INTEGER)[],
+        antimeridian_checked BOOLEAN DEFAULT false
     );".to_owned()
 }
 
-/// Create sql to read sat data from s3 into `DuckDB` based on the passed `DateRange`.
-pub fn generate_populate_sat_data_sql(date_range: &DateRange) -> (Vec<String>, DateRange) {
-    let maturity = std::env::var("DEPLOY_PREFIX").unwrap_or_else(|_| "dev".to_string());
-    let range_start = NaiveDate::from_ymd_opt(date_range.start.year(), date_range.start.month(), 1)
-        .expect("Failed to create start month");
-    let mut range_end: NaiveDate = date_range.end;
-    let missing_months: Vec<NaiveDate> = successors(Some(range_start), |x| {
-        log!("x: {x:?}\tend: {:?}", date_range.end);
-        let next = x.checked_add_months(Months::new(1));
-        if let Some(n) = next
-            && n >= date_range.end
-        {
-            log!("x: {x:?}\tend: {:?}", date_range.end);
-            range_end = next.expect("Failed to get end month for final date range");
-            return None;
-        }
-
-        next
-    })
-    .collect();
-
-    // Generate SQL to import missing data
-    let sql: Vec<String> = missing_months
+/// Create sql to read sat data from a monthly parquet file into `DuckDB`, only ancestors acquired
+///     within days are kept so a one week range does not ingest the whole month and ranges that
+///     share a month can be ingested separately without double counting. Every range missing from
+///     the month is passed at once as the file is downloaded in full each time it is read
+pub fn generate_populate_sat_data_sql(file: &str, days: &[DateRange]) -> String {
+    let ranges: Vec<String> = days
         .iter()
-        .map(|x| {
-            let end = x
-                .checked_add_months(Months::new(1))
-                .expect("Failed to add a month");
+        .map(|range| {
             format!(
-                "INSERT INTO sat_data
-                 SELECT * 
-                 FROM read_parquet('s3://archive-heatmap-storage-{maturity}/sat_data/monthly/{}/{}/{}_{}.parquet');",
-                x.format("%Y"),
-                x.format("%m"),
-                x.format("%Y-%m-01"),
-                end.format("%Y-%m-01")
+                "(x.start_time >= {} AND x.start_time < {})",
+                Param::Date(range.start).literal(),
+                Param::Date(range.end).literal()
             )
         })
         .collect();
 
-    log!("Missing: {sql:?}");
+    format!(
+        "INSERT INTO sat_data (geometry, ancestors)
+         SELECT geometry, ancestors
+         FROM (
+            SELECT
+                geometry,
+                list_filter(ancestors, lambda x:
+                    {}
+                ) AS ancestors
+            FROM read_parquet({})
+         )
+         WHERE len(ancestors) > 0;",
+        if ranges.is_empty() {
+            "false".to_string()
+        } else {
+            ranges.join(" OR\n                    ")
+        },
+        Param::Text(file.to_string()).literal()
+    )
+}
+
+/// Create sql to copy a parquet file into `DuckDB`'s virtual file system so it can be cached
+pub fn generate_copy_parquet_sql(url: &str, file: &str) -> String {
+    format!(
+        "COPY (SELECT * FROM read_parquet({})) TO {} (FORMAT parquet);",
+        Param::Text(url.to_string()).literal(),
+        Param::Text(file.to_string()).literal()
+    )
+}
 
-    // Create Vector representing the imported data, needed to clip
-    //  input range to file resolution, ie 2019-12-08 imports data
-    //  starting from 2019-12-01
-    let missing_range = DateRange {
-        start: range_start,
-        end: range_end,
+/// Create sql to read the row id and footprint of every granule ingested since the last split that
+///     may cross the antimeridian
+pub fn generate_antimeridian_candidates_sql() -> String {
+    format!(
+        "SELECT rowid, ST_AsWKB(geometry)
+         FROM sat_data
+         WHERE {ANTIMERIDIAN_CANDIDATES};"
+    )
+}
+
+/// Create sql to replace the footprints of granules crossing the antimeridian with the parts
+///     they were split into, keyed by row id, every candidate is marked as checked so it is only
+///     read once
+pub fn generate_split_antimeridian_sql(split: &[(i64, MultiPolygon)]) -> String {
+    let geometry = if split.is_empty() {
+        String::new()
+    } else {
+        let cases: Vec<String> = split
+            .iter()
+            .map(|(rowid, parts)| {
+                format!(
+                    "WHEN {} THEN CAST(ST_GeomFromText({}) AS GEOMETRY('EPSG:4326'))",
+                    Param::Int(*rowid).literal(),
+                    Param::Text(multi_polygon_wkt(parts)).literal()
+                )
+            })
+            .collect();
+        format!(
+            "geometry = CASE rowid
+                {}
+                ELSE geometry
+            END,
+            ",
+            cases.join("\n                ")
+        )
     };
 
-    (sql, missing_range)
+    format!(
+        "UPDATE sat_data SET
+            {geometry}antimeridian_checked = true
+         WHERE {ANTIMERIDIAN_CANDIDATES};"
+    )
 }
 
-pub fn generate_ingest_world_outline_sql() -> String {
-    let maturity = std::env::var("DEPLOY_PREFIX").unwrap_or_else(|_| "dev".to_string());
+pub fn generate_ingest_world_outline_sql(source: &dyn DataSource) -> String {
     format!(
         "CREATE TABLE world_outline AS
         SELECT * 
-        FROM read_parquet('s3://archive-heatmap-storage-{maturity}/world_continents.parquet');"
+        FROM read_parquet({});",
+        Param::Text(source.world_outline_url()).literal()
     )
 }
 
-/// Create sql to generate a Heatmap based on a filter and data already in `DuckDB`
-pub fn generate_sql(filter: &Filter) -> String {
-    let mut plat_str = "(".to_string();
-    filter.platform_type.iter().enumerate().for_each(|(i, x)| {
-        if i > 0 {
-            plat_str += ", ";
-        }
-        let _ = write!(plat_str, "'{x}'");
-    });
-    plat_str += ")";
+/// Conditions on the platform and the codes in the granule name of a single mission
+fn mission_conditions(mission: Mission, filter: &Filter) -> Vec<Query> {
+    let fields = mission.granule_fields();
+
+    // Platform: SA, SB, 5C, 5D, NISAR, ...
+    let mut conditions = vec![
+        Query::new("x.platform_type IN ").list(
+            filter
+                .platform_type
+                .iter()
+                .filter(|x| x.mission() == mission)
+                .map(Param::from),
+        ),
+    ];
+
+    // Product Type: SLC, GRD, OCN, RAW, ETA for Sentinel-1
+    if let Some(field) = fields.product {
+        conditions.push(
+            Query::new(&format!("{} IN ", field.sql())).list(
+                filter
+                    .product_type
+                    .iter()
+                    .filter(|x| x.mission() == mission)
+                    .map(Param::from),
+            ),
+        );
+    }
+
+    // Beam Mode: IW, EW, S1-S6, WV
+    if let Some(field) = fields.beam_mode {
+        conditions.push(
+            Query::new(&format!("{} IN ", field.sql())).list(
+                filter
+                    .beam_mode
+                    .iter()
+                    .flat_map(|x| x.granule_codes())
+                    .map(Param::from),
+            ),
+        );
+    }
+
+    // Polarization: SH, SV, DH, DV
+    if let Some(field) = fields.polarization {
+        conditions.push(
+            Query::new(&format!("{} IN ", field.sql()))
+                .list(filter.polarization.iter().map(Param::from)),
+        );
+    }
+
+    conditions
+}
 
-    let mut prod_str = "(".to_string();
-    filter.product_type.iter().enumerate().for_each(|(i, x)| {
-        if i > 0 {
-            prod_str += ", ";
+/// Conditions an ancestor must meet to count towards the weight of a granule, used inside of
+///     `list_filter(ancestors, lambda x: ...)`
+fn ancestor_conditions(filter: &Filter) -> Query {
+    let mut conditions = vec![
+        Query::new("x.start_time > ").param(Param::Date(filter.date_range.start)),
+        Query::new("x.start_time < ").param(Param::Date(filter.date_range.end)),
+    ];
+
+    // Each mission reads its filters from different parts of the granule name, when more than
+    //     one mission is selected an ancestor only needs to meet the conditions of its own mission
+    let missions: Vec<Query> = filter
+        .missions()
+        .into_iter()
+        .map(|mission| Query::join(mission_conditions(mission, filter), " AND\n            "))
+        .collect();
+    match missions.len() {
+        0 => conditions.push(Query::new("x.platform_type IN ").list([])),
+        1 => conditions.extend(missions),
+        _ => {
+            let missions = missions
+                .into_iter()
+                .map(|query| Query::new("(").append(query).sql(")"));
+            conditions.push(
+                Query::new("(")
+                    .append(Query::join(missions, " OR\n            "))
+                    .sql(")"),
+            );
         }
-        let _ = write!(prod_str, "'{x}'");
+    }
+
+    // Ascending or descending, both are included when unset
+    if let Some(direction) = filter.orbit_direction {
+        conditions.push(Query::new("x.flight_direction = ").param(Param::from(&direction)));
+    }
+
+    // Relative orbits, single orbits are compared directly and ranges are inclusive
+    if !filter.relative_orbit.is_empty() {
+        let orbits = filter.relative_orbit.iter().map(|range| {
+            if range.start() == range.end() {
+                Query::new("x.relative_orbit = ").param(Param::Int(i64::from(*range.start())))
+            } else {
+                Query::new("x.relative_orbit BETWEEN ")
+                    .param(Param::Int(i64::from(*range.start())))
+                    .sql(" AND ")
+                    .param(Param::Int(i64::from(*range.end())))
+            }
+        });
+        conditions.push(Query::new("(").append(Query::join(orbits, " OR ")).sql(")"));
+    }
+
+    Query::join(conditions, " AND\n            ")
+}
+
+/// The area of interest as a geometry in the same CRS as `sat_data`
+fn aoi_geometry(aoi: &MultiPolygon) -> Query {
+    Query::new("CAST(ST_MakeValid(ST_GeomFromText(")
+        .param(Param::Text(multi_polygon_wkt(aoi)))
+        .sql(")) AS GEOMETRY('EPSG:4326'))")
+}
+
+/// Create sql to generate a Heatmap based on a filter and data already in `DuckDB`
+pub fn generate_sql(filter: &Filter) -> Query {
+    let conditions = ancestor_conditions(filter);
+
+    // Granules are clipped to the area of interest so only the part inside it adds to the heatmap,
+    //     polygons are extracted as clipping can leave lines or points where granules touch its edge
+    let aoi = filter.aoi.as_ref().map(aoi_geometry);
+    let geometry = aoi.clone().map_or_else(
+        || Query::new("geometry"),
+        |aoi| {
+            Query::new("ST_CollectionExtract(ST_Intersection(geometry, ")
+                .append(aoi)
+                .sql("), 3)")
+        },
+    );
+    let intersects = aoi.map_or_else(Query::default, |aoi| {
+        Query::new(" AND ST_Intersects(geometry, ")
+            .append(aoi)
+            .sql(")")
     });
-    prod_str += ")";
 
-    format!(
+    Query::new(
+        "SELECT
+        ST_AsWKB(",
+    )
+    .append(geometry)
+    .sql(
+        "),
+        len(list_filter(ancestors, lambda x:
+            ",
+    )
+    .append(conditions)
+    .sql(
         "
-    SELECT
-        ST_AsWKB(geometry),
-        len(list_filter(ancestors, lambda x: 
-            x.start_time > '{}' AND               -- Start Time
-            x.start_time < '{}' AND               -- End Time
-            x.platform_type IN {} AND             -- Platform: SA, SB, 5C, 5D
-            substring(x.granule_name, 8, 3) IN {} -- Product Type: SLC, GRD, OCN
         )) AS weight,
     FROM sat_data
-    WHERE weight > 0;
-    ",
-        filter.date_range.start.format("%Y-%m-%d"),
-        filter.date_range.end.format("%Y-%m-%d"),
-        plat_str,
-        prod_str
+    WHERE weight > 0",
+    )
+    .append(intersects)
+    .sql(";")
+}
+
+/// Create sql to list the granules that count towards the heatmap at a point, one row for each
+///     acquisition in the order they were acquired
+pub fn generate_inspect_sql(filter: &Filter, point: Coord) -> Query {
+    let point = Query::new("CAST(ST_GeomFromText(")
+        .param(Param::Text(format!("POINT ({} {})", point.x, point.y)))
+        .sql(") AS GEOMETRY('EPSG:4326'))");
+
+    // Nothing outside of the area of interest counts towards the heatmap
+    let inside_aoi = filter.aoi.as_ref().map_or_else(Query::default, |aoi| {
+        Query::new(" AND ST_Intersects(")
+            .append(aoi_geometry(aoi))
+            .sql(", ")
+            .append(point.clone())
+            .sql(")")
+    });
+
+    Query::new(
+        "SELECT
+        ancestor.granule_name,
+        strftime(ancestor.start_time, '%Y-%m-%d %H:%M:%S') AS start_time
+    FROM (
+        SELECT unnest(list_filter(ancestors, lambda x:
+            ",
+    )
+    .append(ancestor_conditions(filter))
+    .sql(
+        "
+        )) AS ancestor
+        FROM sat_data
+        WHERE ST_Contains(geometry, ",
     )
+    .append(point)
+    .sql(")")
+    .append(inside_aoi)
+    .sql(
+        "
+    )
+    ORDER BY ancestor.start_time, ancestor.granule_name;",
+    )
+}
+
+/// Product type of an ancestor, read from the part of the granule name its mission keeps it in
+fn product_type_sql() -> Query {
+    let cases = Mission::iter().filter_map(|mission| {
+        let field = mission.granule_fields().product?;
+        Some(
+            Query::new("WHEN x.platform_type IN ")
+                .list(mission.platforms().iter().map(Param::from))
+                .sql(&format!(" THEN {}", field.sql())),
+        )
+    });
+
+    Query::new("CASE ")
+        .append(Query::join(cases, " "))
+        .sql(" END")
+}
+
+/// Create sql to list the granules that count towards the heatmap inside of the area of interest,
+///     or the viewport when there is none, along with the footprint of the frame they belong to
+pub fn generate_granule_list_sql(filter: &Filter, viewport: Rect) -> Query {
+    let region = filter.aoi.as_ref().map_or_else(
+        || aoi_geometry(&MultiPolygon::new(vec![viewport.to_polygon()])),
+        aoi_geometry,
+    );
+
+    Query::new(
+        "SELECT
+        x.granule_name,
+        x.platform_type,
+        ",
+    )
+    .append(product_type_sql())
+    .sql(
+        " AS product_type,
+        strftime(x.start_time, '%Y-%m-%d %H:%M:%S') AS start_time,
+        ST_AsWKB(geometry) AS footprint
+    FROM (
+        SELECT geometry, unnest(list_filter(ancestors, lambda x:
+            ",
+    )
+    .append(ancestor_conditions(filter))
+    .sql(
+        "
+        )) AS x
+        FROM sat_data
+        WHERE ST_Intersects(geometry, ",
+    )
+    .append(region)
+    .sql(
+        ")
+    )
+    ORDER BY x.start_time, x.granule_name;",
+    )
+}
+
+/// Well-known text of a multi polygon, ie. `MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)))`
+fn multi_polygon_wkt(multi_polygon: &MultiPolygon) -> String {
+    let ring = |ring: &LineString| {
+        let coords: Vec<String> = ring.coords().map(|x| format!("{} {}", x.x, x.y)).collect();
+        format!("({})", coords.join(", "))
+    };
+    let polygon = |polygon: &Polygon| {
+        let rings: Vec<String> = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(ring)
+            .collect();
+        format!("({})", rings.join(", "))
+    };
+
+    let polygons: Vec<String> = multi_polygon.iter().map(polygon).collect();
+    format!("MULTIPOLYGON ({})", polygons.join(", "))
 }
@@ -0,0 +1,392 @@
+//! Snapshot tests of the SQL sent to `DuckDB` for representative filters
+use chrono::NaiveDate;
+use geo::{MultiPolygon, Rect, coord};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::query::Param;
+use crate::ingest::source::{DataSource as _, HttpSource};
+use crate::ingest::sql::{
+    generate_granule_list_sql, generate_ingest_world_outline_sql, generate_inspect_sql,
+    generate_populate_sat_data_sql, generate_split_antimeridian_sql, generate_sql,
+};
+use crate::mission::Mission;
+use crate::test_support::{date, sentinel1_filter};
+use crate::types::{
+    BeamMode, DateRange, Filter, OrbitDirection, PlatformType, Polarization, ProductTypes,
+};
+
+fn default_filter() -> Filter {
+    sentinel1_filter(date(2026, 6, 1), date(2026, 7, 1))
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_default_filter() {
+    assert_eq!(
+        generate_sql(&default_filter()).inline(),
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn inspect_sql_default_filter() {
+    assert_eq!(
+        generate_inspect_sql(&default_filter(), coord! { x: -146.5, y: 63.5 }).inline(),
+        "SELECT
+        ancestor.granule_name,
+        strftime(ancestor.start_time, '%Y-%m-%d %H:%M:%S') AS start_time
+    FROM (
+        SELECT unnest(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')
+        )) AS ancestor
+        FROM sat_data
+        WHERE ST_Contains(geometry, CAST(ST_GeomFromText('POINT (-146.5 63.5)') AS GEOMETRY('EPSG:4326')))
+    )
+    ORDER BY ancestor.start_time, ancestor.granule_name;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn granule_list_sql_viewport() {
+    let viewport = Rect::new(coord! { x: -151.0, y: 59.0 }, coord! { x: -142.0, y: 68.0 });
+
+    assert_eq!(
+        generate_granule_list_sql(&default_filter(), viewport).inline(),
+        "SELECT
+        x.granule_name,
+        x.platform_type,
+        CASE WHEN x.platform_type IN ('SA', 'SB', '5C', '5D') THEN substring(x.granule_name, 8, 3) WHEN x.platform_type IN ('NISAR') THEN substring(x.granule_name, 13, 4) END AS product_type,
+        strftime(x.start_time, '%Y-%m-%d %H:%M:%S') AS start_time,
+        ST_AsWKB(geometry) AS footprint
+    FROM (
+        SELECT geometry, unnest(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')
+        )) AS x
+        FROM sat_data
+        WHERE ST_Intersects(geometry, CAST(ST_MakeValid(ST_GeomFromText('MULTIPOLYGON (((-142 59, -142 68, -151 68, -151 59, -142 59)))')) AS GEOMETRY('EPSG:4326')))
+    )
+    ORDER BY x.start_time, x.granule_name;"
+    );
+
+    // The area of interest is listed instead of the viewport when there is one
+    let mut filter = default_filter();
+    let aoi = Rect::new(coord! { x: -149.0, y: 61.0 }, coord! { x: -146.0, y: 64.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+    assert!(
+        generate_granule_list_sql(&filter, viewport)
+            .inline()
+            .contains("'MULTIPOLYGON (((-146 61, -146 64, -149 64, -149 61, -146 61)))'")
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_prepared() {
+    let (sql, params) = generate_sql(&default_filter()).prepared();
+    assert_eq!(
+        sql,
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > CAST(? AS DATE) AND
+            x.start_time < CAST(? AS DATE) AND
+            x.platform_type IN (?, ?, ?, ?) AND
+            substring(x.granule_name, 8, 3) IN (?, ?, ?) AND
+            substring(x.granule_name, 5, 2) IN (?, ?, ?, ?, ?, ?, ?, ?, ?) AND
+            substring(x.granule_name, 15, 2) IN (?, ?, ?, ?)
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+    assert_eq!(
+        params,
+        vec![
+            Param::Date(date(2026, 6, 1)),
+            Param::Date(date(2026, 7, 1)),
+            Param::Text("SA".to_string()),
+            Param::Text("SB".to_string()),
+            Param::Text("5C".to_string()),
+            Param::Text("5D".to_string()),
+            Param::Text("GRD".to_string()),
+            Param::Text("SLC".to_string()),
+            Param::Text("OCN".to_string()),
+            Param::Text("IW".to_string()),
+            Param::Text("EW".to_string()),
+            Param::Text("S1".to_string()),
+            Param::Text("S2".to_string()),
+            Param::Text("S3".to_string()),
+            Param::Text("S4".to_string()),
+            Param::Text("S5".to_string()),
+            Param::Text("S6".to_string()),
+            Param::Text("WV".to_string()),
+            Param::Text("SH".to_string()),
+            Param::Text("SV".to_string()),
+            Param::Text("DH".to_string()),
+            Param::Text("DV".to_string()),
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_single_platform_no_products() {
+    let mut filter = default_filter();
+    filter.date_range = DateRange::new(date(2020, 1, 5), date(2020, 1, 12)).expect("Invalid range");
+    filter.platform_type = vec![PlatformType::Sentinel1A];
+    filter.product_type = vec![];
+
+    assert_eq!(
+        generate_sql(&filter).inline(),
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2020-01-05' AND
+            x.start_time < DATE '2020-01-12' AND
+            x.platform_type IN ('SA') AND
+            substring(x.granule_name, 8, 3) IN (NULL) AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_auxiliary_products() {
+    let mut filter = default_filter();
+    filter.product_type = vec![ProductTypes::Raw, ProductTypes::ExtendedTimingAnnotation];
+
+    assert_eq!(
+        generate_sql(&filter).inline(),
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('RAW', 'ETA') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_multiple_missions() {
+    let mut filter = default_filter();
+    filter
+        .platform_type
+        .extend([PlatformType::Nisar, PlatformType::Ers1]);
+    filter.product_type.push(ProductTypes::GeocodedCovariance);
+
+    // ERS granule names do not encode a product, beam mode or polarization
+    assert_eq!(
+        generate_sql(&filter).inline(),
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            ((x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')) OR
+            (x.platform_type IN ('NISAR') AND
+            substring(x.granule_name, 13, 4) IN ('GCOV')) OR
+            (x.platform_type IN ('ERS-1')))
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_aoi() {
+    let mut filter = default_filter();
+    let aoi = Rect::new(coord! { x: -149.0, y: 61.0 }, coord! { x: -146.0, y: 64.0 });
+    filter.aoi = Some(MultiPolygon::new(vec![aoi.to_polygon()]));
+
+    assert_eq!(
+        generate_sql(&filter).inline(),
+        "SELECT
+        ST_AsWKB(ST_CollectionExtract(ST_Intersection(geometry, CAST(ST_MakeValid(ST_GeomFromText('MULTIPOLYGON (((-146 61, -146 64, -149 64, -149 61, -146 61)))')) AS GEOMETRY('EPSG:4326'))), 3)),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV')
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0 AND ST_Intersects(geometry, CAST(ST_MakeValid(ST_GeomFromText('MULTIPOLYGON (((-146 61, -146 64, -149 64, -149 61, -146 61)))')) AS GEOMETRY('EPSG:4326')));"
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_iw_dual_pol() {
+    let mut filter = default_filter();
+    filter.beam_mode = vec![BeamMode::InterferometricWideSwath];
+    filter.polarization = vec![Polarization::DualHH, Polarization::DualVV];
+
+    assert_eq!(
+        generate_sql(&filter).inline(),
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW') AND
+            substring(x.granule_name, 15, 2) IN ('DH', 'DV')
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn heatmap_sql_ascending_relative_orbits() {
+    let mut filter = default_filter();
+    filter.orbit_direction = Some(OrbitDirection::Ascending);
+    filter.relative_orbit = vec![94..=94, 100..=110];
+
+    let query = generate_sql(&filter);
+    assert_eq!(
+        query.inline(),
+        "SELECT
+        ST_AsWKB(geometry),
+        len(list_filter(ancestors, lambda x:
+            x.start_time > DATE '2026-06-01' AND
+            x.start_time < DATE '2026-07-01' AND
+            x.platform_type IN ('SA', 'SB', '5C', '5D') AND
+            substring(x.granule_name, 8, 3) IN ('GRD', 'SLC', 'OCN') AND
+            substring(x.granule_name, 5, 2) IN ('IW', 'EW', 'S1', 'S2', 'S3', 'S4', 'S5', 'S6', 'WV') AND
+            substring(x.granule_name, 15, 2) IN ('SH', 'SV', 'DH', 'DV') AND
+            x.flight_direction = 'ASCENDING' AND
+            (x.relative_orbit = 94 OR x.relative_orbit BETWEEN 100 AND 110)
+        )) AS weight,
+    FROM sat_data
+    WHERE weight > 0;"
+    );
+    assert!(query.prepared().0.contains(
+        "x.flight_direction = ? AND
+            (x.relative_orbit = CAST(? AS INTEGER) OR x.relative_orbit BETWEEN CAST(? AS INTEGER) AND CAST(? AS INTEGER))"
+    ));
+}
+
+#[wasm_bindgen_test]
+fn populate_sql_quotes_file() {
+    let days = DateRange::new(date(2026, 6, 8), date(2026, 6, 15)).expect("Invalid range");
+
+    assert_eq!(
+        generate_populate_sat_data_sql("s3://bucket/it's.parquet", &[days]),
+        "INSERT INTO sat_data (geometry, ancestors)
+         SELECT geometry, ancestors
+         FROM (
+            SELECT
+                geometry,
+                list_filter(ancestors, lambda x:
+                    (x.start_time >= DATE '2026-06-08' AND x.start_time < DATE '2026-06-15')
+                ) AS ancestors
+            FROM read_parquet('s3://bucket/it''s.parquet')
+         )
+         WHERE len(ancestors) > 0;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn split_antimeridian_sql_replaces_by_rowid() {
+    let square = Rect::new(coord! { x: 170.0, y: 0.0 }, coord! { x: 180.0, y: 1.0 });
+    let split = [(3, MultiPolygon::new(vec![square.to_polygon()]))];
+
+    assert_eq!(
+        generate_split_antimeridian_sql(&split),
+        "UPDATE sat_data SET
+            geometry = CASE rowid
+                WHEN 3 THEN CAST(ST_GeomFromText('MULTIPOLYGON (((180 0, 180 1, 170 1, 170 0, 180 0)))') AS GEOMETRY('EPSG:4326'))
+                ELSE geometry
+            END,
+            antimeridian_checked = true
+         WHERE NOT antimeridian_checked AND ST_XMax(geometry) - ST_XMin(geometry) > 180;"
+    );
+
+    // Candidates that do not cross are only marked as checked
+    assert_eq!(
+        generate_split_antimeridian_sql(&[]),
+        "UPDATE sat_data SET
+            antimeridian_checked = true
+         WHERE NOT antimeridian_checked AND ST_XMax(geometry) - ST_XMin(geometry) > 180;"
+    );
+}
+
+#[wasm_bindgen_test]
+fn populate_sql_reads_month_once() {
+    // Two gaps in June and one that runs into July, June is read once with both of its gaps
+    let source = HttpSource {
+        base_url: "http://localhost:8000".to_string(),
+    };
+    let gaps = [
+        DateRange::new(date(2026, 6, 1), date(2026, 6, 8)).expect("Invalid range"),
+        DateRange::new(date(2026, 6, 22), date(2026, 7, 5)).expect("Invalid range"),
+    ];
+    let files = source.sat_data_files(Mission::Sentinel1, &gaps);
+    let days = |ranges: &[DateRange]| -> Vec<(NaiveDate, NaiveDate)> {
+        ranges.iter().map(|x| (x.start, x.end)).collect()
+    };
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(
+        days(&files[0].1),
+        vec![
+            (date(2026, 6, 1), date(2026, 6, 8)),
+            (date(2026, 6, 22), date(2026, 7, 1)),
+        ]
+    );
+    assert_eq!(
+        days(&files[1].1),
+        vec![(date(2026, 7, 1), date(2026, 7, 5))]
+    );
+
+    let sql = generate_populate_sat_data_sql(&files[0].0, &files[0].1);
+    assert!(sql.contains(
+        "(x.start_time >= DATE '2026-06-01' AND x.start_time < DATE '2026-06-08') OR
+                    (x.start_time >= DATE '2026-06-22' AND x.start_time < DATE '2026-07-01')"
+    ));
+}
+
+#[wasm_bindgen_test]
+fn world_outline_sql_quotes_url() {
+    let source = HttpSource {
+        base_url: "http://localhost:8000/it's/".to_string(),
+    };
+
+    assert_eq!(
+        generate_ingest_world_outline_sql(&source),
+        "CREATE TABLE world_outline AS
+        SELECT * 
+        FROM read_parquet('http://localhost:8000/it''s/world_continents.parquet');"
+    );
+}
@@ -0,0 +1,205 @@
+use geo::geometry::{Coord, LineString, MultiPolygon, Polygon, Rect};
+use geo::{BooleanOps, BoundingRect, Geometry, coord};
+use leptos::logging::log;
+
+use crate::ingest::error::IngestError;
+use crate::types::Granule;
+
+/// Tracks how many granule geometries were changed or removed while validating
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Geometries that were used without modification
+    pub valid: usize,
+    /// Geometries that were split into multiple polygons, ie. `MultiPolygon`s and antimeridian crossings
+    pub repaired: usize,
+    /// Geometries that could not be used, ie. points, lines and degenerate polygons
+    pub dropped: usize,
+}
+
+/// Validate and repair a decoded granule geometry, every polygon produced keeps the weight of the granule
+pub fn validate_granule(
+    geometry: Geometry,
+    weight: u64,
+    report: &mut ValidationReport,
+) -> Vec<Granule> {
+    match validate_geometry(geometry) {
+        Ok((polygons, repaired)) if !polygons.is_empty() => {
+            if repaired {
+                report.repaired += 1;
+            } else {
+                report.valid += 1;
+            }
+
+            polygons
+                .into_iter()
+                .map(|geometry| Granule { geometry, weight })
+                .collect()
+        }
+        Ok(_) => {
+            report.dropped += 1;
+            Vec::new()
+        }
+        Err(err) => {
+            log!("Dropping granule: {err}");
+            report.dropped += 1;
+            Vec::new()
+        }
+    }
+}
+
+/// Convert a geometry into polygons that lie within [-180, 180] longitude,
+///     the returned bool is true if the geometry had to be repaired
+fn validate_geometry(geometry: Geometry) -> Result<(Vec<Polygon>, bool), IngestError> {
+    let polygons: Vec<Polygon> = match geometry {
+        Geometry::Polygon(poly) => vec![poly],
+        Geometry::MultiPolygon(multi_poly) => multi_poly.0,
+        Geometry::Rect(rect) => vec![rect.to_polygon()],
+        Geometry::Triangle(triangle) => vec![triangle.to_polygon()],
+        other => {
+            return Err(IngestError::NonPolygon(
+                geometry_type_name(&other).to_string(),
+            ));
+        }
+    };
+
+    let mut repaired = polygons.len() > 1;
+    let mut output: Vec<Polygon> = Vec::new();
+    for poly in polygons {
+        if poly.exterior().0.len() < 4 {
+            // A closed ring needs at least 3 distinct points
+            repaired = true;
+            continue;
+        }
+
+        if crosses_antimeridian(poly.exterior()) {
+            repaired = true;
+            output.extend(split_antimeridian(&poly));
+        } else {
+            output.push(poly);
+        }
+    }
+
+    Ok((output, repaired))
+}
+
+/// Name of the geometry variant, used when reporting unexpected geometries
+pub const fn geometry_type_name(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "Point",
+        Geometry::Line(_) => "Line",
+        Geometry::LineString(_) => "LineString",
+        Geometry::Polygon(_) => "Polygon",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+        Geometry::GeometryCollection(_) => "GeometryCollection",
+        Geometry::Rect(_) => "Rect",
+        Geometry::Triangle(_) => "Triangle",
+    }
+}
+
+/// Split the polygons of a footprint that cross the antimeridian the way `validate_granule` does,
+///     None if it is not a polygon or nothing crosses so the footprint can be kept as is
+pub fn split_footprint(geometry: &Geometry) -> Option<MultiPolygon> {
+    let polygons = match geometry {
+        Geometry::Polygon(poly) => std::slice::from_ref(poly),
+        Geometry::MultiPolygon(multi_poly) => multi_poly.0.as_slice(),
+        _ => return None,
+    };
+    if !polygons.iter().any(|x| crosses_antimeridian(x.exterior())) {
+        return None;
+    }
+
+    Some(MultiPolygon::new(
+        polygons
+            .iter()
+            .flat_map(|x| {
+                if crosses_antimeridian(x.exterior()) {
+                    split_antimeridian(x)
+                } else {
+                    vec![x.clone()]
+                }
+            })
+            .collect(),
+    ))
+}
+
+/// A ring crosses the antimeridian if two consecutive points are more than half the globe apart
+pub fn crosses_antimeridian(ring: &LineString) -> bool {
+    ring.lines()
+        .any(|line| (line.end.x - line.start.x).abs() > 180.0)
+}
+
+/// Shift longitudes by multiples of 360 so that consecutive points are never more than 180 degrees apart,
+///     rings that encircle a pole are closed by walking along the pole
+pub fn unwrap_ring(ring: &LineString, reference_x: f64) -> LineString {
+    let mut coords: Vec<Coord> = Vec::with_capacity(ring.0.len() + 2);
+
+    for point in &ring.0 {
+        let prev_x = coords.last().map_or(reference_x, |prev: &Coord| prev.x);
+        let x = 360.0f64.mul_add(-((point.x - prev_x) / 360.0).round(), point.x);
+        coords.push(coord! {x: x, y: point.y});
+    }
+
+    // If the ring no longer closes it wraps around a pole, walk over the pole to close it
+    if let (Some(first), Some(last)) = (coords.first().copied(), coords.last().copied())
+        && (last.x - first.x).abs() > 180.0
+    {
+        let mean_y = coords.iter().map(|c| c.y).sum::<f64>() / coords.len() as f64;
+        let pole = if mean_y >= 0.0 { 90.0 } else { -90.0 };
+
+        coords.push(coord! {x: last.x, y: pole});
+        coords.push(coord! {x: first.x, y: pole});
+        coords.push(first);
+    }
+
+    LineString(coords)
+}
+
+/// Split a polygon that crosses the antimeridian into parts that each lie within [-180, 180]
+pub fn split_antimeridian(poly: &Polygon) -> Vec<Polygon> {
+    let reference_x = poly.exterior().0.first().map_or(0.0, |c| c.x);
+    let exterior = unwrap_ring(poly.exterior(), reference_x);
+    let interiors: Vec<LineString> = poly
+        .interiors()
+        .iter()
+        .map(|ring| unwrap_ring(ring, reference_x))
+        .collect();
+    let unwrapped = Polygon::new(exterior, interiors);
+
+    let Some(bounds) = unwrapped.bounding_rect() else {
+        return Vec::new();
+    };
+
+    // Clip the unwrapped polygon against each copy of the globe it touches and shift the piece back into range
+    let mut parts: Vec<Polygon> = Vec::new();
+    let first_copy = ((bounds.min().x + 180.0) / 360.0).floor() as i32;
+    let last_copy = ((bounds.max().x + 180.0) / 360.0).ceil() as i32;
+    for copy in first_copy..last_copy {
+        let offset = f64::from(copy) * 360.0;
+        let globe = Rect::new(
+            coord! {x: offset - 180.0, y: -90.0},
+            coord! {x: offset + 180.0, y: 90.0},
+        )
+        .to_polygon();
+
+        let clipped: MultiPolygon = unwrapped.intersection(&globe);
+        parts.extend(clipped.into_iter().map(|mut part| {
+            part.exterior_mut(|ring| shift_ring(ring, -offset));
+            part.interiors_mut(|rings| {
+                for ring in rings {
+                    shift_ring(ring, -offset);
+                }
+            });
+            part
+        }));
+    }
+
+    parts
+}
+
+fn shift_ring(ring: &mut LineString, dx: f64) {
+    for point in &mut ring.0 {
+        point.x += dx;
+    }
+}
@@ -0,0 +1,188 @@
+//! Tests of validating and repairing granule geometries
+use geo::{Area as _, BoundingRect as _, Geometry, LineString, MultiPolygon, Point, Polygon};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::ingest::validate::{
+    ValidationReport, crosses_antimeridian, split_antimeridian, split_footprint, unwrap_ring,
+    validate_granule,
+};
+
+fn polygon(points: &[(f64, f64)]) -> Polygon {
+    Polygon::new(LineString::from(points.to_vec()), vec![])
+}
+
+// Every part must lie on the globe
+fn assert_in_range(parts: &[Polygon]) {
+    for part in parts {
+        let bounds = part.bounding_rect().expect("Part is empty");
+        assert!(bounds.min().x >= -180.0 && bounds.max().x <= 180.0);
+        assert!(bounds.min().y >= -90.0 && bounds.max().y <= 90.0);
+    }
+}
+
+#[wasm_bindgen_test]
+fn unwrap_ring_keeps_points_close() {
+    let ring = LineString::from(vec![
+        (170.0, 0.0),
+        (-170.0, 0.0),
+        (-170.0, 10.0),
+        (170.0, 10.0),
+    ]);
+    let unwrapped = unwrap_ring(&ring, 170.0);
+
+    let xs: Vec<f64> = unwrapped.0.iter().map(|c| c.x).collect();
+    assert_eq!(xs, vec![170.0, 190.0, 190.0, 170.0]);
+    assert!(!crosses_antimeridian(&unwrapped));
+}
+
+#[wasm_bindgen_test]
+fn unwrap_ring_closes_over_pole() {
+    // Circles the north pole at 80 degrees, it never closes once unwrapped
+    let ring = LineString::from(vec![
+        (-170.0, 80.0),
+        (-90.0, 80.0),
+        (0.0, 80.0),
+        (90.0, 80.0),
+        (170.0, 80.0),
+        (-170.0, 80.0),
+    ]);
+    let unwrapped = unwrap_ring(&ring, -170.0);
+
+    let tail: Vec<(f64, f64)> = unwrapped.0[6..].iter().map(|c| (c.x, c.y)).collect();
+    assert_eq!(tail, vec![(190.0, 90.0), (-170.0, 90.0), (-170.0, 80.0)]);
+
+    // The south pole is used for rings south of the equator
+    let ring = LineString::from(vec![
+        (-170.0, -80.0),
+        (0.0, -80.0),
+        (170.0, -80.0),
+        (-170.0, -80.0),
+    ]);
+    assert!(unwrap_ring(&ring, -170.0).0.iter().any(|c| c.y <= -90.0));
+}
+
+#[wasm_bindgen_test]
+fn split_antimeridian_crossing() {
+    let parts = split_antimeridian(&polygon(&[
+        (170.0, 0.0),
+        (-170.0, 0.0),
+        (-170.0, 10.0),
+        (170.0, 10.0),
+        (170.0, 0.0),
+    ]));
+
+    assert_eq!(parts.len(), 2);
+    assert_in_range(&parts);
+    assert!((MultiPolygon::new(parts).unsigned_area() - 200.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+fn split_antimeridian_pole() {
+    let parts = split_antimeridian(&polygon(&[
+        (-170.0, 80.0),
+        (-90.0, 80.0),
+        (0.0, 80.0),
+        (90.0, 80.0),
+        (170.0, 80.0),
+        (-170.0, 80.0),
+    ]));
+
+    // The cap from 80 degrees to the pole all the way around the globe
+    assert_eq!(parts.len(), 2);
+    assert_in_range(&parts);
+    assert!((MultiPolygon::new(parts).unsigned_area() - 3600.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+fn split_antimeridian_dateline_exact() {
+    // Points on 180 next to points in the west are moved to -180 instead of leaving a sliver
+    let parts = split_antimeridian(&polygon(&[
+        (180.0, 0.0),
+        (-170.0, 0.0),
+        (-170.0, 10.0),
+        (180.0, 10.0),
+        (180.0, 0.0),
+    ]));
+
+    assert_eq!(parts.len(), 1);
+    assert_in_range(&parts);
+    let bounds = parts[0].bounding_rect().expect("Part is empty");
+    assert_eq!((bounds.min().x, bounds.max().x), (-180.0, -170.0));
+
+    // An edge that only touches the antimeridian is not a crossing
+    let east = polygon(&[
+        (170.0, 0.0),
+        (180.0, 0.0),
+        (180.0, 10.0),
+        (170.0, 10.0),
+        (170.0, 0.0),
+    ]);
+    assert!(!crosses_antimeridian(east.exterior()));
+}
+
+#[wasm_bindgen_test]
+fn split_footprint_only_crossing() {
+    let square = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)]);
+    assert!(split_footprint(&Geometry::Polygon(square.clone())).is_none());
+    assert!(split_footprint(&Geometry::Point(Point::new(0.0, 0.0))).is_none());
+
+    // The square is kept as is next to the halves of the crossing polygon
+    let crossing = polygon(&[
+        (170.0, 0.0),
+        (-170.0, 0.0),
+        (-170.0, 10.0),
+        (170.0, 10.0),
+        (170.0, 0.0),
+    ]);
+    let multi = MultiPolygon::new(vec![square.clone(), crossing]);
+    let split = split_footprint(&Geometry::MultiPolygon(multi)).expect("Footprint crosses");
+    assert_eq!(split.0.len(), 3);
+    assert_eq!(split.0[0], square);
+    assert_in_range(&split.0);
+}
+
+#[wasm_bindgen_test]
+fn validate_granule_counts() {
+    let mut report = ValidationReport::default();
+    let square = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)]);
+
+    // Used as is
+    let granules = validate_granule(Geometry::Polygon(square.clone()), 3, &mut report);
+    assert_eq!(granules.len(), 1);
+    assert_eq!(granules[0].weight, 3);
+
+    // Split into a polygon per part
+    let multi = MultiPolygon::new(vec![
+        square.clone(),
+        polygon(&[(5.0, 5.0), (6.0, 5.0), (6.0, 6.0), (5.0, 5.0)]),
+    ]);
+    assert_eq!(
+        validate_granule(Geometry::MultiPolygon(multi), 1, &mut report).len(),
+        2
+    );
+    let crossing = polygon(&[
+        (170.0, 0.0),
+        (-170.0, 0.0),
+        (-170.0, 10.0),
+        (170.0, 10.0),
+        (170.0, 0.0),
+    ]);
+    assert_eq!(
+        validate_granule(Geometry::Polygon(crossing), 1, &mut report).len(),
+        2
+    );
+
+    // Not an area
+    let degenerate = polygon(&[(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)]);
+    assert!(validate_granule(Geometry::Polygon(degenerate), 1, &mut report).is_empty());
+    assert!(validate_granule(Geometry::Point(Point::new(0.0, 0.0)), 1, &mut report).is_empty());
+
+    assert_eq!(
+        report,
+        ValidationReport {
+            valid: 1,
+            repaired: 2,
+            dropped: 2,
+        }
+    );
+}
@@ -0,0 +1,5 @@
+// Bootstraps the mesh worker, the wasm bundle detects that it is running inside of a
+//     DedicatedWorkerGlobalScope and starts the worker instead of mounting the UI
+import init from "{JS_URL}";
+
+await init({ module_or_path: "{WASM_URL}" });
@@ -0,0 +1,387 @@
+//! Runs Arrow decoding, WKB parsing and meshing in a dedicated Web Worker so large requests
+//!     do not freeze the canvas or the UI. The worker loads the same wasm bundle as the main thread,
+//!     `main` checks which global scope it is running in to decide what to start
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use js_sys::{Array, Float64Array, Function, Object, Promise, Reflect, Uint8Array, Uint32Array};
+use leptos::logging::log;
+use strum_macros::Display;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, BlobPropertyBag, DedicatedWorkerGlobalScope, HtmlLinkElement, MessageEvent, Url, Worker,
+    WorkerOptions, WorkerType,
+};
+
+use crate::canvas::geometry::BlendVertex;
+use crate::ingest::async_duckdb::decode_ipc;
+use crate::ingest::error::IngestError;
+use crate::ingest::load::{BufferStorage, Data};
+use crate::ingest::lod::{LodConfig, LodLevel};
+use crate::ingest::mesh::mesh_data;
+use crate::ingest::request::{decode_granules, decode_outline};
+
+/// Which query the Arrow data passed to the worker came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+pub enum MeshKind {
+    #[strum(to_string = "heatmap")]
+    Heatmap,
+    #[strum(to_string = "outline")]
+    Outline,
+}
+
+/// Resolve and reject functions of a request that is waiting on a response
+type Pending = Rc<RefCell<HashMap<u32, (Function, Function)>>>;
+
+/// Handle to the mesh worker held by the main thread
+pub struct MeshWorker {
+    worker: Worker,
+    lod_config: Rc<LodConfig>,
+    next_id: Cell<u32>,
+    // Requests that are waiting on a response, keyed by request id
+    pending: Pending,
+    // Set once the worker has failed, it will not answer any further requests
+    failure: Rc<RefCell<Option<String>>>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(JsValue)>,
+}
+
+impl MeshWorker {
+    /// Start the worker and wait for it to finish loading the wasm bundle,
+    ///     every request is meshed with the levels of detail in `lod_config`
+    pub async fn spawn(lod_config: Rc<LodConfig>) -> Result<Self, IngestError> {
+        let (js_url, wasm_url) = bundle_urls()?;
+        let script = include_str!("worker.js")
+            .replace("{JS_URL}", &js_url)
+            .replace("{WASM_URL}", &wasm_url);
+
+        let blob_options = BlobPropertyBag::new();
+        blob_options.set_type("text/javascript");
+        let blob =
+            Blob::new_with_str_sequence_and_options(&Array::of1(&script.into()), &blob_options)
+                .map_err(worker_error)?;
+        let worker_url = Url::create_object_url_with_blob(&blob).map_err(worker_error)?;
+
+        let worker_options = WorkerOptions::new();
+        worker_options.set_type(WorkerType::Module);
+        let worker =
+            Worker::new_with_options(&worker_url, &worker_options).map_err(worker_error)?;
+
+        // The worker posts a message once it is able to receive requests, it raises an error event
+        //     instead if the bundle fails to load
+        let ready = Promise::new(&mut |resolve, reject| {
+            worker.set_onmessage(Some(&resolve));
+            worker.set_onerror(Some(&reject));
+            worker.set_onmessageerror(Some(&reject));
+        });
+        let ready = JsFuture::from(ready).await;
+        let _ = Url::revoke_object_url(&worker_url);
+        ready.map_err(|err| {
+            worker.terminate();
+            IngestError::Worker(format!("Failed to start: {}", error_message(&err)))
+        })?;
+        log!("Mesh worker ready");
+
+        let pending: Pending = Rc::new(RefCell::new(HashMap::new()));
+        let failure = Rc::new(RefCell::new(None));
+        let on_message = {
+            let pending = pending.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let data = event.data();
+                let Some(id) = get_field(&data, "id").ok().and_then(|id| id.as_f64()) else {
+                    log!("Mesh worker sent a message without an id: {data:?}");
+                    return;
+                };
+
+                let request = pending.borrow_mut().remove(&(id as u32));
+                if let Some((resolve, _)) = request {
+                    let _ = resolve.call1(&JsValue::NULL, &data);
+                }
+            })
+        };
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        // An uncaught error or a message that can't be deserialized leaves requests unanswered,
+        //     reject every one of them so loading does not wait forever
+        let on_error = {
+            let pending = pending.clone();
+            let failure = failure.clone();
+            Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                let message = error_message(&event);
+                log!("Mesh worker failed: {message}");
+
+                let requests: Vec<(Function, Function)> = pending
+                    .borrow_mut()
+                    .drain()
+                    .map(|(_, request)| request)
+                    .collect();
+                for (_, reject) in requests {
+                    let _ = reject.call1(&JsValue::NULL, &JsValue::from_str(&message));
+                }
+                *failure.borrow_mut() = Some(message);
+            })
+        };
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        worker.set_onmessageerror(Some(on_error.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            worker,
+            lod_config,
+            next_id: Cell::new(0),
+            pending,
+            failure,
+            _on_message: on_message,
+            _on_error: on_error,
+        })
+    }
+
+    /// Send Arrow IPC bytes to the worker and wait for the meshed levels of detail,
+    ///     the bytes are transferred to the worker and are unusable afterwards
+    pub async fn mesh(
+        &self,
+        ipc: Uint8Array,
+        kind: MeshKind,
+    ) -> Result<Vec<BufferStorage>, IngestError> {
+        if let Some(message) = self.failure.borrow().as_ref() {
+            return Err(IngestError::Worker(message.clone()));
+        }
+
+        let id = self.next_id.get();
+        self.next_id.set(id.wrapping_add(1));
+
+        // LOD levels are flattened into [tolerance, min_zoom, tolerance, min_zoom, ...]
+        let levels: Vec<f64> = self
+            .lod_config
+            .levels
+            .iter()
+            .flat_map(|level| [level.tolerance, level.min_zoom])
+            .collect();
+
+        let message = Object::new();
+        set_field(&message, "id", &id.into())?;
+        set_field(&message, "kind", &kind.to_string().into())?;
+        set_field(&message, "ipc", &ipc)?;
+        set_field(&message, "levels", &Float64Array::from(levels.as_slice()))?;
+
+        let response = Promise::new(&mut |resolve, reject| {
+            self.pending.borrow_mut().insert(id, (resolve, reject));
+        });
+
+        if let Err(err) = self
+            .worker
+            .post_message_with_transfer(&message, &Array::of1(&ipc.buffer()))
+        {
+            self.pending.borrow_mut().remove(&id);
+            return Err(worker_error(err));
+        }
+
+        let response = JsFuture::from(response)
+            .await
+            .map_err(|err| IngestError::Worker(error_message(&err)))?;
+        decode_response(&response)
+    }
+}
+
+impl MeshWorker {
+    pub fn lod_config(&self) -> &LodConfig {
+        &self.lod_config
+    }
+}
+
+impl Drop for MeshWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// Entry point when the wasm bundle is loaded inside of the mesh worker
+pub fn run_worker(scope: DedicatedWorkerGlobalScope) {
+    let on_message = {
+        let scope = scope.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let (response, transfer) = handle_request(&event.data());
+            if let Err(err) = scope.post_message_with_transfer(&response, &transfer) {
+                log!("Mesh worker failed to post response: {err:?}");
+            }
+        })
+    };
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+    // The worker lives for the rest of the page, so does its message handler
+    on_message.forget();
+
+    let _ = scope.post_message(&JsValue::from_str("ready"));
+}
+
+/// Decode and mesh a single request, returns the response and the buffers to transfer with it
+fn handle_request(data: &JsValue) -> (Object, Array) {
+    let response = Object::new();
+    let transfer = Array::new();
+
+    let id = get_field(data, "id").unwrap_or(JsValue::NULL);
+    let _ = Reflect::set(&response, &"id".into(), &id);
+
+    match mesh_request(data) {
+        Ok(lods) => {
+            let lod_array = Array::new();
+            for lod in lods {
+                let vertices = Uint8Array::from(bytemuck::cast_slice::<BlendVertex, u8>(
+                    lod.vertices.as_slice(),
+                ));
+                let indices = Uint32Array::from(lod.indices.as_slice());
+                transfer.push(&vertices.buffer());
+                transfer.push(&indices.buffer());
+
+                let lod_object = Object::new();
+                let _ = Reflect::set(&lod_object, &"vertices".into(), &vertices);
+                let _ = Reflect::set(&lod_object, &"indices".into(), &indices);
+                lod_array.push(&lod_object);
+            }
+            let _ = Reflect::set(&response, &"lods".into(), &lod_array);
+        }
+        Err(err) => {
+            log!("Mesh worker failed: {err}");
+            let serialized = serde_json::to_string(&err).unwrap_or_else(|_| err.to_string());
+            let _ = Reflect::set(&response, &"error".into(), &serialized.into());
+        }
+    }
+
+    (response, transfer)
+}
+
+fn mesh_request(data: &JsValue) -> Result<Vec<BufferStorage>, IngestError> {
+    let kind = get_field(data, "kind")?.as_string();
+    let ipc: Uint8Array = get_field(data, "ipc")?
+        .dyn_into()
+        .map_err(|_| IngestError::Worker("Request ipc was not a Uint8Array".to_string()))?;
+    let levels: Float64Array = get_field(data, "levels")?
+        .dyn_into()
+        .map_err(|_| IngestError::Worker("Request levels was not a Float64Array".to_string()))?;
+
+    let lod_config = LodConfig {
+        levels: levels
+            .to_vec()
+            .chunks_exact(2)
+            .map(|level| LodLevel {
+                tolerance: level[0],
+                min_zoom: level[1],
+            })
+            .collect(),
+    };
+
+    let batches = decode_ipc(ipc.to_vec()).map_err(|err| IngestError::Schema(err.to_string()))?;
+
+    let data = match kind.as_deref() {
+        Some("heatmap") => Data::Heatmap(decode_granules(&batches)?),
+        Some("outline") => Data::Outline(decode_outline(&batches)?),
+        other => {
+            return Err(IngestError::Worker(format!(
+                "Unknown mesh request kind {other:?}"
+            )));
+        }
+    };
+
+    log!("Meshing data...");
+    Ok(mesh_data(data, &lod_config))
+}
+
+/// Convert a response from the worker back into meshed levels of detail
+fn decode_response(response: &JsValue) -> Result<Vec<BufferStorage>, IngestError> {
+    if let Some(error) = get_field(response, "error")?.as_string() {
+        return Err(serde_json::from_str(&error).unwrap_or(IngestError::Worker(error)));
+    }
+
+    let lod_array: Array = get_field(response, "lods")?
+        .dyn_into()
+        .map_err(|_| IngestError::Worker("Response lods was not an Array".to_string()))?;
+
+    lod_array
+        .iter()
+        .map(|lod| {
+            let vertices: Uint8Array = get_field(&lod, "vertices")?
+                .dyn_into()
+                .map_err(|_| IngestError::Worker("LOD vertices was not a Uint8Array".into()))?;
+            let indices: Uint32Array = get_field(&lod, "indices")?
+                .dyn_into()
+                .map_err(|_| IngestError::Worker("LOD indices was not a Uint32Array".into()))?;
+
+            // The copied bytes are not guaranteed to be aligned for BlendVertex so read them unaligned
+            let vertices: Vec<BlendVertex> = vertices
+                .to_vec()
+                .chunks_exact(size_of::<BlendVertex>())
+                .map(bytemuck::pod_read_unaligned)
+                .collect();
+            let indices = indices.to_vec();
+            let num_indices = indices
+                .len()
+                .try_into()
+                .map_err(|_| IngestError::Worker("Too many indices in LOD".to_string()))?;
+
+            Ok(BufferStorage {
+                vertices,
+                indices,
+                num_indices,
+            })
+        })
+        .collect()
+}
+
+/// Trunk preloads the wasm-bindgen glue and wasm binary, the worker reuses those same files
+fn bundle_urls() -> Result<(String, String), IngestError> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| IngestError::Worker("Failed to get document".to_string()))?;
+
+    let link_href = |selector: &str| -> Result<String, IngestError> {
+        document
+            .query_selector(selector)
+            .map_err(worker_error)?
+            .and_then(|element| element.dyn_into::<HtmlLinkElement>().ok())
+            .map(|link| link.href())
+            .ok_or_else(|| IngestError::Worker(format!("Failed to find {selector} in document")))
+    };
+
+    Ok((
+        link_href("link[rel=modulepreload][href$='.js']")?,
+        link_href("link[rel=preload][href$='_bg.wasm']")?,
+    ))
+}
+
+fn get_field(object: &JsValue, key: &str) -> Result<JsValue, IngestError> {
+    Reflect::get(object, &key.into()).map_err(worker_error)
+}
+
+fn set_field(object: &Object, key: &str, value: &JsValue) -> Result<(), IngestError> {
+    Reflect::set(object, &key.into(), value)
+        .map(|_| ())
+        .map_err(worker_error)
+}
+
+/// Message of a rejected promise or an error event raised by the worker
+fn error_message(err: &JsValue) -> String {
+    err.as_string()
+        .or_else(|| {
+            Reflect::get(err, &"message".into())
+                .ok()
+                .and_then(|message| message.as_string())
+        })
+        .unwrap_or_else(|| {
+            Reflect::get(err, &"type".into())
+                .ok()
+                .and_then(|kind| kind.as_string())
+                .map_or_else(
+                    || format!("{err:?}"),
+                    |kind| format!("Unexpected {kind} event"),
+                )
+        })
+}
+
+#[allow(
+    clippy::needless_pass_by_value,
+    reason = "Used directly with map_err which passes the error by value"
+)]
+fn worker_error(err: JsValue) -> IngestError {
+    IngestError::Worker(format!("{err:?}"))
+}
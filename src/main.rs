@@ -4,32 +4,45 @@
 use canvas::Canvas;
 use chrono::NaiveDate;
 use leptos::{mount::mount_to_body, prelude::*};
-use ui::{feedback::Feedback, legend::Legend, popup::Popup, user_interface::UserInterface};
+use ui::{
+    feedback::Feedback, legend::Legend, popup::Popup, tooltip::Tooltip,
+    user_interface::UserInterface,
+};
+use wasm_bindgen::JsCast as _;
+use web_sys::DedicatedWorkerGlobalScope;
 
 use crate::types::MaxWeightSignal;
 
 mod canvas;
+mod colormap;
+#[cfg(test)]
+mod colormap_tests;
 mod ingest;
+mod mission;
+#[cfg(test)]
+mod mission_tests;
+#[cfg(test)]
+mod test_support;
 mod types;
 mod ui;
 
-use crate::types::{DateRange, GeneratePngSignal, PopupBody, PopupTitle, ReadySignal};
+use crate::types::{
+    AoiFileSignal, AoiSignal, ColormapSignal, DateRange, ExportOptionsSignal, ExportWidthCapSignal,
+    GenerateImageSignal, HoverSignal, LoadState, LoadStateSignal, PopupBody, PopupRows,
+    PopupRowsSignal, PopupTitle, ReadySignal,
+};
 
 #[component]
 fn Application() -> impl IntoView {
     // Default filter, used on startup
     let (filter, set_filter) = signal(types::Filter {
-        product_type: vec![
-            types::ProductTypes::GroundRangeDetected,
-            types::ProductTypes::SingleLookComplex,
-            types::ProductTypes::Ocean,
-        ],
-        platform_type: vec![
-            types::PlatformType::Sentinel1A,
-            types::PlatformType::Sentinel1B,
-            types::PlatformType::Sentinel1C,
-            types::PlatformType::Sentinel1D,
-        ],
+        product_type: types::ProductTypes::defaults(),
+        platform_type: types::PlatformType::defaults(),
+        beam_mode: types::BeamMode::defaults(),
+        polarization: types::Polarization::defaults(),
+        orbit_direction: None,
+        relative_orbit: vec![],
+        aoi: None,
         date_range: DateRange::new(
             NaiveDate::from_ymd_opt(2026, 6, 1)
                 .expect("Failed to create start date when creating filter signal"),
@@ -44,8 +57,29 @@ fn Application() -> impl IntoView {
     let (ready, set_ready) = signal(false);
     provide_context(ReadySignal(ready));
 
-    let (generate_img, set_generate_img) = signal(false);
-    provide_context(GeneratePngSignal(generate_img));
+    // Stage of the most recent data load, a new filter can be submitted while this is not idle
+    let (load_state, set_load_state) = signal(LoadState::Idle);
+    provide_context(LoadStateSignal(load_state));
+
+    // Format of the image the canvas should export, it is reset once the export has started
+    let (generate_img, set_generate_img) = signal(None);
+    provide_context(GenerateImageSignal(generate_img));
+
+    // Size, resolution and framing exported pngs are rendered with
+    let (export_options, set_export_options) = signal(types::ExportOptions::default());
+    provide_context(ExportOptionsSignal(export_options));
+
+    // Widest export the GPU can render, set by the canvas once it has the limits of the device
+    let (export_width_cap, set_export_width_cap) = signal(*types::EXPORT_WIDTHS.end());
+    provide_context(ExportWidthCapSignal(export_width_cap));
+
+    // Colormap the heatmap, legend and exported images are drawn with
+    let (colormap, set_colormap) = signal(colormap::Colormap::default());
+    provide_context(ColormapSignal(colormap));
+
+    // Format the granules in view are exported as, read by the canvas which knows the viewport
+    let (export_granules, set_export_granules) = signal(None);
+    provide_context(GranuleExportSignal(export_granules));
 
     let (title, set_title) = signal("Disclaimers".to_string());
     provide_context(PopupTitle(title));
@@ -53,20 +87,58 @@ fn Application() -> impl IntoView {
     let (body, set_body) = signal(
         "This product is in early development, expect to see bugs! <br/><br/>
         Generated data is not guaranteed to be accurate. <br/><br/>
-        On encountering a crash the app will hang. <br/>
+        If data fails to load you will be notified here. <br/>
         Check the dev console if the page becomes unresponsive."
             .to_string(),
     );
     provide_context(PopupBody(body));
 
+    // Granules listed under the popup body, ie. the ones at a point clicked on the map
+    let (popup_rows, set_popup_rows) = signal(PopupRows::default());
+    provide_context(PopupRowsSignal(popup_rows));
+
     let (max_weight, set_max_weight) = signal(0u32);
     provide_context(MaxWeightSignal(max_weight));
 
+    // Area of interest drawn on the canvas, applied to the filter by the UI
+    let (aoi, set_aoi) = signal(None);
+    provide_context(AoiSignal(aoi));
+
+    // File the user imported an area of interest from, read by the canvas once DuckDB is ready
+    let (aoi_file, set_aoi_file) = signal(None);
+    provide_context(AoiFileSignal(aoi_file));
+
+    // Coordinate and weight under the cursor, read back from the canvas
+    let (hover, set_hover) = signal(None);
+    provide_context(HoverSignal(hover));
+
     view! {
         <div>
             <Popup/>
-            <UserInterface set_filter set_title set_body/>
-            <Canvas set_generate_img set_ready set_max_weight/>
+            <UserInterface
+                set_filter
+                set_aoi
+                set_aoi_file
+                set_export_granules
+                set_generate_img
+                set_export_options
+                set_colormap
+                set_title
+                set_body
+            />
+            <Canvas
+                set_generate_img
+                set_ready
+                set_load_state
+                set_max_weight
+                set_export_width_cap
+                set_aoi
+                set_title
+                set_body
+                set_popup_rows
+                set_hover
+            />
+            <Tooltip/>
             <Legend/>
             <Feedback/>
         </div>
@@ -75,5 +147,12 @@ fn Application() -> impl IntoView {
 
 fn main() {
     console_error_panic_hook::set_once();
+
+    // The mesh worker loads this same bundle, start the worker instead of the UI when inside of it
+    if let Ok(scope) = js_sys::global().dyn_into::<DedicatedWorkerGlobalScope>() {
+        ingest::worker::run_worker(scope);
+        return;
+    }
+
     mount_to_body(Application);
 }
@@ -0,0 +1,176 @@
+//! Registry of the SAR missions the heatmap can show, every mission's archive is stored as monthly
+//!     parquet files with the same `ancestors` schema so missions only differ in where their files
+//!     are kept and how filters are read out of their granule names
+use std::ops::RangeInclusive;
+
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::types::{PlatformType, ProductTypes};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display, EnumIter)]
+pub enum Mission {
+    #[strum(to_string = "Sentinel-1")]
+    Sentinel1,
+    #[strum(to_string = "NISAR")]
+    Nisar,
+    #[strum(to_string = "ALOS-2")]
+    Alos2,
+    #[strum(to_string = "ERS")]
+    Ers,
+}
+
+/// Location of a code in a granule name, start is 1-indexed to match SQL's `substring`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GranuleField {
+    pub start: u8,
+    pub length: u8,
+}
+
+impl GranuleField {
+    /// SQL expression extracting the field from an ancestor's granule name
+    pub fn sql(self) -> String {
+        format!("substring(x.granule_name, {}, {})", self.start, self.length)
+    }
+}
+
+/// Fields encoded in a mission's granule names, filters on a field the mission does not encode
+///     are not applied to its granules
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GranuleFields {
+    pub product: Option<GranuleField>,
+    pub beam_mode: Option<GranuleField>,
+    pub polarization: Option<GranuleField>,
+}
+
+impl Mission {
+    /// Platforms that flew on the mission
+    pub fn platforms(self) -> Vec<PlatformType> {
+        PlatformType::iter()
+            .filter(|x| x.mission() == self)
+            .collect()
+    }
+
+    /// Orbits the mission flies before its ground track repeats, relative orbits are numbered from
+    ///     1 up to this
+    pub const fn relative_orbits(self) -> u16 {
+        match self {
+            Self::Sentinel1 => 175,
+            Self::Nisar => 173,
+            Self::Alos2 => 207,
+            // The 35 day repeat cycle most of the archive was acquired in
+            Self::Ers => 501,
+        }
+    }
+
+    /// Directory under the data source root holding the mission's monthly parquet files
+    pub const fn directory(self) -> &'static str {
+        match self {
+            Self::Sentinel1 => "sat_data",
+            Self::Nisar => "nisar_data",
+            Self::Alos2 => "alos2_data",
+            Self::Ers => "ers_data",
+        }
+    }
+
+    pub const fn granule_fields(self) -> GranuleFields {
+        match self {
+            // S1A_IW_GRDH_1SDV_...
+            Self::Sentinel1 => GranuleFields {
+                product: Some(GranuleField {
+                    start: 8,
+                    length: 3,
+                }),
+                beam_mode: Some(GranuleField {
+                    start: 5,
+                    length: 2,
+                }),
+                polarization: Some(GranuleField {
+                    start: 15,
+                    length: 2,
+                }),
+            },
+            // NISAR_L1_PR_RSLC_...
+            Self::Nisar => GranuleFields {
+                product: Some(GranuleField {
+                    start: 13,
+                    length: 4,
+                }),
+                beam_mode: None,
+                polarization: None,
+            },
+            Self::Alos2 | Self::Ers => GranuleFields {
+                product: None,
+                beam_mode: None,
+                polarization: None,
+            },
+        }
+    }
+
+    /// Color the mission is drawn with when missions are compared, as RGB
+    pub const fn color(self) -> [u8; 3] {
+        match self {
+            Self::Sentinel1 => [31, 119, 180],
+            Self::Nisar => [255, 127, 14],
+            Self::Alos2 => [44, 160, 44],
+            Self::Ers => [214, 39, 40],
+        }
+    }
+}
+
+/// The first mission with a selected platform that reads product types from its granule names
+///     but has none of its product types selected, every one of its granules would be filtered out
+pub fn mission_without_products(
+    product_type: &[ProductTypes],
+    platform_type: &[PlatformType],
+) -> Option<Mission> {
+    Mission::iter().find(|mission| {
+        platform_type.iter().any(|x| x.mission() == *mission)
+            && mission.granule_fields().product.is_some()
+            && !product_type.iter().any(|x| x.mission() == *mission)
+    })
+}
+
+/// Parse a comma separated list of relative orbits and inclusive ranges of them, ie. "12, 40-45",
+///     an empty list includes every relative orbit. Orbits up to the most any of the missions have
+///     are accepted, missions with fewer simply have no data for the rest
+pub fn parse_relative_orbits(
+    text: &str,
+    missions: &[Mission],
+) -> Result<Vec<RangeInclusive<u16>>, String> {
+    let max = missions
+        .iter()
+        .map(|mission| mission.relative_orbits())
+        .max()
+        .unwrap_or_else(|| Mission::Sentinel1.relative_orbits());
+    let names: Vec<String> = missions.iter().map(ToString::to_string).collect();
+
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let parse = |orbit: &str| {
+                orbit
+                    .trim()
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|orbit| (1..=max).contains(orbit))
+                    .ok_or_else(|| {
+                        format!(
+                            "\"{item}\" is not a relative orbit of {}, expected a number from 1 to {max}",
+                            names.join(" or ")
+                        )
+                    })
+            };
+
+            let (start, end) = item.split_once('-').unwrap_or((item, item));
+            let (start, end) = (parse(start)?, parse(end)?);
+            if start > end {
+                return Err(format!(
+                    "Relative orbit range \"{item}\" starts after it ends"
+                ));
+            }
+            Ok(start..=end)
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+//! Tests of the per mission rules the filter is checked against
+use strum::IntoEnumIterator as _;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::mission::{Mission, mission_without_products, parse_relative_orbits};
+use crate::types::{PlatformType, ProductTypes};
+
+#[wasm_bindgen_test]
+fn relative_orbits_up_to_mission_max() {
+    for mission in Mission::iter() {
+        let max = mission.relative_orbits();
+        assert_eq!(
+            parse_relative_orbits(&format!("1-{max}"), &[mission]),
+            Ok(vec![1..=max])
+        );
+        assert!(parse_relative_orbits(&(max + 1).to_string(), &[mission]).is_err());
+        assert!(parse_relative_orbits("0", &[mission]).is_err());
+    }
+
+    // The mission with the most relative orbits sets the limit
+    let missions = [Mission::Sentinel1, Mission::Ers];
+    assert_eq!(parse_relative_orbits("501", &missions), Ok(vec![501..=501]));
+    assert!(parse_relative_orbits("502", &missions).is_err());
+    assert_eq!(parse_relative_orbits("", &missions), Ok(vec![]));
+}
+
+#[wasm_bindgen_test]
+fn mission_needs_its_own_products() {
+    let sentinel = [ProductTypes::GroundRangeDetected];
+    let nisar = [ProductTypes::GeocodedCovariance];
+
+    assert_eq!(
+        mission_without_products(&sentinel, &[PlatformType::Sentinel1A]),
+        None
+    );
+    // NISAR products are read from its granule names, without one of them nothing matches
+    assert_eq!(
+        mission_without_products(&sentinel, &[PlatformType::Sentinel1A, PlatformType::Nisar]),
+        Some(Mission::Nisar)
+    );
+    assert_eq!(
+        mission_without_products(&nisar, &[PlatformType::Sentinel1A, PlatformType::Nisar]),
+        Some(Mission::Sentinel1)
+    );
+    assert_eq!(
+        mission_without_products(
+            &[sentinel[0], nisar[0]],
+            &[PlatformType::Sentinel1A, PlatformType::Nisar]
+        ),
+        None
+    );
+
+    // ALOS-2 and ERS granule names have no product type
+    assert_eq!(
+        mission_without_products(&sentinel, &[PlatformType::Alos2, PlatformType::Ers1]),
+        None
+    );
+}
@@ -0,0 +1,78 @@
+//! Dates, filters and export settings shared by the tests
+use chrono::NaiveDate;
+use geo::{Rect, coord};
+use image::{Rgba, RgbaImage};
+
+use crate::types::{
+    BeamMode, DateRange, ExportOptions, Filter, PlatformType, Polarization, ProductTypes,
+};
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("Invalid date")
+}
+
+/// Every Sentinel-1 platform, product, beam mode and polarization between the dates
+pub fn sentinel1_filter(start: NaiveDate, end: NaiveDate) -> Filter {
+    Filter {
+        date_range: DateRange::new(start, end).expect("Invalid date range"),
+        product_type: vec![
+            ProductTypes::GroundRangeDetected,
+            ProductTypes::SingleLookComplex,
+            ProductTypes::Ocean,
+        ],
+        platform_type: vec![
+            PlatformType::Sentinel1A,
+            PlatformType::Sentinel1B,
+            PlatformType::Sentinel1C,
+            PlatformType::Sentinel1D,
+        ],
+        beam_mode: vec![
+            BeamMode::InterferometricWideSwath,
+            BeamMode::ExtraWideSwath,
+            BeamMode::Stripmap,
+            BeamMode::Wave,
+        ],
+        polarization: vec![
+            Polarization::SingleHH,
+            Polarization::SingleVV,
+            Polarization::DualHH,
+            Polarization::DualVV,
+        ],
+        orbit_direction: None,
+        relative_orbit: vec![],
+        aoi: None,
+    }
+}
+
+/// A filter narrow enough that every part of it is written in the caption of an export
+pub fn filter() -> Filter {
+    Filter {
+        date_range: DateRange::new(date(2026, 6, 1), date(2026, 7, 1)).expect("Invalid date range"),
+        product_type: vec![
+            ProductTypes::GroundRangeDetected,
+            ProductTypes::SingleLookComplex,
+        ],
+        platform_type: vec![PlatformType::Sentinel1A, PlatformType::Sentinel1C],
+        beam_mode: vec![BeamMode::InterferometricWideSwath],
+        polarization: vec![Polarization::DualVV],
+        orbit_direction: None,
+        relative_orbit: vec![],
+        aoi: None,
+    }
+}
+
+pub fn options() -> ExportOptions {
+    ExportOptions {
+        dpi: 288,
+        ..ExportOptions::default()
+    }
+}
+
+pub fn world() -> Rect {
+    Rect::new(coord! { x: -180.0, y: -90.0 }, coord! { x: 180.0, y: 90.0 })
+}
+
+/// Runs from black to red so legend colors can't be confused with the map or text
+pub fn colormap() -> RgbaImage {
+    RgbaImage::from_fn(256, 1, |x, _| Rgba([x as u8, 0, 0, 255]))
+}
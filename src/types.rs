@@ -1,13 +1,45 @@
 use std::error::Error;
+use std::ops::RangeInclusive;
 
 use chrono::NaiveDate;
-use geo::Polygon;
+use geo::{Geometry, MultiPolygon, Polygon};
 use leptos::prelude::ReadSignal;
 use serde::{Deserialize, Serialize};
-use strum_macros::Display;
+use strum::{EnumMessage, IntoEnumIterator};
+use strum_macros::{Display, EnumIter, EnumMessage};
 
-// Enums defining possible filter options
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+use crate::colormap::Colormap;
+use crate::mission::Mission;
+
+/// A set of options the filter can select between, the UI generates a checkbox for each variant
+///     in declaration order so a new variant only needs to be added to its enum
+pub trait FilterOption:
+    Copy + PartialEq + std::fmt::Display + IntoEnumIterator + EnumMessage + 'static
+{
+    /// Text shown next to the checkbox, the strum message if there is one otherwise the code
+    fn label(self) -> String {
+        self.get_message()
+            .map_or_else(|| self.to_string(), str::to_string)
+    }
+
+    /// Whether the option is included in the filter the app starts with
+    fn default_selected(self) -> bool {
+        true
+    }
+
+    /// Color of the swatch shown next to the checkbox, as RGB
+    fn color(self) -> Option<[u8; 3]> {
+        None
+    }
+
+    /// Every option included in the filter the app starts with
+    fn defaults() -> Vec<Self> {
+        Self::iter().filter(|x| x.default_selected()).collect()
+    }
+}
+
+// Enums defining possible filter options, Display gives the code used in the archive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumIter, EnumMessage)]
 pub enum ProductTypes {
     #[strum(to_string = "GRD")]
     GroundRangeDetected,
@@ -15,18 +47,143 @@ pub enum ProductTypes {
     SingleLookComplex,
     #[strum(to_string = "OCN")]
     Ocean,
+    #[strum(to_string = "RAW")]
+    Raw,
+    // ETAD granules are named S1X_MM_ETA__AXPP_...
+    #[strum(to_string = "ETA", message = "ETAD")]
+    ExtendedTimingAnnotation,
+    #[strum(to_string = "RSLC")]
+    RangeDopplerSingleLookComplex,
+    #[strum(to_string = "GSLC")]
+    GeocodedSingleLookComplex,
+    #[strum(to_string = "GCOV")]
+    GeocodedCovariance,
+    #[strum(to_string = "GUNW")]
+    GeocodedUnwrappedInterferogram,
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Display)]
+impl ProductTypes {
+    /// Mission the product is produced for, its code is read from the granule name as described by
+    ///     the mission's `GranuleFields`
+    pub const fn mission(self) -> Mission {
+        match self {
+            Self::GroundRangeDetected
+            | Self::SingleLookComplex
+            | Self::Ocean
+            | Self::Raw
+            | Self::ExtendedTimingAnnotation => Mission::Sentinel1,
+            Self::RangeDopplerSingleLookComplex
+            | Self::GeocodedSingleLookComplex
+            | Self::GeocodedCovariance
+            | Self::GeocodedUnwrappedInterferogram => Mission::Nisar,
+        }
+    }
+}
+
+impl FilterOption for ProductTypes {
+    // Auxiliary products are mostly of interest on their own so they start unchecked
+    fn default_selected(self) -> bool {
+        self.mission() == Mission::Sentinel1
+            && !matches!(self, Self::Raw | Self::ExtendedTimingAnnotation)
+    }
+
+    fn color(self) -> Option<[u8; 3]> {
+        Some(self.mission().color())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumIter, EnumMessage)]
+pub enum BeamMode {
+    #[strum(to_string = "IW")]
+    InterferometricWideSwath,
+    #[strum(to_string = "EW")]
+    ExtraWideSwath,
+    #[strum(to_string = "SM")]
+    Stripmap,
+    #[strum(to_string = "WV")]
+    Wave,
+}
+
+impl BeamMode {
+    /// Codes used for the beam mode in granule names, stripmap is named after the swath it used
+    pub const fn granule_codes(self) -> &'static [&'static str] {
+        match self {
+            Self::InterferometricWideSwath => &["IW"],
+            Self::ExtraWideSwath => &["EW"],
+            Self::Stripmap => &["S1", "S2", "S3", "S4", "S5", "S6"],
+            Self::Wave => &["WV"],
+        }
+    }
+}
+
+impl FilterOption for BeamMode {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumIter, EnumMessage)]
+pub enum Polarization {
+    #[strum(to_string = "SH")]
+    SingleHH,
+    #[strum(to_string = "SV")]
+    SingleVV,
+    #[strum(to_string = "DH")]
+    DualHH,
+    #[strum(to_string = "DV")]
+    DualVV,
+}
+
+impl FilterOption for Polarization {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+pub enum OrbitDirection {
+    #[strum(to_string = "ASCENDING")]
+    Ascending,
+    #[strum(to_string = "DESCENDING")]
+    Descending,
+}
+
+#[derive(
+    Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Display, EnumIter, EnumMessage,
+)]
 pub enum PlatformType {
-    #[strum(to_string = "SA")]
+    #[strum(to_string = "SA", message = "S1A")]
     Sentinel1A,
-    #[strum(to_string = "SB")]
+    #[strum(to_string = "SB", message = "S1B")]
     Sentinel1B,
-    #[strum(to_string = "5C")]
+    #[strum(to_string = "5C", message = "S1C")]
     Sentinel1C,
-    #[strum(to_string = "5D")]
+    #[strum(to_string = "5D", message = "S1D")]
     Sentinel1D,
+    #[strum(to_string = "NISAR")]
+    Nisar,
+    #[strum(to_string = "ALOS-2")]
+    Alos2,
+    #[strum(to_string = "ERS-1")]
+    Ers1,
+    #[strum(to_string = "ERS-2")]
+    Ers2,
+}
+
+impl PlatformType {
+    pub const fn mission(self) -> Mission {
+        match self {
+            Self::Sentinel1A | Self::Sentinel1B | Self::Sentinel1C | Self::Sentinel1D => {
+                Mission::Sentinel1
+            }
+            Self::Nisar => Mission::Nisar,
+            Self::Alos2 => Mission::Alos2,
+            Self::Ers1 | Self::Ers2 => Mission::Ers,
+        }
+    }
+}
+
+impl FilterOption for PlatformType {
+    // Other missions are opt in so their archives are only downloaded when asked for
+    fn default_selected(self) -> bool {
+        self.mission() == Mission::Sentinel1
+    }
+
+    fn color(self) -> Option<[u8; 3]> {
+        Some(self.mission().color())
+    }
 }
 
 #[derive(Debug)]
@@ -35,6 +192,116 @@ pub struct Granule {
     pub weight: u64,
 }
 
+/// An acquisition that counts towards the heatmap at an inspected point
+#[derive(Clone, Debug)]
+pub struct InspectedGranule {
+    pub granule_name: String,
+    pub start_time: String,
+}
+
+/// An acquisition that counts towards the heatmap in the exported region, the footprint is the
+///     whole frame rather than the part of it inside of the region
+#[derive(Clone, Debug)]
+pub struct ExportedGranule {
+    pub granule_name: String,
+    pub platform_type: String,
+    pub product_type: Option<String>,
+    pub start_time: String,
+    pub footprint: Geometry,
+}
+
+/// File format the granules in view are exported as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GranuleFormat {
+    Csv,
+    GeoJson,
+}
+
+impl GranuleFormat {
+    pub const fn file_name(self) -> &'static str {
+        match self {
+            Self::Csv => "granules.csv",
+            Self::GeoJson => "granules.geojson",
+        }
+    }
+
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::GeoJson => "application/geo+json",
+        }
+    }
+}
+
+/// Region of the world an exported png shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumIter)]
+pub enum ExportFraming {
+    #[default]
+    #[strum(to_string = "Whole World")]
+    World,
+    #[strum(to_string = "Current View")]
+    Viewport,
+    // Falls back to the whole world when there is no area of interest
+    #[strum(to_string = "Area of Interest")]
+    Aoi,
+}
+
+/// Widths in pixels the map of an export can be rendered at, the GPU may cap this further
+pub const EXPORT_WIDTHS: RangeInclusive<u32> = 256..=8192;
+/// Resolutions in dots per inch an exported png can be printed at
+pub const EXPORT_DPIS: RangeInclusive<u32> = 72..=1200;
+
+/// How the heatmap is rendered for an export, the height of the map follows from its width
+///     and the framing so it is never stretched
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExportOptions {
+    // Width of the map in pixels, the legend and caption are laid out around it
+    pub width: u32,
+    // Written to the png and used to size the text so it is legible when printed, unused by GeoTIFFs
+    pub dpi: u32,
+    pub framing: ExportFraming,
+    // Leaves out the white page and the dark background of the map
+    pub transparent: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            width: 2560,
+            dpi: 300,
+            framing: ExportFraming::World,
+            transparent: false,
+        }
+    }
+}
+
+/// File format the heatmap is exported as, a png or SVG of the map or a GeoTIFF of the weights
+///     under it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    GeoTiff,
+    Svg,
+}
+
+impl ImageFormat {
+    pub const fn file_name(self) -> &'static str {
+        match self {
+            Self::Png => "heatmap.png",
+            Self::GeoTiff => "heatmap.tif",
+            Self::Svg => "heatmap.svg",
+        }
+    }
+
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::GeoTiff => "image/tiff",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DateRange {
     pub start: NaiveDate,
@@ -65,49 +332,91 @@ impl DateRange {
         Ok(())
     }
 
-    /// Returns all sections of other that are disjoint from self
-    pub fn get_disjoint(&self, other: &Self) -> Option<Vec<Self>> {
-        if self.end < other.start || other.end < self.start {
-            // Non overlapping ranges
-            return Some(vec![other.clone()]);
-        }
-        if self.start > other.start && self.end < other.end {
-            // Self is a subset of other
-            return Some(vec![
-                Self {
-                    start: other.start,
-                    end: self.start,
-                },
-                Self {
-                    start: self.end,
-                    end: other.end,
-                },
-            ]);
+    /// Returns the sections of self that are not covered by any of the passed ranges, sorted by start
+    pub fn subtract(&self, covered: &[Self]) -> Vec<Self> {
+        let mut covered: Vec<&Self> = covered
+            .iter()
+            .filter(|x| x.start < self.end && self.start < x.end)
+            .collect();
+        covered.sort_by_key(|x| x.start);
+
+        let mut gaps = Vec::new();
+        let mut cursor = self.start;
+        for range in covered {
+            if cursor < range.start {
+                gaps.push(Self {
+                    start: cursor,
+                    end: range.start,
+                });
+            }
+            cursor = cursor.max(range.end);
         }
-        if self.end < other.end {
-            // Self comes first and partially overlaps other
-            return Some(vec![Self {
-                start: self.end,
-                end: other.end,
-            }]);
+        if cursor < self.end {
+            gaps.push(Self {
+                start: cursor,
+                end: self.end,
+            });
         }
-        if self.start > other.start {
-            // Other comes first and partially overlaps self
-            return Some(vec![Self {
-                start: other.start,
-                end: self.start,
-            }]);
+        gaps
+    }
+
+    /// Add a range to a set of disjoint ranges, merging it with any range it borders
+    pub fn insert_into(ranges: &mut Vec<Self>, range: Self) {
+        ranges.push(range);
+        ranges.sort_by_key(|x| x.start);
+
+        let mut merged: Vec<Self> = Vec::with_capacity(ranges.len());
+        for range in ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.merge(&range).is_ok() => (),
+                _ => merged.push(range),
+            }
         }
-        None
+        *ranges = merged;
     }
 }
 
+/// Stage the `DataLoader` is in for the most recent filter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+pub enum LoadState {
+    #[strum(to_string = "Idle")]
+    Idle,
+    #[strum(to_string = "Downloading data")]
+    Ingesting,
+    #[strum(to_string = "Meshing data")]
+    Meshing,
+}
+
 // Describes a heatmap to generate
 #[derive(Clone)]
 pub struct Filter {
     pub date_range: DateRange,
     pub product_type: Vec<ProductTypes>,
     pub platform_type: Vec<PlatformType>,
+    pub beam_mode: Vec<BeamMode>,
+    pub polarization: Vec<Polarization>,
+    // None includes both ascending and descending passes
+    pub orbit_direction: Option<OrbitDirection>,
+    // Relative orbits to include, empty includes every relative orbit
+    pub relative_orbit: Vec<RangeInclusive<u16>>,
+    // Area of interest in lon/lat, granules are clipped to it, None covers the whole globe
+    pub aoi: Option<MultiPolygon>,
+}
+
+impl Filter {
+    /// Missions with at least one selected platform, in registry order
+    pub fn missions(&self) -> Vec<Mission> {
+        Mission::iter()
+            .filter(|mission| self.platform_type.iter().any(|x| x.mission() == *mission))
+            .collect()
+    }
+}
+
+/// A file the user picked to import an area of interest from, ie. GeoJSON, WKT or a zipped shapefile
+#[derive(Clone, Debug)]
+pub struct AoiFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
 }
 
 ////////////////////////////////////////
@@ -118,7 +427,22 @@ pub struct Filter {
 pub struct ReadySignal(pub ReadSignal<bool>);
 
 #[derive(Clone)]
-pub struct GeneratePngSignal(pub ReadSignal<bool>);
+pub struct LoadStateSignal(pub ReadSignal<LoadState>);
+
+#[derive(Clone)]
+pub struct GenerateImageSignal(pub ReadSignal<Option<ImageFormat>>);
+
+#[derive(Clone)]
+pub struct ExportOptionsSignal(pub ReadSignal<ExportOptions>);
+
+#[derive(Clone)]
+pub struct ExportWidthCapSignal(pub ReadSignal<u32>);
+
+#[derive(Clone)]
+pub struct ColormapSignal(pub ReadSignal<Colormap>);
+
+#[derive(Clone)]
+pub struct GranuleExportSignal(pub ReadSignal<Option<GranuleFormat>>);
 
 #[derive(Clone)]
 pub struct ExpansionSignal(pub ReadSignal<bool>);
@@ -126,8 +450,39 @@ pub struct ExpansionSignal(pub ReadSignal<bool>);
 #[derive(Clone)]
 pub struct MaxWeightSignal(pub ReadSignal<u32>);
 
+#[derive(Clone)]
+pub struct AoiSignal(pub ReadSignal<Option<MultiPolygon>>);
+
+#[derive(Clone)]
+pub struct AoiFileSignal(pub ReadSignal<Option<AoiFile>>);
+
 #[derive(Clone)]
 pub struct PopupTitle(pub ReadSignal<String>);
 
+/// Table listed under the popup body a page at a time, it is only shown while the popup still has
+///     the title it was written with so a newer message never shows stale rows
+#[derive(Clone, Debug, Default)]
+pub struct PopupRows {
+    pub title: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Clone)]
+pub struct PopupRowsSignal(pub ReadSignal<PopupRows>);
+
+/// Coordinate and blended weight under the cursor, `x` and `y` place the tooltip in CSS pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HoverInfo {
+    pub lon: f64,
+    pub lat: f64,
+    pub weight: f32,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Clone)]
+pub struct HoverSignal(pub ReadSignal<Option<HoverInfo>>);
+
 #[derive(Clone)]
 pub struct PopupBody(pub ReadSignal<String>);
@@ -0,0 +1,77 @@
+use leptos::wasm_bindgen::JsCast as _;
+use leptos::{IntoView, component, prelude::*, view};
+
+use crate::types::FilterOption;
+
+/// A titled group with a checkbox for every variant of T, the value of each checkbox is the
+///     index of its variant so `checked_options` can map them back
+#[component]
+pub fn CheckboxGroup<T>(
+    title: &'static str,
+    name: &'static str,
+    // Options that start checked
+    selected: Vec<T>,
+) -> impl IntoView
+where
+    T: FilterOption,
+{
+    let checkboxes = T::iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let id = format!("{name}-{i}");
+            view! {
+                <input
+                    class="checkbox"
+                    type="checkbox"
+                    id=id.clone()
+                    name=name
+                    value=i
+                    checked=selected.contains(&option)
+                />
+                <label class="text" for=id>
+                    {option
+                        .color()
+                        .map(|[r, g, b]| {
+                            view! {
+                                <span
+                                    class="swatch"
+                                    style=format!("background-color: rgb({r}, {g}, {b})")
+                                ></span>
+                            }
+                        })}
+                    {option.label()}
+                </label>
+                <br/>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div id=name>
+            <p>{title}</p>
+            {checkboxes}
+        </div>
+    }
+}
+
+/// Options whose checkbox is checked in the `CheckboxGroup` with the given name
+pub fn checked_options<T: FilterOption>(name: &str) -> Vec<T> {
+    let Ok(nodes) = document().query_selector_all(&format!("input[name={name}]:checked")) else {
+        return Vec::new();
+    };
+
+    (0..nodes.length())
+        .filter_map(|i| {
+            let index = nodes
+                .get(i)
+                .expect("Failed to get checked node")
+                .dyn_into::<web_sys::Element>()
+                .expect("Failed to cast Node to element")
+                .get_attribute("value")
+                .expect("Failed to get value attribute")
+                .parse::<usize>()
+                .expect("Failed to parse index from value");
+            T::iter().nth(index)
+        })
+        .collect()
+}
@@ -1,7 +1,8 @@
 use leptos::{IntoView, component, prelude::*, view};
 
 use crate::MaxWeightSignal;
-use crate::types::ExpansionSignal;
+use crate::colormap::COLORMAP_WIDTH;
+use crate::types::{ColormapSignal, ExpansionSignal};
 use crate::ui::expansion_button::ExpansionButton;
 
 #[component]
@@ -12,7 +13,10 @@ pub fn Legend() -> impl IntoView {
     let MaxWeightSignal(max_weight) =
         use_context::<MaxWeightSignal>().expect("Failed to get max weight signal in Legend");
 
-    let calc_tex_coord = |x: f32, max: f32| (((x * 1.32) / max) * 480.0) as u32;
+    let ColormapSignal(colormap) =
+        use_context::<ColormapSignal>().expect("Failed to get colormap signal in Legend");
+
+    let calc_tex_coord = |x: f32, max: f32| (((x * 1.32) / max) * COLORMAP_WIDTH as f32) as u32;
 
     // List of weights to use for the legend
     let weights = move || {
@@ -31,7 +35,7 @@ pub fn Legend() -> impl IntoView {
                 n => ((f64::from(*n) / 5.0).round() * 5.0) as usize,
             };
             let val = calc_tex_coord(scaled_weight as f32, max as f32);
-            if val > 0 && val < 480 {
+            if val > 0 && val < COLORMAP_WIDTH {
                 Some(scaled_weight)
             } else {
                 None
@@ -60,20 +64,18 @@ pub fn Legend() -> impl IntoView {
                             let wvec = weights();
                             let row = format!("{}", i + 2);
 
-                            // Reading the image every time the signal update feels bad...
-                            let colormap_bytes = include_bytes!("../../assets/plasma.png");
-                            let colormap_image = image::load_from_memory(colormap_bytes)
-                                .expect("ERROR: Failed to generate image from colormap_bytes")
-                                .to_rgba8();
-
                             let max = max_weight();
                             let coord = calc_tex_coord(x as f32, max as f32);
-                            let pixel = colormap_image.get_pixel(coord, 0).0;
 
-                            let background_color = format!(
-                                "background-color: #{:02x}{:02x}{:02x}",
-                                pixel[0], pixel[1], pixel[2]
-                            );
+                            // The swatch follows the colormap the heatmap is drawn with
+                            let background_color = move || {
+                                let pixel =
+                                    colormap.with(|colormap| colormap.image.get_pixel(coord, 0).0);
+                                format!(
+                                    "background-color: #{:02x}{:02x}{:02x}",
+                                    pixel[0], pixel[1], pixel[2]
+                                )
+                            };
 
                             let legend_text = create_legend_text(i, x, &wvec);
 
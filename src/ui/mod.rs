@@ -1,6 +1,8 @@
 //! Module contains User Interface for app
+mod checkbox_group;
 mod expansion_button;
 pub mod feedback;
 pub mod legend;
 pub mod popup;
+pub mod tooltip;
 pub mod user_interface;
@@ -1,14 +1,20 @@
 use leptos::{IntoView, component, prelude::*, view};
 
-use crate::types::{PopupBody, PopupTitle};
+use crate::types::{PopupBody, PopupRowsSignal, PopupTitle};
+
+// Rows of the popup table shown at once
+const ROWS_PER_PAGE: usize = 10;
 
 #[component]
 pub fn Popup() -> impl IntoView {
     let PopupTitle(title) =
         use_context::<PopupTitle>().expect("Failed to get title signal in Popup");
     let PopupBody(body) = use_context::<PopupBody>().expect("Failed to get body signal in Popup");
+    let PopupRowsSignal(rows) =
+        use_context::<PopupRowsSignal>().expect("Failed to get rows signal in Popup");
 
     let (dismissed, set_dismissed) = signal(false);
+    let (page, set_page) = signal(0usize);
 
     Effect::new(move |_| {
         title();
@@ -16,6 +22,14 @@ pub fn Popup() -> impl IntoView {
         set_dismissed(false);
     });
 
+    // A new table starts on its first page
+    Effect::new(move |_| {
+        rows.track();
+        set_page(0);
+    });
+
+    let pages = move || rows.with(|x| x.rows.len().div_ceil(ROWS_PER_PAGE));
+
     view! {
         <Show when=move || { !dismissed() }>
             <div
@@ -32,8 +46,70 @@ pub fn Popup() -> impl IntoView {
                         {title}
                     </h3>
                     <span class="popup-text" inner_html=body/>
+                    <Show when=move || rows.with(|x| x.title == title() && !x.rows.is_empty())>
+                        <table class="popup-table">
+                            <tr>
+                                {move || {
+                                    rows.with(|x| {
+                                        x.columns
+                                            .iter()
+                                            .map(|column| view! { <th>{column.clone()}</th> })
+                                            .collect_view()
+                                    })
+                                }}
+                            </tr>
+                            {move || {
+                                rows.with(|x| {
+                                    x.rows
+                                        .iter()
+                                        .skip(page() * ROWS_PER_PAGE)
+                                        .take(ROWS_PER_PAGE)
+                                        .map(|row| {
+                                            view! {
+                                                <tr>
+                                                    {row
+                                                        .iter()
+                                                        .map(|cell| view! { <td>{cell.clone()}</td> })
+                                                        .collect_view()}
+                                                </tr>
+                                            }
+                                        })
+                                        .collect_view()
+                                })
+                            }}
+                        </table>
+                        <div class="popup-pages">
+                            <input
+                                type="button"
+                                value="Previous"
+                                class="button"
+                                disabled=move || page() == 0
+                                on:click=move |_| set_page.update(|x| *x = x.saturating_sub(1))
+                            />
+                            <span class="popup-text">
+                                {move || format!("Page {} of {}", page() + 1, pages())}
+                            </span>
+                            <input
+                                type="button"
+                                value="Next"
+                                class="button"
+                                disabled=move || page() + 1 >= pages()
+                                on:click=move |_| set_page.update(|x| *x += 1)
+                            />
+                        </div>
+                    </Show>
                 </div>
             </div>
         </Show>
     }
 }
+
+/// Escape text written into the popup body, the body is rendered as html so messages can break
+///     lines, anything from outside of the app, ie. DuckDB errors or user input, must be escaped
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,34 @@
+use leptos::{IntoView, component, prelude::*, view};
+
+use crate::types::HoverSignal;
+
+/// Follows the cursor over the map with its coordinate and the number of acquisitions there
+#[component]
+pub fn Tooltip() -> impl IntoView {
+    let HoverSignal(hover) =
+        use_context::<HoverSignal>().expect("Failed to get hover signal in Tooltip");
+
+    view! {
+        {move || {
+            hover()
+                .map(|info| {
+                    let acquisitions = info.weight.round() as u32;
+                    view! {
+                        <div
+                            class="tooltip"
+                            style:left=format!("{}px", info.x)
+                            style:top=format!("{}px", info.y)
+                        >
+                            <div>{format!("{:.4}, {:.4}", info.lat, info.lon)}</div>
+                            <div>
+                                {format!(
+                                    "{acquisitions} acquisition{}",
+                                    if acquisitions == 1 { "" } else { "s" },
+                                )}
+                            </div>
+                        </div>
+                    }
+                })
+        }}
+    }
+}
@@ -1,15 +1,33 @@
+use std::ops::RangeInclusive;
+
 use chrono::naive::NaiveDate;
-use leptos::wasm_bindgen::JsCast as _;
+use geo::MultiPolygon;
 use leptos::{html, prelude::*};
+use strum::IntoEnumIterator as _;
 use types::Filter;
 
+use crate::colormap::{BundledColormap, CUSTOM_COLORMAP, Colormap};
+use crate::ingest::cache;
+use crate::mission::{Mission, mission_without_products, parse_relative_orbits};
 use crate::types::ExpansionSignal;
-use crate::types::{self, DateRange, ReadySignal};
+use crate::types::{
+    self, AoiFile, AoiSignal, ColormapSignal, DateRange, ExportFraming, ExportOptions,
+    ExportOptionsSignal, ExportWidthCapSignal, GranuleFormat, ImageFormat, LoadState,
+    LoadStateSignal, ReadySignal,
+};
+use crate::ui::checkbox_group::{CheckboxGroup, checked_options};
 use crate::ui::expansion_button::ExpansionButton;
+use crate::ui::popup::escape_html;
 
 #[component]
 pub fn UserInterface(
     set_filter: WriteSignal<Filter>,
+    set_aoi: WriteSignal<Option<MultiPolygon>>,
+    set_aoi_file: WriteSignal<Option<AoiFile>>,
+    set_export_granules: WriteSignal<Option<GranuleFormat>>,
+    set_generate_img: WriteSignal<Option<ImageFormat>>,
+    set_export_options: WriteSignal<ExportOptions>,
+    set_colormap: WriteSignal<Colormap>,
     set_title: WriteSignal<String>,
     set_body: WriteSignal<String>,
 ) -> impl IntoView {
@@ -19,6 +37,43 @@ pub fn UserInterface(
     let ReadySignal(ready) =
         use_context::<ReadySignal>().expect("Failed to get ready read signal from context in UI");
 
+    let LoadStateSignal(load_state) = use_context::<LoadStateSignal>()
+        .expect("Failed to get load state read signal from context in UI");
+
+    let AoiSignal(aoi) =
+        use_context::<AoiSignal>().expect("Failed to get AOI read signal from context in UI");
+
+    let ExportOptionsSignal(export_options) = use_context::<ExportOptionsSignal>()
+        .expect("Failed to get export options read signal from context in UI");
+
+    let ExportWidthCapSignal(export_width_cap) = use_context::<ExportWidthCapSignal>()
+        .expect("Failed to get export width cap read signal from context in UI");
+
+    let ColormapSignal(colormap) = use_context::<ColormapSignal>()
+        .expect("Failed to get colormap read signal from context in UI");
+
+    // An area of interest is applied as soon as it is drawn, the rest of the filter is unchanged
+    Effect::watch(
+        move || aoi.get(),
+        move |aoi, _, _| {
+            let mut new_filter = filter.get_untracked();
+            new_filter.aoi.clone_from(aoi);
+            set_filter(new_filter);
+        },
+        false,
+    );
+
+    // A width the GPU can't render is lowered to the widest one it can once its limits are known
+    Effect::watch(
+        move || export_width_cap.get(),
+        move |cap, _, _| {
+            if export_options.get_untracked().width > *cap {
+                set_export_options.update(|x| x.width = *cap);
+            }
+        },
+        false,
+    );
+
     let (expanded, set_expanded) = signal(true);
     provide_context(ExpansionSignal(expanded));
 
@@ -41,6 +96,9 @@ pub fn UserInterface(
     );
     let end_date_element: NodeRef<html::Input> = NodeRef::new();
 
+    let orbit_direction_element: NodeRef<html::Select> = NodeRef::new();
+    let relative_orbit_element: NodeRef<html::Input> = NodeRef::new();
+
     let max_date = chrono::Utc::now()
         .date_naive()
         .format("%Y-%m-%d")
@@ -50,73 +108,49 @@ pub fn UserInterface(
         .format("%Y-%m-%d")
         .to_string();
 
-    let doc = document();
+    // Checkboxes start out matching the filter the app was started with
+    let initial = filter.get_untracked();
+    let initial_export = export_options.get_untracked();
 
     // Run when an element of the UI changes, updates the filter signal
     let on_update = move |_| {
-        let mut product_type = Vec::new();
-
-        // If there is a checked button in granule_type append its value to the filter_string
-        if let Ok(nodes) = doc.query_selector_all("input[name=granule_type]:checked") {
-            for i in 0..nodes.length() {
-                let val = nodes
-                    .get(i)
-                    .expect("Failed to get node in on_submit")
-                    .dyn_into::<web_sys::Element>()
-                    .expect("Failed to cast Node to element")
-                    .get_attribute("value")
-                    .expect("Failed to get value attribute")
-                    .parse::<u32>()
-                    .expect("Failed to parse u32 from val");
-
-                match val {
-                    0 => product_type.push(types::ProductTypes::GroundRangeDetected),
-                    1 => product_type.push(types::ProductTypes::SingleLookComplex),
-                    2 => product_type.push(types::ProductTypes::Ocean),
-                    _ => (),
-                }
-            }
-        }
-
+        let product_type = checked_options::<types::ProductTypes>("product_type");
         if product_type.is_empty() {
-            // TO-DO: Add feedback to user about why the query is not valid
             set_title("Invalid Filter".to_string());
             set_body("Include at least 1 product type".to_string());
             return;
         }
 
-        let mut platform_type = Vec::new();
-
-        // If there is a checked button in sat_selection append its value to the filter_string
-        if let Ok(nodes) = doc.query_selector_all("input[name=sat_selection]:checked") {
-            for i in 0..nodes.length() {
-                let val = nodes
-                    .get(i)
-                    .expect("Failed to get node in on_submit")
-                    .dyn_into::<web_sys::Element>()
-                    .expect("Failed to cast Node to element")
-                    .get_attribute("value")
-                    .expect("Failed to get value attribute")
-                    .parse::<u32>()
-                    .expect("Failed to parse u32 from val");
-
-                match val {
-                    0 => platform_type.push(types::PlatformType::Sentinel1A),
-                    1 => platform_type.push(types::PlatformType::Sentinel1B),
-                    2 => platform_type.push(types::PlatformType::Sentinel1C),
-                    3 => platform_type.push(types::PlatformType::Sentinel1D),
-                    _ => (),
-                }
-            }
-        }
-
+        let platform_type = checked_options::<types::PlatformType>("platform_type");
         if platform_type.is_empty() {
-            // TO-DO: Add feedback to user about why the query is not valid
             set_title("Invalid Filter".to_string());
             set_body("Include at least 1 platform type".to_string());
             return;
         }
 
+        // Product types are read per mission, a mission with none of its own would show nothing
+        if let Some(mission) = mission_without_products(&product_type, &platform_type) {
+            set_title("Invalid Filter".to_string());
+            set_body(format!(
+                "Include at least 1 {mission} product type or deselect the {mission} platforms"
+            ));
+            return;
+        }
+
+        let beam_mode = checked_options::<types::BeamMode>("beam_mode");
+        if beam_mode.is_empty() {
+            set_title("Invalid Filter".to_string());
+            set_body("Include at least 1 beam mode".to_string());
+            return;
+        }
+
+        let polarization = checked_options::<types::Polarization>("polarization");
+        if polarization.is_empty() {
+            set_title("Invalid Filter".to_string());
+            set_body("Include at least 1 polarization".to_string());
+            return;
+        }
+
         // Gets the selected start and end dates
         let start_date_naive = NaiveDate::parse_from_str(
             &start_date_element
@@ -144,11 +178,167 @@ pub fn UserInterface(
             return;
         }
 
-        set_filter(types::Filter {
+        let orbit_direction = match orbit_direction_element
+            .read_untracked()
+            .as_ref()
+            .expect("Failed to read orbit direction element")
+            .value()
+            .as_str()
+        {
+            "ascending" => Some(types::OrbitDirection::Ascending),
+            "descending" => Some(types::OrbitDirection::Descending),
+            _ => None,
+        };
+
+        let mut filter = types::Filter {
             product_type,
             platform_type,
+            beam_mode,
+            polarization,
+            orbit_direction,
+            relative_orbit: Vec::new(),
+            aoi: aoi.get_untracked(),
             date_range: DateRange::new(start_date_naive, end_date_naive)
                 .expect("Failed to create DateRange"),
+        };
+        filter.relative_orbit = match parse_relative_orbits(
+            &relative_orbit_element
+                .read_untracked()
+                .as_ref()
+                .expect("Failed to read relative orbit element")
+                .value(),
+            &filter.missions(),
+        ) {
+            Ok(relative_orbit) => relative_orbit,
+            Err(err) => {
+                set_title("Invalid Filter".to_string());
+                set_body(escape_html(&err));
+                return;
+            }
+        };
+
+        set_filter(filter);
+    };
+
+    // Reads the chosen file and hands it to the canvas, which parses it into the area of interest
+    let on_import_aoi = move |ev: leptos::ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        // Lets the same file be imported again after the area of interest is cleared
+        input.set_value("");
+
+        leptos::task::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                Ok(buffer) => set_aoi_file(Some(AoiFile {
+                    name: file.name(),
+                    bytes: js_sys::Uint8Array::new(&buffer).to_vec(),
+                })),
+                Err(err) => {
+                    set_title("Invalid Area of Interest".to_string());
+                    set_body(escape_html(&format!(
+                        "Failed to read {}: {err:?}",
+                        file.name()
+                    )));
+                }
+            }
+        });
+    };
+
+    // Export options are applied as soon as they change, a number out of range is reported instead
+    let on_export_width = move |ev: leptos::ev::Event| match parse_export_option(
+        &event_target_value(&ev),
+        "Export Width",
+        &(*types::EXPORT_WIDTHS.start()..=export_width_cap.get_untracked()),
+    ) {
+        Ok(width) => set_export_options.update(|x| x.width = width),
+        Err(err) => {
+            set_title("Invalid Export Options".to_string());
+            set_body(escape_html(&err));
+        }
+    };
+
+    let on_export_dpi = move |ev: leptos::ev::Event| match parse_export_option(
+        &event_target_value(&ev),
+        "PNG DPI",
+        &types::EXPORT_DPIS,
+    ) {
+        Ok(dpi) => set_export_options.update(|x| x.dpi = dpi),
+        Err(err) => {
+            set_title("Invalid Export Options".to_string());
+            set_body(escape_html(&err));
+        }
+    };
+
+    let on_export_framing = move |ev: leptos::ev::Event| {
+        let value = event_target_value(&ev);
+        if let Some(framing) = ExportFraming::iter().find(|x| x.to_string() == value) {
+            set_export_options.update(|x| x.framing = framing);
+        }
+    };
+
+    let on_colormap = move |ev: leptos::ev::Event| {
+        let value = event_target_value(&ev);
+        if let Some(bundled) = BundledColormap::iter().find(|x| x.to_string() == value) {
+            set_colormap(Colormap::bundled(bundled));
+        }
+    };
+
+    // Custom colormaps are read from the row through the middle of a png or from color stops
+    let on_import_colormap = move |ev: leptos::ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        input.set_value("");
+
+        leptos::task::spawn_local(async move {
+            let colormap = wasm_bindgen_futures::JsFuture::from(file.array_buffer())
+                .await
+                .map_err(|err| format!("Failed to read {}: {err:?}", file.name()))
+                .and_then(|buffer| Colormap::from_png(&js_sys::Uint8Array::new(&buffer).to_vec()));
+            match colormap {
+                Ok(colormap) => set_colormap(colormap),
+                Err(err) => {
+                    set_title("Invalid Colormap".to_string());
+                    set_body(escape_html(&err));
+                }
+            }
+        });
+    };
+
+    let on_colormap_stops = move |ev: leptos::ev::Event| {
+        let text = event_target_value(&ev);
+        if text.trim().is_empty() {
+            return;
+        }
+        match Colormap::from_stops(&text) {
+            Ok(colormap) => set_colormap(colormap),
+            Err(err) => {
+                set_title("Invalid Colormap".to_string());
+                set_body(escape_html(&err));
+            }
+        }
+    };
+
+    // Removes every cached parquet file, the popup reports how much space was freed
+    let on_clear_cache = move |_| {
+        leptos::task::spawn_local(async move {
+            let freed = cache::size().await.unwrap_or(0.0);
+            match cache::clear().await {
+                Ok(()) => {
+                    set_title("Cache Cleared".to_string());
+                    set_body(format!(
+                        "Freed {:.1} MB, data will be downloaded again the next time it is needed",
+                        freed / (1024.0 * 1024.0)
+                    ));
+                }
+                Err(err) => {
+                    set_title(err.title().to_string());
+                    set_body(escape_html(&err.to_string()));
+                }
+            }
         });
     };
 
@@ -161,96 +351,26 @@ pub fn UserInterface(
             <Show when=move || { expanded() }>
                 <form id="form">
                     <div id="checkboxes">
-                        <div id="product_types">
-                            <p>Products</p>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="grd"
-                                name="granule_type"
-                                value=0
-                                checked
-                            />
-                            <label class="text" for="grd">
-                                "GRD"
-                            </label>
-                            <br/>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="slc"
-                                name="granule_type"
-                                value=1
-                                checked
-                            />
-                            <label class="text" for="slc">
-                                "SLC"
-                            </label>
-                            <br/>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="ocn"
-                                name="granule_type"
-                                value=2
-                                checked
-                            />
-                            <label class="text" for="ocn">
-                                "OCN"
-                            </label>
-                        </div>
-
-                        <div id="platform_types">
-                            <p>Platforms</p>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="s1-a"
-                                name="sat_selection"
-                                value=0
-                                checked
-                            />
-                            <label class="text" for="s1-a">
-                                "S1A"
-                            </label>
-                            <br/>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="s1-b"
-                                name="sat_selection"
-                                value=1
-                                checked
-                            />
-                            <label class="text" for="s1-b">
-                                "S1B"
-                            </label>
-                            <br/>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="s1-c"
-                                name="sat_selection"
-                                value=2
-                                checked
-                            />
-                            <label class="text" for="s1-c">
-                                "S1C"
-                            </label>
-                            <br/>
-                            <input
-                                class="checkbox"
-                                type="checkbox"
-                                id="s1-d"
-                                name="sat_selection"
-                                value=3
-                                checked
-                            />
-                            <label class="text" for="s1-d">
-                                "S1D"
-                            </label>
-                            <br/>
-                        </div>
+                        <CheckboxGroup
+                            title="Products"
+                            name="product_type"
+                            selected=initial.product_type.clone()
+                        />
+                        <CheckboxGroup
+                            title="Platforms"
+                            name="platform_type"
+                            selected=initial.platform_type.clone()
+                        />
+                        <CheckboxGroup
+                            title="Beam Modes"
+                            name="beam_mode"
+                            selected=initial.beam_mode.clone()
+                        />
+                        <CheckboxGroup
+                            title="Polarizations"
+                            name="polarization"
+                            selected=initial.polarization.clone()
+                        />
                     </div>
 
                     <div id="date_range">
@@ -289,27 +409,330 @@ pub fn UserInterface(
                                     />
                                 </td>
                             </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="orbit_direction">
+                                        Orbit Direction
+                                    </label>
+                                </td>
+                                <td>
+                                    <select
+                                        id="orbit_direction"
+                                        class="datepicker"
+                                        node_ref=orbit_direction_element
+                                    >
+                                        <option value="both" selected>
+                                            "Both"
+                                        </option>
+                                        <option value="ascending">"Ascending"</option>
+                                        <option value="descending">"Descending"</option>
+                                    </select>
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="relative_orbit">
+                                        Relative Orbits
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="text"
+                                        id="relative_orbit"
+                                        class="datepicker"
+                                        title=Mission::iter()
+                                            .map(|mission| {
+                                                format!("{mission}: 1-{}", mission.relative_orbits())
+                                            })
+                                            .collect::<Vec<String>>()
+                                            .join(", ")
+                                        placeholder="All, ie. 12, 40-45"
+                                        node_ref=relative_orbit_element
+                                    />
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text">Area of Interest</label>
+                                </td>
+                                <td>
+                                    <Show
+                                        when=move || aoi.with(Option::is_some)
+                                        fallback=|| {
+                                            view! {
+                                                <span
+                                                    class="text"
+                                                    title="Shift + drag draws a box, Alt + click adds the corners of a polygon, Escape clears it"
+                                                >
+                                                    "Globe, Shift + drag to draw"
+                                                </span>
+                                            }
+                                        }
+                                    >
+                                        <input
+                                            type="button"
+                                            value="Clear"
+                                            class="button"
+                                            on:click=move |_| set_aoi(None)
+                                        />
+                                    </Show>
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="aoi_file">
+                                        Import AOI
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="file"
+                                        id="aoi_file"
+                                        class="datepicker"
+                                        title="GeoJSON, WKT or a zipped shapefile in longitude and latitude"
+                                        accept=".geojson,.json,.wkt,.txt,.zip"
+                                        on:change=on_import_aoi
+                                    />
+                                </td>
+                            </tr>
                         </table>
                     </div>
                     <div id="submit">
                         <input
                             type="button"
-                            value="Generate Heatmap!"
+                            // Submitting while loading supersedes the current load
+                            value=move || match load_state() {
+                                LoadState::Idle => "Generate Heatmap!".to_string(),
+                                state => format!("{state}..."),
+                            }
                             class="button"
-                            disabled=move || !ready()
+                            disabled=move || !ready() && load_state() == LoadState::Idle
                             on:click=on_update.clone()
                         />
                     </div>
+                    <div id="colormap">
+                        <table>
+                            <tr>
+                                <td>
+                                    <label class="text" for="colormap_select">
+                                        Colormap
+                                    </label>
+                                </td>
+                                <td>
+                                    <select
+                                        id="colormap_select"
+                                        class="datepicker"
+                                        prop:value=move || colormap.with(|x| x.name.clone())
+                                        on:change=on_colormap
+                                    >
+                                        {BundledColormap::iter()
+                                            .map(|bundled| {
+                                                view! {
+                                                    <option value=bundled.to_string()>
+                                                        {bundled.to_string()}
+                                                    </option>
+                                                }
+                                            })
+                                            .collect_view()}
+                                        <option value=CUSTOM_COLORMAP disabled=true>
+                                            {CUSTOM_COLORMAP}
+                                        </option>
+                                    </select>
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="colormap_file">
+                                        Import Colormap
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="file"
+                                        id="colormap_file"
+                                        class="datepicker"
+                                        title="A png of the colormap from left to right, the row through its middle is used"
+                                        accept=".png"
+                                        on:change=on_import_colormap
+                                    />
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="colormap_stops">
+                                        Color Stops
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="text"
+                                        id="colormap_stops"
+                                        class="datepicker"
+                                        title="Evenly spaced colors the colormap blends between"
+                                        placeholder="ie. #000000, #ff8000, #ffff00"
+                                        on:change=on_colormap_stops
+                                    />
+                                </td>
+                            </tr>
+                        </table>
+                    </div>
+                    <div id="export_options">
+                        <table>
+                            <tr>
+                                <td>
+                                    <label class="text" for="export_width">
+                                        {move || {
+                                            format!("Export Width (max {})", export_width_cap.get())
+                                        }}
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="number"
+                                        id="export_width"
+                                        class="datepicker"
+                                        title="Width of the map in pixels, its height follows from the framing and is capped by the GPU"
+                                        min=types::EXPORT_WIDTHS.start().to_string()
+                                        max=move || export_width_cap.get().to_string()
+                                        prop:value=move || export_options.get().width.to_string()
+                                        on:change=on_export_width
+                                    />
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="export_dpi">
+                                        PNG DPI
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="number"
+                                        id="export_dpi"
+                                        class="datepicker"
+                                        title="Resolution the png is printed at, the legend and caption are sized to match"
+                                        min=types::EXPORT_DPIS.start().to_string()
+                                        max=types::EXPORT_DPIS.end().to_string()
+                                        prop:value=initial_export.dpi.to_string()
+                                        on:change=on_export_dpi
+                                    />
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="export_framing">
+                                        Export Framing
+                                    </label>
+                                </td>
+                                <td>
+                                    <select
+                                        id="export_framing"
+                                        class="datepicker"
+                                        on:change=on_export_framing
+                                    >
+                                        {ExportFraming::iter()
+                                            .map(|framing| {
+                                                view! {
+                                                    <option
+                                                        value=framing.to_string()
+                                                        selected=framing == initial_export.framing
+                                                    >
+                                                        {framing.to_string()}
+                                                    </option>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </select>
+                                </td>
+                            </tr>
+                            <tr>
+                                <td>
+                                    <label class="text" for="export_transparent">
+                                        Transparent PNG
+                                    </label>
+                                </td>
+                                <td>
+                                    <input
+                                        type="checkbox"
+                                        id="export_transparent"
+                                        class="checkbox"
+                                        prop:checked=initial_export.transparent
+                                        on:change=move |ev| {
+                                            set_export_options
+                                                .update(|x| x.transparent = event_target_checked(&ev));
+                                        }
+                                    />
+                                </td>
+                            </tr>
+                        </table>
+                    </div>
+                    <div id="export">
+                        <input
+                            type="button"
+                            value="Export CSV"
+                            class="button"
+                            title="Lists the acquisitions in the area of interest, or in view when there is none"
+                            disabled=move || !ready()
+                            on:click=move |_| set_export_granules(Some(GranuleFormat::Csv))
+                        />
+                        <input
+                            type="button"
+                            value="Export GeoJSON"
+                            class="button"
+                            title="Lists the acquisitions in the area of interest, or in view when there is none"
+                            disabled=move || !ready()
+                            on:click=move |_| set_export_granules(Some(GranuleFormat::GeoJson))
+                        />
+                        <input
+                            type="button"
+                            value="Export PNG"
+                            class="button"
+                            title="Renders the map with the export options above, with a legend and a caption describing the filter"
+                            disabled=move || !ready()
+                            on:click=move |_| set_generate_img(Some(ImageFormat::Png))
+                        />
+                        <input
+                            type="button"
+                            value="Export GeoTIFF"
+                            class="button"
+                            title="Writes the number of acquisitions at each pixel of the map as a GeoTIFF in longitude and latitude"
+                            disabled=move || !ready()
+                            on:click=move |_| set_generate_img(Some(ImageFormat::GeoTiff))
+                        />
+                        <input
+                            type="button"
+                            value="Export SVG"
+                            class="button"
+                            title="Draws the map, legend and caption as vectors that can be scaled and edited for print"
+                            disabled=move || !ready()
+                            on:click=move |_| set_generate_img(Some(ImageFormat::Svg))
+                        />
+                    </div>
+                    <div id="cache">
+                        <input
+                            type="button"
+                            value="Clear Cache"
+                            class="button"
+                            on:click=on_clear_cache
+                        />
+                    </div>
                 </form>
             </Show>
-            // <div>
-            //     <a
-            //         class="button"
-            //         on:click= move |_| {set_generate_img(true)}
-            //     >
-            //         Export to PNG
-            //     </a>
-            // </div>
         </div>
     }
 }
+
+/// Parse a whole number an export option is set to, it must be within the passed range
+fn parse_export_option(text: &str, name: &str, range: &RangeInclusive<u32>) -> Result<u32, String> {
+    text.trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|x| range.contains(x))
+        .ok_or_else(|| {
+            format!(
+                "{name} must be a whole number from {} to {}",
+                range.start(),
+                range.end()
+            )
+        })
+}